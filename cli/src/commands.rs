@@ -0,0 +1,333 @@
+use rand::rngs::OsRng;
+use serde_json::json;
+use solana_sdk::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use zerosol_client::{
+    account_view::fetch_state,
+    derivation::{derive_wallet, generate_mnemonic, mnemonic_to_seed},
+    keystore::BalanceHint,
+    to_hex, AccountView, DerivationPath, KeystoreFile, RingMember, Wallet,
+};
+use zerosol_solana::{
+    instruction,
+    state::{find_token_authority_address, GlobalState, PendingAccount, ZerosolAccount},
+};
+
+use crate::{
+    keystore::{parse_hex32, registered_accounts, Pool},
+    Context, Result,
+};
+
+/// Ring member for the accounts `FundToKey` derives from `public_key`
+fn key_member(program_id: &Pubkey, public_key: [u8; 32]) -> RingMember {
+    RingMember {
+        public_key,
+        zerosol_account: ZerosolAccount::find_address(program_id, &public_key).0,
+        pending_account: PendingAccount::find_address(program_id, &public_key).0,
+    }
+}
+
+/// Our own accounts: those `register` created, or the key-derived ones
+fn own_accounts(file: &KeystoreFile, pool: &Pool) -> Result<(Pubkey, Pubkey)> {
+    Ok(match registered_accounts(file)? {
+        Some(accounts) => accounts,
+        None => {
+            let member = key_member(&pool.program_id, file.public_key()?);
+            (member.zerosol_account, member.pending_account)
+        }
+    })
+}
+
+async fn own_view(ctx: &mut Context, file: &KeystoreFile, pool: &Pool) -> Result<AccountView> {
+    let (account, pending) = own_accounts(file, pool)?;
+    Ok(AccountView::fetch(&mut ctx.rpc, &pool.global_state, account, pending).await?)
+}
+
+pub fn keygen(
+    ctx: &Context,
+    force: bool,
+    mnemonic: bool,
+    restore: Option<String>,
+    passphrase: &str,
+    index: u32,
+) -> Result<()> {
+    if ctx.keystore.has_key(&ctx.name) && !force {
+        return Err(format!("key `{}` already exists; pass --force to replace it", ctx.name).into());
+    }
+
+    let mut output = json!({ "name": ctx.name });
+    let mut text = String::new();
+    let wallet = if mnemonic || restore.is_some() {
+        let path = DerivationPath::new(ctx.keystore.load_pool()?.global_state, index);
+        let phrase = match restore {
+            Some(phrase) => phrase,
+            None => {
+                let phrase = generate_mnemonic(&mut OsRng).to_string();
+                output["mnemonic"] = json!(phrase);
+                text.push_str(&format!("Mnemonic (write it down, it is not stored): {}\n", phrase));
+                phrase
+            }
+        };
+        output["path"] = json!(path.to_string());
+        derive_wallet(&mnemonic_to_seed(&phrase, passphrase)?, &path)
+    } else {
+        Wallet::generate(&mut OsRng)
+    };
+    let public_key = to_hex(&wallet.public_key());
+    let mut file = KeystoreFile::encrypt(&wallet, ctx.password()?, &mut OsRng)?;
+    if let Ok(pool) = ctx.keystore.load_pool() {
+        file.pool = Some(pool.global_state.to_string());
+        file.mint = Some(pool.token_mint.to_string());
+    }
+    ctx.keystore.save_key(&ctx.name, &file)?;
+    output["public_key"] = json!(public_key);
+    text.push_str(&format!("Public key: {}", public_key));
+    ctx.print(output, &text);
+    Ok(())
+}
+
+pub fn change_password(ctx: &Context, new_password: &str) -> Result<()> {
+    let mut file = ctx.keystore.load_key(&ctx.name)?;
+    file.change_password(ctx.password()?, new_password, &mut OsRng)?;
+    ctx.keystore.save_key(&ctx.name, &file)?;
+    ctx.print(json!({ "name": ctx.name }), &format!("Changed the password of `{}`", ctx.name));
+    Ok(())
+}
+
+pub async fn register(ctx: &Context) -> Result<()> {
+    let (wallet, mut file) = ctx.keystore.load_wallet(&ctx.name, ctx.password()?)?;
+    if registered_accounts(&file)?.is_some() {
+        return Err(format!("key `{}` is already registered", ctx.name).into());
+    }
+    let pool = ctx.keystore.load_pool()?;
+    let zerosol_account = Keypair::new();
+    let pending_account = Keypair::new();
+
+    let register_ix = wallet.register(
+        &pool.program_id,
+        &ctx.payer.pubkey(),
+        &zerosol_account.pubkey(),
+        &pending_account.pubkey(),
+    );
+    let result = ctx.submit(&[register_ix], &[&zerosol_account, &pending_account]).await?;
+
+    file.registered = true;
+    file.zerosol_account = Some(zerosol_account.pubkey().to_string());
+    file.pending_account = Some(pending_account.pubkey().to_string());
+    ctx.keystore.save_key(&ctx.name, &file)?;
+
+    ctx.print_submitted(
+        json!({
+            "zerosol_account": zerosol_account.pubkey().to_string(),
+            "pending_account": pending_account.pubkey().to_string(),
+        }),
+        &format!("Registered account {}", zerosol_account.pubkey()),
+        vec![result],
+    );
+    Ok(())
+}
+
+pub async fn fund(ctx: &Context, amount: u64, from: &Pubkey, to: Option<&str>) -> Result<()> {
+    let pool = ctx.keystore.load_pool()?;
+    let (fund_ix, account) = match to {
+        Some(to) => {
+            let public_key = parse_hex32(to)?;
+            let fund_ix = instruction::fund_to_key(
+                &pool.program_id,
+                &ctx.payer.pubkey(),
+                from,
+                &pool.program_token_account,
+                public_key,
+                amount,
+            );
+            (fund_ix, key_member(&pool.program_id, public_key).zerosol_account)
+        }
+        None => {
+            let (account, pending) = own_accounts(&ctx.keystore.load_key(&ctx.name)?, &pool)?;
+            let fund_ix = instruction::fund(
+                &pool.program_id,
+                &ctx.payer.pubkey(),
+                &account,
+                &pending,
+                from,
+                &pool.program_token_account,
+                amount,
+            );
+            (fund_ix, account)
+        }
+    };
+
+    let result = ctx.submit(&[fund_ix], &[]).await?;
+    ctx.print_submitted(
+        json!({ "account": account.to_string(), "amount": amount }),
+        &format!("Funded {} with {}", account, amount),
+        vec![result],
+    );
+    Ok(())
+}
+
+pub async fn balance(ctx: &mut Context) -> Result<()> {
+    let (wallet, mut file) = ctx.keystore.load_wallet(&ctx.name, ctx.password()?)?;
+    let pool = ctx.keystore.load_pool()?;
+    let view = own_view(ctx, &file, &pool).await?;
+    let balance = view.balance(&wallet, &ctx.keystore.table()?)?;
+    file.balance_hint = Some(BalanceHint {
+        spendable: balance.spendable,
+        pending: balance.pending,
+        epoch: view.current_epoch,
+    });
+    ctx.keystore.save_key(&ctx.name, &file)?;
+    ctx.print(
+        json!({
+            "account": view.address.to_string(),
+            "epoch": view.current_epoch,
+            "spendable": balance.spendable,
+            "pending": balance.pending,
+        }),
+        &format!("Spendable: {}\nPending:   {}", balance.spendable, balance.pending),
+    );
+    Ok(())
+}
+
+pub async fn rollover(ctx: &Context) -> Result<()> {
+    let pool = ctx.keystore.load_pool()?;
+    let (account, pending) = own_accounts(&ctx.keystore.load_key(&ctx.name)?, &pool)?;
+    let roll_over_ix =
+        instruction::roll_over(&pool.program_id, &ctx.payer.pubkey(), &account, &pending);
+    let result = ctx.submit(&[roll_over_ix], &[]).await?;
+    ctx.print_submitted(
+        json!({ "account": account.to_string() }),
+        &format!("Rolled over {}", account),
+        vec![result],
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn pool_init(
+    ctx: &Context,
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    epoch_length: u64,
+    fee: u64,
+    epoch_source: u8,
+    epoch_offset: u64,
+) -> Result<()> {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    let program_token_account = Keypair::new();
+    let (token_authority, _) = find_token_authority_address(program_id);
+    let token_account_len = spl_token::state::Account::LEN;
+    let rent = if ctx.sign_only() {
+        solana_sdk::rent::Rent::default().minimum_balance(token_account_len)
+    } else {
+        ctx.rpc.get_minimum_balance_for_rent_exemption(token_account_len).await?
+    };
+
+    let instructions = [
+        instruction::initialize(
+            program_id,
+            &ctx.payer.pubkey(),
+            mint,
+            epoch_length,
+            fee,
+            epoch_source,
+            epoch_offset,
+        ),
+        system_instruction::create_account(
+            &ctx.payer.pubkey(),
+            &program_token_account.pubkey(),
+            rent,
+            token_account_len as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account3(
+            &spl_token::id(),
+            &program_token_account.pubkey(),
+            mint,
+            &token_authority,
+        )?,
+    ];
+    let result = ctx.submit(&instructions, &[&program_token_account]).await?;
+
+    let pool = Pool {
+        program_id: *program_id,
+        global_state,
+        token_mint: *mint,
+        program_token_account: program_token_account.pubkey(),
+    };
+    ctx.keystore.save_pool(&pool)?;
+    ctx.print_submitted(
+        json!({
+            "program_id": pool.program_id.to_string(),
+            "global_state": pool.global_state.to_string(),
+            "token_mint": pool.token_mint.to_string(),
+            "program_token_account": pool.program_token_account.to_string(),
+        }),
+        &format!(
+            "Global state:          {}\nProgram token account: {}",
+            pool.global_state, pool.program_token_account
+        ),
+        vec![result],
+    );
+    Ok(())
+}
+
+pub async fn pool_config(
+    ctx: &mut Context,
+    guardian: Option<Pubkey>,
+    pause: Option<(bool, u8)>,
+    burn_cap: Option<u64>,
+) -> Result<()> {
+    let pool = ctx.keystore.load_pool()?;
+    let signer = ctx.payer.pubkey();
+
+    let mut instructions = Vec::new();
+    if let Some(guardian) = guardian {
+        instructions.push(instruction::set_guardian(&pool.program_id, &signer, guardian));
+    }
+    if let Some((paused, pause_flags)) = pause {
+        instructions.push(instruction::set_pause(&pool.program_id, &signer, paused, pause_flags));
+    }
+    if let Some(burn_cap) = burn_cap {
+        instructions.push(instruction::set_withdrawal_cap(&pool.program_id, &signer, burn_cap));
+    }
+
+    if instructions.is_empty() {
+        let state: GlobalState = fetch_state(&mut ctx.rpc, &pool.global_state).await?;
+        ctx.print(
+            json!({
+                "authority": state.authority.to_string(),
+                "guardian": state.guardian.to_string(),
+                "token_mint": state.token_mint.to_string(),
+                "epoch_length": state.epoch_length,
+                "epoch_source": state.epoch_source,
+                "epoch_offset": state.epoch_offset,
+                "current_epoch": state.current_epoch,
+                "fee": state.fee,
+                "paused": state.paused,
+                "pause_flags": state.pause_flags,
+                "burn_cap_per_epoch": state.burn_cap_per_epoch,
+            }),
+            &format!(
+                "Authority:      {}\nGuardian:       {}\nEpoch length:   {}\nCurrent epoch:  {}\nFee:            {}\nPaused:         {} (flags {:#04x})\nBurn cap:       {}",
+                state.authority,
+                state.guardian,
+                state.epoch_length,
+                state.current_epoch,
+                state.fee,
+                state.paused,
+                state.pause_flags,
+                state.burn_cap_per_epoch,
+            ),
+        );
+        return Ok(());
+    }
+
+    let result = ctx.submit(&instructions, &[]).await?;
+    ctx.print_submitted(json!({}), "Updated pool configuration", vec![result]);
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use zerosol_client::{from_hex, KeystoreFile, Wallet};
+use zerosol_solana::client::balance::BabyStepTable;
+
+use crate::Result;
+
+/// Accounts `register` created for a key, if any; key-derived accounts are used otherwise
+pub fn registered_accounts(file: &KeystoreFile) -> Result<Option<(Pubkey, Pubkey)>> {
+    match (&file.zerosol_account, &file.pending_account) {
+        (Some(account), Some(pending)) => Ok(Some((account.parse()?, pending.parse()?))),
+        _ => Ok(None),
+    }
+}
+
+/// The pool the CLI talks to, written by `pool init`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PoolConfig {
+    pub program_id: String,
+    pub global_state: String,
+    pub token_mint: String,
+    pub program_token_account: String,
+}
+
+/// `PoolConfig` with parsed addresses
+pub struct Pool {
+    pub program_id: Pubkey,
+    pub global_state: Pubkey,
+    pub token_mint: Pubkey,
+    pub program_token_account: Pubkey,
+}
+
+impl PoolConfig {
+    pub fn parse(&self) -> Result<Pool> {
+        Ok(Pool {
+            program_id: self.program_id.parse()?,
+            global_state: self.global_state.parse()?,
+            token_mint: self.token_mint.parse()?,
+            program_token_account: self.program_token_account.parse()?,
+        })
+    }
+}
+
+/// Directory holding keys, the pool config and the baby-step table
+///
+/// Layout: `keys/<name>.json` (see `zerosol_client::keystore`), `pool.json`
+/// and `bsgs-<bits>.bin`.
+pub struct Keystore {
+    dir: PathBuf,
+}
+
+impl Keystore {
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(dir.join("keys"))?;
+        Ok(Self { dir })
+    }
+
+    fn key_path(&self, name: &str) -> PathBuf {
+        self.dir.join("keys").join(format!("{}.json", name))
+    }
+
+    pub fn has_key(&self, name: &str) -> bool {
+        self.key_path(name).exists()
+    }
+
+    pub fn save_key(&self, name: &str, file: &KeystoreFile) -> Result<()> {
+        Ok(file.save(self.key_path(name))?)
+    }
+
+    pub fn load_key(&self, name: &str) -> Result<KeystoreFile> {
+        let path = self.key_path(name);
+        KeystoreFile::load(&path)
+            .map_err(|e| format!("cannot read key `{}` ({}): {}", name, path.display(), e).into())
+    }
+
+    pub fn load_wallet(&self, name: &str, password: &str) -> Result<(Wallet, KeystoreFile)> {
+        let file = self.load_key(name)?;
+        let wallet = file.decrypt(password)?;
+        Ok((wallet, file))
+    }
+
+    pub fn save_pool(&self, pool: &Pool) -> Result<()> {
+        let pool = PoolConfig {
+            program_id: pool.program_id.to_string(),
+            global_state: pool.global_state.to_string(),
+            token_mint: pool.token_mint.to_string(),
+            program_token_account: pool.program_token_account.to_string(),
+        };
+        fs::write(self.dir.join("pool.json"), serde_json::to_vec_pretty(&pool)?)?;
+        Ok(())
+    }
+
+    pub fn load_pool(&self) -> Result<Pool> {
+        let data = fs::read(self.dir.join("pool.json"))
+            .map_err(|_| "no pool configured; run `gargantua pool init` first")?;
+        serde_json::from_slice::<PoolConfig>(&data)?.parse()
+    }
+
+    /// Baby-step table for decrypting balances, built on first use
+    pub fn table(&self) -> Result<BabyStepTable> {
+        let bits = BabyStepTable::DEFAULT_BITS;
+        let path = self.dir.join(format!("bsgs-{}.bin", bits));
+        Ok(BabyStepTable::load_or_create(path, bits)?)
+    }
+}
+
+pub fn parse_hex32(s: &str) -> Result<[u8; 32]> {
+    let s = s.trim();
+    from_hex(s)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("expected 32 hex-encoded bytes, got `{}`", s).into())
+}
@@ -0,0 +1,295 @@
+use std::path::PathBuf;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+
+mod commands;
+mod keystore;
+
+use keystore::Keystore;
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Command line client for a Gargantua pool
+#[derive(Parser)]
+#[command(name = "gargantua", version)]
+struct Cli {
+    /// RPC endpoint
+    #[arg(long, global = true, env = "GARGANTUA_URL", default_value = "http://localhost:8899")]
+    url: String,
+
+    /// Fee payer keypair, also the token owner for `fund`
+    #[arg(long, global = true, env = "GARGANTUA_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    /// Directory holding zerosol keys and the pool config
+    #[arg(long, global = true, env = "GARGANTUA_KEYSTORE")]
+    keystore: Option<PathBuf>,
+
+    /// Name of the zerosol key to use
+    #[arg(long, global = true, default_value = "default")]
+    name: String,
+
+    /// Password of the zerosol key
+    #[arg(long, global = true, env = "GARGANTUA_PASSWORD", hide_env_values = true)]
+    password: Option<String>,
+
+    /// Print machine-readable JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Sign transactions and print them base64-encoded instead of sending them
+    #[arg(long, global = true, requires = "blockhash")]
+    sign_only: bool,
+
+    /// Recent blockhash to sign with in `--sign-only` mode
+    #[arg(long, global = true)]
+    blockhash: Option<Hash>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a zerosol key and store it in the keystore
+    Keygen {
+        /// Overwrite an existing key with the same name
+        #[arg(long)]
+        force: bool,
+        /// Derive the key from a new mnemonic, printed once, for the configured pool
+        #[arg(long, conflicts_with = "restore")]
+        mnemonic: bool,
+        /// Restore the key from an existing mnemonic; key-derived accounts are found again
+        #[arg(long, value_name = "PHRASE")]
+        restore: Option<String>,
+        /// BIP-39 passphrase protecting the mnemonic
+        #[arg(long, env = "GARGANTUA_PASSPHRASE", default_value = "")]
+        passphrase: String,
+        /// Index of the key within the pool
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+    },
+    /// Re-encrypt the key under a new password
+    ChangePassword {
+        #[arg(long, env = "GARGANTUA_NEW_PASSWORD", hide_env_values = true)]
+        new_password: String,
+    },
+    /// Register fresh accounts for the key
+    Register,
+    /// Deposit tokens into the key's account, or into another key's account
+    Fund {
+        #[arg(long)]
+        amount: u64,
+        /// Token account to pay from, owned by the fee payer
+        #[arg(long)]
+        from: Pubkey,
+        /// Hex public key to fund instead of our own account
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Decrypt the spendable and pending balance
+    Balance,
+    /// Merge the pending balance into the account
+    Rollover,
+    /// Pool administration
+    #[command(subcommand)]
+    Pool(PoolCommand),
+}
+
+#[derive(Subcommand)]
+enum PoolCommand {
+    /// Create a pool and its token account, and save it as the CLI's pool
+    Init {
+        #[arg(long)]
+        program_id: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long, default_value_t = 3600)]
+        epoch_length: u64,
+        /// Minimum relayer fee
+        #[arg(long, default_value_t = 0)]
+        fee: u64,
+        #[arg(long, value_enum, default_value_t = EpochSource::Timestamp)]
+        epoch_source: EpochSource,
+        #[arg(long, default_value_t = 0)]
+        epoch_offset: u64,
+    },
+    /// Show the pool configuration, or change it when options are given
+    Config {
+        #[arg(long)]
+        guardian: Option<Pubkey>,
+        /// Pause every operation
+        #[arg(long, conflicts_with = "resume")]
+        pause: bool,
+        /// Lift the global pause and every per-operation pause (authority only)
+        #[arg(long, conflicts_with = "pause_flags")]
+        resume: bool,
+        /// PAUSE_* bits to set without pausing globally
+        #[arg(long)]
+        pause_flags: Option<u8>,
+        /// Tokens that may be burned per epoch, 0 for no cap; the guardian may only lower it
+        #[arg(long)]
+        burn_cap: Option<u64>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum EpochSource {
+    Timestamp,
+    Slot,
+}
+
+/// State shared by every command
+pub struct Context {
+    pub rpc: RpcClient,
+    pub payer: Keypair,
+    pub keystore: Keystore,
+    pub name: String,
+    pub password: Option<String>,
+    json: bool,
+    sign_only: bool,
+    blockhash: Option<Hash>,
+}
+
+impl Context {
+    pub fn sign_only(&self) -> bool {
+        self.sign_only
+    }
+
+    pub fn password(&self) -> Result<&str> {
+        Ok(self
+            .password
+            .as_deref()
+            .ok_or("the key is encrypted; set GARGANTUA_PASSWORD or pass --password")?)
+    }
+
+    /// Sign a transaction paid by the fee payer, then send it and return its
+    /// signature, or return it base64-encoded in `--sign-only` mode
+    pub async fn submit(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<String> {
+        let blockhash = match self.blockhash {
+            Some(blockhash) => blockhash,
+            None => self.rpc.get_latest_blockhash().await?,
+        };
+        let mut keypairs = vec![&self.payer];
+        keypairs.extend_from_slice(signers);
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&self.payer.pubkey()), &keypairs, blockhash);
+
+        if self.sign_only {
+            Ok(STANDARD.encode(bincode::serialize(&transaction)?))
+        } else {
+            Ok(self.rpc.send_and_confirm_transaction(&transaction).await?.to_string())
+        }
+    }
+
+    /// Print `value` in `--json` mode, `text` otherwise
+    pub fn print(&self, value: Value, text: &str) {
+        if self.json {
+            println!("{}", value);
+        } else {
+            println!("{}", text);
+        }
+    }
+
+    /// Print the results of `submit` along with `value`
+    pub fn print_submitted(&self, mut value: Value, text: &str, results: Vec<String>) {
+        let key = if self.sign_only { "transactions" } else { "signatures" };
+        value[key] = json!(results);
+        let mut text = text.to_string();
+        for result in &results {
+            text.push('\n');
+            text.push_str(result);
+        }
+        self.print(value, &text);
+    }
+}
+
+fn default_dir(parts: &[&str]) -> PathBuf {
+    let mut path = std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+    path.extend(parts);
+    path
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let keystore = Keystore::open(cli.keystore.unwrap_or_else(|| default_dir(&[".config", "gargantua"])))?;
+    let keypair_path = cli
+        .keypair
+        .unwrap_or_else(|| default_dir(&[".config", "solana", "id.json"]));
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|e| format!("cannot read keypair {}: {}", keypair_path.display(), e))?;
+
+    let mut ctx = Context {
+        rpc: RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed()),
+        payer,
+        keystore,
+        name: cli.name,
+        password: cli.password,
+        json: cli.json,
+        sign_only: cli.sign_only,
+        blockhash: cli.blockhash,
+    };
+
+    match cli.command {
+        Command::Keygen {
+            force,
+            mnemonic,
+            restore,
+            passphrase,
+            index,
+        } => commands::keygen(&ctx, force, mnemonic, restore, &passphrase, index),
+        Command::ChangePassword { new_password } => commands::change_password(&ctx, &new_password),
+        Command::Register => commands::register(&ctx).await,
+        Command::Fund { amount, from, to } => commands::fund(&ctx, amount, &from, to.as_deref()).await,
+        Command::Balance => commands::balance(&mut ctx).await,
+        Command::Rollover => commands::rollover(&ctx).await,
+        Command::Pool(PoolCommand::Init {
+            program_id,
+            mint,
+            epoch_length,
+            fee,
+            epoch_source,
+            epoch_offset,
+        }) => {
+            let epoch_source = match epoch_source {
+                EpochSource::Timestamp => zerosol_solana::state::EPOCH_SOURCE_TIMESTAMP,
+                EpochSource::Slot => zerosol_solana::state::EPOCH_SOURCE_SLOT,
+            };
+            commands::pool_init(&ctx, &program_id, &mint, epoch_length, fee, epoch_source, epoch_offset).await
+        }
+        Command::Pool(PoolCommand::Config {
+            guardian,
+            pause,
+            resume,
+            pause_flags,
+            burn_cap,
+        }) => {
+            let pause = if pause {
+                Some((true, pause_flags.unwrap_or(0)))
+            } else if resume {
+                Some((false, 0))
+            } else {
+                pause_flags.map(|flags| (false, flags))
+            };
+            commands::pool_config(&mut ctx, guardian, pause, burn_cap).await
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run(Cli::parse()).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
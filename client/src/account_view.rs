@@ -0,0 +1,114 @@
+use borsh::BorshDeserialize;
+use solana_sdk::pubkey::Pubkey;
+use zerosol_solana::{
+    client::balance::{account_balance, AccountBalance, BabyStepTable},
+    state::{epoch_at, GlobalState, PendingAccount, ZerosolAccount},
+    utils::G1Point,
+};
+
+use crate::{connection::Connection, error::ClientError, wallet::Wallet};
+
+/// A ring member as the transfer instruction addresses it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RingMember {
+    pub public_key: [u8; 32],
+    pub zerosol_account: Pubkey,
+    pub pending_account: Pubkey,
+}
+
+/// Snapshot of a zerosol account, its pending account and the epoch
+#[derive(Clone, Debug)]
+pub struct AccountView {
+    pub address: Pubkey,
+    pub pending_address: Pubkey,
+    pub account: ZerosolAccount,
+    pub pending: PendingAccount,
+    pub current_epoch: u64,
+}
+
+/// Fetch and decode an account owned by the program
+pub async fn fetch_state<T: BorshDeserialize, C: Connection + ?Sized>(
+    connection: &mut C,
+    address: &Pubkey,
+) -> Result<T, ClientError> {
+    let data = connection
+        .get_account_data(address)
+        .await?
+        .ok_or(ClientError::AccountNotFound(*address))?;
+    T::deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccountData(*address))
+}
+
+/// The epoch the program computes for the current clock
+pub async fn current_epoch<C: Connection + ?Sized>(
+    connection: &mut C,
+    global_state: &Pubkey,
+) -> Result<u64, ClientError> {
+    let state: GlobalState = fetch_state(connection, global_state).await?;
+    let clock = connection.get_clock().await?;
+    Ok(epoch_at(&clock, &state))
+}
+
+impl AccountView {
+    pub async fn fetch<C: Connection + ?Sized>(
+        connection: &mut C,
+        global_state: &Pubkey,
+        address: Pubkey,
+        pending_address: Pubkey,
+    ) -> Result<Self, ClientError> {
+        let account = fetch_state(connection, &address).await?;
+        let pending = fetch_state(connection, &pending_address).await?;
+        let current_epoch = current_epoch(connection, global_state).await?;
+        Ok(Self {
+            address,
+            pending_address,
+            account,
+            pending,
+            current_epoch,
+        })
+    }
+
+    /// Fetch the accounts `FundToKey` derives for `public_key`
+    pub async fn fetch_for_key<C: Connection + ?Sized>(
+        connection: &mut C,
+        program_id: &Pubkey,
+        global_state: &Pubkey,
+        public_key: &[u8; 32],
+    ) -> Result<Self, ClientError> {
+        let (address, _) = ZerosolAccount::find_address(program_id, public_key);
+        let (pending_address, _) = PendingAccount::find_address(program_id, public_key);
+        Self::fetch(connection, global_state, address, pending_address).await
+    }
+
+    /// Decrypt the spendable and pending balances with `wallet`
+    pub fn balance(&self, wallet: &Wallet, table: &BabyStepTable) -> Result<AccountBalance, ClientError> {
+        Ok(account_balance(
+            table,
+            wallet.secret_key(),
+            &self.account,
+            &self.pending,
+            self.current_epoch,
+        )?)
+    }
+
+    /// Ciphertext `(CLn, CRn)` proofs in the current epoch are checked against,
+    /// with the pending balance merged in if the account still has to roll over
+    pub fn effective_ciphertext(&self) -> Result<([u8; 32], [u8; 32]), ClientError> {
+        let invalid = |_| ClientError::InvalidAccountData(self.address);
+        let left = G1Point::from_bytes(&self.account.commitment_left).map_err(invalid)?;
+        let right = G1Point::from_bytes(&self.account.commitment_right).map_err(invalid)?;
+        if self.account.last_rollover >= self.current_epoch {
+            return Ok((left.to_bytes(), right.to_bytes()));
+        }
+        let pending_left = self.pending.get_commitment_left().map_err(invalid)?;
+        let pending_right = self.pending.get_commitment_right().map_err(invalid)?;
+        Ok((left.add(&pending_left).to_bytes(), right.add(&pending_right).to_bytes()))
+    }
+
+    pub fn ring_member(&self) -> RingMember {
+        RingMember {
+            public_key: self.account.public_key,
+            zerosol_account: self.address,
+            pending_account: self.pending_address,
+        }
+    }
+}
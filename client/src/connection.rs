@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use solana_banks_client::BanksClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    account::from_account, clock::Clock, hash::Hash, pubkey::Pubkey, signature::Signature,
+    sysvar, transaction::Transaction,
+};
+
+use crate::error::ClientError;
+
+/// The cluster access the client needs
+///
+/// Implemented for the nonblocking `RpcClient`, an `Arc` of one, and for
+/// `BanksClient`, so the same code runs against a validator and inside
+/// `solana-program-test`.
+#[async_trait]
+pub trait Connection: Send {
+    /// Data of `address`, `None` if the account does not exist
+    async fn get_account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError>;
+
+    async fn get_clock(&mut self) -> Result<Clock, ClientError>;
+
+    async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError>;
+
+    /// Submit a signed transaction and wait for it to be confirmed
+    async fn send_transaction(&mut self, transaction: Transaction) -> Result<Signature, ClientError>;
+
+    /// Execute a signed transaction without committing it, failing with
+    /// `ClientError::Simulation` if it would fail
+    async fn simulate_transaction(&mut self, transaction: &Transaction) -> Result<(), ClientError>;
+}
+
+/// The method bodies only need `&RpcClient`, so the same impl serves an
+/// owned client and one shared behind an `Arc`
+macro_rules! impl_rpc_connection {
+    ($rpc:ty) => {
+        #[async_trait]
+        impl Connection for $rpc {
+            async fn get_account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+                let response = self.get_account_with_commitment(address, self.commitment()).await?;
+                Ok(response.value.map(|account| account.data))
+            }
+
+            async fn get_clock(&mut self) -> Result<Clock, ClientError> {
+                let account = self.get_account(&sysvar::clock::id()).await?;
+                from_account(&account).ok_or(ClientError::InvalidAccountData(sysvar::clock::id()))
+            }
+
+            async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError> {
+                Ok(RpcClient::get_latest_blockhash(self).await?)
+            }
+
+            async fn send_transaction(&mut self, transaction: Transaction) -> Result<Signature, ClientError> {
+                Ok(self.send_and_confirm_transaction(&transaction).await?)
+            }
+
+            async fn simulate_transaction(&mut self, transaction: &Transaction) -> Result<(), ClientError> {
+                let result = RpcClient::simulate_transaction(self, transaction).await?.value;
+                match result.err {
+                    Some(error) => Err(ClientError::Simulation {
+                        error: error.to_string(),
+                        logs: result.logs.unwrap_or_default(),
+                    }),
+                    None => Ok(()),
+                }
+            }
+        }
+    };
+}
+
+impl_rpc_connection!(RpcClient);
+impl_rpc_connection!(Arc<RpcClient>);
+
+#[async_trait]
+impl Connection for BanksClient {
+    async fn get_account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        Ok(self.get_account(*address).await?.map(|account| account.data))
+    }
+
+    async fn get_clock(&mut self) -> Result<Clock, ClientError> {
+        Ok(self.get_sysvar::<Clock>().await?)
+    }
+
+    async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError> {
+        Ok(BanksClient::get_latest_blockhash(self).await?)
+    }
+
+    async fn send_transaction(&mut self, transaction: Transaction) -> Result<Signature, ClientError> {
+        let signature = transaction.signatures[0];
+        self.process_transaction(transaction).await?;
+        Ok(signature)
+    }
+
+    async fn simulate_transaction(&mut self, transaction: &Transaction) -> Result<(), ClientError> {
+        let simulation = BanksClient::simulate_transaction(self, transaction.clone()).await?;
+        match simulation.result {
+            Some(Err(error)) => Err(ClientError::Simulation {
+                error: error.to_string(),
+                logs: simulation
+                    .simulation_details
+                    .map(|details| details.logs)
+                    .unwrap_or_default(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
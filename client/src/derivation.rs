@@ -0,0 +1,83 @@
+use std::{fmt, str::FromStr};
+
+use bip39::{Language, Mnemonic};
+use curve25519_dalek::scalar::Scalar;
+use hkdf::Hkdf;
+use rand::{CryptoRng, RngCore};
+use sha2::Sha512;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+use crate::wallet::Wallet;
+
+/// HKDF salt separating zerosol keys from other keys derived from the same seed
+pub const DERIVATION_SALT: &[u8] = b"gargantua-zerosol-v1";
+
+#[derive(Error, Debug)]
+pub enum DerivationError {
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(#[from] bip39::Error),
+    #[error("Invalid derivation path `{0}`")]
+    InvalidPath(String),
+}
+
+/// `m/gargantua/<pool>/<index>`: the `index`-th key of the pool whose
+/// global state account is `pool`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DerivationPath {
+    pub pool: Pubkey,
+    pub index: u32,
+}
+
+impl DerivationPath {
+    pub fn new(pool: Pubkey, index: u32) -> Self {
+        Self { pool, index }
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m/gargantua/{}/{}", self.pool, self.index)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = DerivationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DerivationError::InvalidPath(s.to_string());
+        match s.split('/').collect::<Vec<_>>()[..] {
+            ["m", "gargantua", pool, index] => Ok(Self {
+                pool: pool.parse().map_err(|_| invalid())?,
+                index: index.parse().map_err(|_| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Generate a 24-word English mnemonic
+pub fn generate_mnemonic<R: RngCore + CryptoRng>(rng: &mut R) -> Mnemonic {
+    let mut entropy = [0u8; 32];
+    rng.fill_bytes(&mut entropy);
+    Mnemonic::from_entropy(&entropy).expect("32 bytes is a valid entropy length")
+}
+
+/// BIP-39 seed of an English `phrase` protected by `passphrase`
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<[u8; 64], DerivationError> {
+    Ok(Mnemonic::parse_in(Language::English, phrase)?.to_seed(passphrase))
+}
+
+/// Secret key at `path`: HKDF-SHA512 of the seed with the path as info,
+/// reduced from 64 bytes so the scalar is uniform
+pub fn derive_secret_key(seed: &[u8], path: &DerivationPath) -> Scalar {
+    let mut okm = [0u8; 64];
+    Hkdf::<Sha512>::new(Some(DERIVATION_SALT), seed)
+        .expand(path.to_string().as_bytes(), &mut okm)
+        .expect("64 bytes is a valid HKDF-SHA512 output length");
+    Scalar::from_bytes_mod_order_wide(&okm)
+}
+
+pub fn derive_wallet(seed: &[u8], path: &DerivationPath) -> Wallet {
+    Wallet::from_secret_key(derive_secret_key(seed, path))
+}
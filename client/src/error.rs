@@ -0,0 +1,37 @@
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+use zerosol_solana::client::balance::BalanceError;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("RPC error: {0}")]
+    Rpc(Box<solana_client::client_error::ClientError>),
+    #[error("Banks client error: {0}")]
+    Banks(Box<solana_banks_client::BanksClientError>),
+    #[error("Account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("Account {0} does not hold the expected data")]
+    InvalidAccountData(Pubkey),
+    #[error(transparent)]
+    Balance(#[from] BalanceError),
+    #[error("Ring size {0} is not a power of two of at least 2")]
+    InvalidRingSize(usize),
+    #[error("Only {available} decoys available, {required} required")]
+    NotEnoughDecoys { available: usize, required: usize },
+    #[error("Simulation failed: {error}")]
+    Simulation { error: String, logs: Vec<String> },
+    #[error("Preflight only checks Transfer and Burn instructions with all their accounts")]
+    UnsupportedInstruction,
+}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        ClientError::Rpc(Box::new(e))
+    }
+}
+
+impl From<solana_banks_client::BanksClientError> for ClientError {
+    fn from(e: solana_banks_client::BanksClientError) -> Self {
+        ClientError::Banks(Box::new(e))
+    }
+}
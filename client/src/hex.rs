@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Invalid hex string")]
+pub struct HexError;
+
+/// Lowercase hex, two digits per byte
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Inverse of [`to_hex`], accepting either case
+pub fn from_hex(s: &str) -> Result<Vec<u8>, HexError> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(HexError);
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| HexError))
+        .collect()
+}
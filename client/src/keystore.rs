@@ -0,0 +1,250 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    hex::{from_hex, to_hex},
+    wallet::Wallet,
+};
+
+/// Format version written by this crate
+pub const KEYSTORE_VERSION: u32 = 1;
+pub const CIPHER: &str = "chacha20poly1305";
+pub const KDF: &str = "scrypt";
+
+#[derive(Error, Debug)]
+pub enum KeystoreError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Malformed keystore: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unsupported keystore version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Unsupported {0} `{1}`")]
+    Unsupported(&'static str, String),
+    #[error("Invalid keystore field `{0}`")]
+    InvalidField(&'static str),
+    #[error("Wrong password or corrupted keystore")]
+    WrongPassword,
+}
+
+/// scrypt cost parameters
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    /// 32 MiB and about a tenth of a second per unlock
+    fn default() -> Self {
+        Self { log_n: 15, r: 8, p: 1 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    pub nonce: String,                 // hex, 12 bytes
+    pub ciphertext: String,            // hex, encrypted secret key and tag
+    pub kdf: String,
+    pub kdf_params: ScryptParams,
+    pub salt: String,                  // hex, 32 bytes
+}
+
+/// Last decrypted balance, so tools can show a balance without the table
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BalanceHint {
+    pub spendable: u64,
+    pub pending: i64,
+    pub epoch: u64,
+}
+
+/// A password-encrypted zerosol secret key with its account metadata
+///
+/// Only the secret key is encrypted, bound to `public_key` as associated
+/// data. The metadata stays readable and can be updated without the
+/// password; `balance_hint` therefore reveals the last balance to anyone who
+/// can read the file.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct KeystoreFile {
+    pub version: u32,
+    pub public_key: String,            // hex
+    pub pool: Option<String>,          // global state address
+    pub mint: Option<String>,
+    pub registered: bool,
+    pub zerosol_account: Option<String>,
+    pub pending_account: Option<String>,
+    pub balance_hint: Option<BalanceHint>,
+    pub crypto: KeystoreCrypto,
+}
+
+impl KeystoreFile {
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        wallet: &Wallet,
+        password: &str,
+        rng: &mut R,
+    ) -> Result<Self, KeystoreError> {
+        Self::encrypt_with_params(wallet, password, ScryptParams::default(), rng)
+    }
+
+    pub fn encrypt_with_params<R: RngCore + CryptoRng>(
+        wallet: &Wallet,
+        password: &str,
+        params: ScryptParams,
+        rng: &mut R,
+    ) -> Result<Self, KeystoreError> {
+        let public_key = wallet.public_key();
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            public_key: to_hex(&public_key),
+            pool: None,
+            mint: None,
+            registered: false,
+            zerosol_account: None,
+            pending_account: None,
+            balance_hint: None,
+            crypto: seal(wallet.secret_key().as_bytes(), &public_key, password, params, rng)?,
+        })
+    }
+
+    pub fn public_key(&self) -> Result<[u8; 32], KeystoreError> {
+        from_hex(&self.public_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(KeystoreError::InvalidField("public_key"))
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<Wallet, KeystoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        let public_key = self.public_key()?;
+        let secret_key: [u8; 32] = open(&self.crypto, &public_key, password)?
+            .try_into()
+            .map_err(|_| KeystoreError::InvalidField("ciphertext"))?;
+        let wallet = Wallet::from_secret_bytes(&secret_key).ok_or(KeystoreError::InvalidField("ciphertext"))?;
+        if wallet.public_key() != public_key {
+            return Err(KeystoreError::InvalidField("public_key"));
+        }
+        Ok(wallet)
+    }
+
+    /// Re-encrypt the secret key under `new_password` with a fresh salt and nonce
+    pub fn change_password<R: RngCore + CryptoRng>(
+        &mut self,
+        old_password: &str,
+        new_password: &str,
+        rng: &mut R,
+    ) -> Result<(), KeystoreError> {
+        let wallet = self.decrypt(old_password)?;
+        self.crypto = seal(
+            wallet.secret_key().as_bytes(),
+            &wallet.public_key(),
+            new_password,
+            self.crypto.kdf_params,
+            rng,
+        )?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, KeystoreError> {
+        let file: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if file.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(file.version));
+        }
+        Ok(file)
+    }
+
+    /// Write the file readable by the owner only, replacing any previous
+    /// version atomically
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), KeystoreError> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)?;
+        file.write_all(&serde_json::to_vec_pretty(self)?)?;
+        file.sync_all()?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8], params: &ScryptParams) -> Result<[u8; 32], KeystoreError> {
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p)
+        .map_err(|_| KeystoreError::InvalidField("kdf_params"))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password.as_bytes(), salt, &scrypt_params, &mut key)
+        .map_err(|_| KeystoreError::InvalidField("kdf_params"))?;
+    Ok(key)
+}
+
+fn seal<R: RngCore + CryptoRng>(
+    secret: &[u8],
+    public_key: &[u8; 32],
+    password: &str,
+    params: ScryptParams,
+    rng: &mut R,
+) -> Result<KeystoreCrypto, KeystoreError> {
+    let mut salt = [0u8; 32];
+    let mut nonce = [0u8; 12];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let key = derive_key(password, &salt, &params)?;
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: secret, aad: public_key })
+        .map_err(|_| KeystoreError::InvalidField("ciphertext"))?;
+
+    Ok(KeystoreCrypto {
+        cipher: CIPHER.to_string(),
+        nonce: to_hex(&nonce),
+        ciphertext: to_hex(&ciphertext),
+        kdf: KDF.to_string(),
+        kdf_params: params,
+        salt: to_hex(&salt),
+    })
+}
+
+fn open(crypto: &KeystoreCrypto, public_key: &[u8; 32], password: &str) -> Result<Vec<u8>, KeystoreError> {
+    if crypto.cipher != CIPHER {
+        return Err(KeystoreError::Unsupported("cipher", crypto.cipher.clone()));
+    }
+    if crypto.kdf != KDF {
+        return Err(KeystoreError::Unsupported("kdf", crypto.kdf.clone()));
+    }
+    let nonce = from_hex(&crypto.nonce).map_err(|_| KeystoreError::InvalidField("nonce"))?;
+    if nonce.len() != 12 {
+        return Err(KeystoreError::InvalidField("nonce"));
+    }
+
+    let salt = from_hex(&crypto.salt).map_err(|_| KeystoreError::InvalidField("salt"))?;
+    let ciphertext = from_hex(&crypto.ciphertext).map_err(|_| KeystoreError::InvalidField("ciphertext"))?;
+    let key = derive_key(password, &salt, &crypto.kdf_params)?;
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: public_key,
+            },
+        )
+        .map_err(|_| KeystoreError::WrongPassword)
+}
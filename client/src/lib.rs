@@ -0,0 +1,19 @@
+pub mod account_view;
+pub mod connection;
+pub mod derivation;
+pub mod error;
+pub mod hex;
+pub mod keystore;
+pub mod preflight;
+pub mod ring;
+pub mod wallet;
+
+pub use account_view::{AccountView, RingMember};
+pub use connection::Connection;
+pub use derivation::DerivationPath;
+pub use error::ClientError;
+pub use hex::{from_hex, to_hex, HexError};
+pub use keystore::KeystoreFile;
+pub use preflight::{preflight, PreflightFailure, PreflightReport};
+pub use ring::{Candidate, Ring};
+pub use wallet::Wallet;
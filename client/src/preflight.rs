@@ -0,0 +1,258 @@
+use borsh::BorshDeserialize;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use zerosol_solana::{
+    instruction::ZerosolInstruction,
+    processor::{check_burn_proof, check_transfer_proof, ProofCheck},
+    state::{
+        epoch_at, BurnProof, GlobalState, NonceState, PendingAccount, TransferPayload, ZerosolAccount,
+        PAUSE_BURN, PAUSE_TRANSFER,
+    },
+    utils::MAX_TRANSFER_AMOUNT,
+};
+
+use crate::{
+    account_view::{fetch_state, AccountView},
+    connection::Connection,
+    error::ClientError,
+};
+
+/// A check the program would fail an instruction on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreflightFailure {
+    /// Transfers or burns are paused in the pool
+    Paused,
+    /// Transfer fee below the pool fee, or amount above the maximum
+    AmountOutOfRange(u64),
+    /// The burn would exceed the pool's withdrawal cap for the epoch
+    WithdrawalCapExceeded,
+    /// The nonce account is derived for another epoch than `current`, so the
+    /// proof was built for that epoch too
+    StaleEpoch { current: u64 },
+    /// The nonce was already used in this epoch
+    NonceUsed,
+    /// A zerosol or pending account does not exist or holds other data
+    MissingAccount(Pubkey),
+    AccountNotRegistered(Pubkey),
+    /// A locked account spent without the signature of its locking program
+    AccountLocked(Pubkey),
+    /// The pending account passed with this account is not the one it records
+    PendingAccountMismatch(Pubkey),
+    /// A participant account does not hold the ring key at its index
+    ParticipantKeyMismatch(Pubkey),
+    /// The beneficiary account does not hold the proof's beneficiary key
+    BeneficiaryMismatch,
+    /// The proof failed the program's verifier
+    Proof(ProofCheck),
+}
+
+/// Result of running every check of an instruction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreflightReport {
+    /// Epoch the program would execute the instruction in
+    pub epoch: u64,
+    /// Failed checks, in the order the program runs them
+    pub failures: Vec<PreflightFailure>,
+}
+
+impl PreflightReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Run the checks the program applies to a `Transfer` or `Burn` instruction
+/// against the current chain state, without sending it
+///
+/// Proofs go through the program's own verifier, in the epoch the program
+/// would execute the instruction in now. Unlike the program, every check
+/// runs, so the report lists all the reasons the instruction would fail.
+/// Signatures are not checked.
+pub async fn preflight<C: Connection + ?Sized>(
+    connection: &mut C,
+    instruction: &Instruction,
+) -> Result<PreflightReport, ClientError> {
+    let accounts: Vec<Pubkey> = instruction.accounts.iter().map(|meta| meta.pubkey).collect();
+    match ZerosolInstruction::try_from_slice(&instruction.data) {
+        Ok(ZerosolInstruction::Transfer {
+            commitments_c,
+            commitment_d,
+            public_keys,
+            nonce,
+            beneficiary,
+            fee,
+            tip_lamports,
+            proof,
+        }) if accounts.len() >= 6 => {
+            let payload = TransferPayload {
+                commitments_c,
+                commitment_d,
+                public_keys,
+                nonce,
+                beneficiary,
+                fee,
+                tip_lamports,
+                proof,
+            };
+            preflight_transfer(connection, &instruction.program_id, &accounts, &payload).await
+        }
+        Ok(ZerosolInstruction::Burn { amount, nonce, proof }) if accounts.len() >= 8 => {
+            preflight_burn(connection, &instruction.program_id, &accounts, amount, &nonce, &proof).await
+        }
+        _ => Err(ClientError::UnsupportedInstruction),
+    }
+}
+
+async fn preflight_transfer<C: Connection + ?Sized>(
+    connection: &mut C,
+    program_id: &Pubkey,
+    accounts: &[Pubkey],
+    payload: &TransferPayload,
+) -> Result<PreflightReport, ClientError> {
+    // relayer, beneficiary account and pending, nonce, global state, system
+    // program, then the tip payer and participant pairs
+    let state: GlobalState = fetch_state(connection, &accounts[4]).await?;
+    let epoch = epoch_at(&connection.get_clock().await?, &state);
+    let mut failures = Vec::new();
+
+    if state.is_paused(PAUSE_TRANSFER) {
+        failures.push(PreflightFailure::Paused);
+    }
+    if payload.fee < state.fee || payload.fee > MAX_TRANSFER_AMOUNT {
+        failures.push(PreflightFailure::AmountOutOfRange(payload.fee));
+    }
+    if let Err(check) = check_transfer_proof(payload, epoch) {
+        failures.push(PreflightFailure::Proof(check));
+    }
+    check_nonce(connection, program_id, &accounts[3], &payload.nonce, epoch, &mut failures).await?;
+
+    let beneficiary = check_account(connection, &accounts[1], &accounts[2], None, &mut failures).await?;
+    if beneficiary.is_some_and(|(account, _)| account.public_key != payload.beneficiary) {
+        failures.push(PreflightFailure::BeneficiaryMismatch);
+    }
+
+    let first = if payload.tip_lamports > 0 { 7 } else { 6 };
+    let participants = accounts.get(first..).unwrap_or_default();
+    for (pair, public_key) in participants.chunks_exact(2).zip(&payload.public_keys) {
+        let participant = check_account(connection, &pair[0], &pair[1], Some(&accounts[0]), &mut failures).await?;
+        if participant.is_some_and(|(account, _)| account.public_key != *public_key) {
+            failures.push(PreflightFailure::ParticipantKeyMismatch(pair[0]));
+        }
+    }
+
+    Ok(PreflightReport { epoch, failures })
+}
+
+async fn preflight_burn<C: Connection + ?Sized>(
+    connection: &mut C,
+    program_id: &Pubkey,
+    accounts: &[Pubkey],
+    amount: u64,
+    nonce: &[u8; 32],
+    proof: &BurnProof,
+) -> Result<PreflightReport, ClientError> {
+    // withdrawer, zerosol account and pending, token accounts, nonce, token
+    // program, global state, ...
+    let mut state: GlobalState = fetch_state(connection, &accounts[7]).await?;
+    let epoch = epoch_at(&connection.get_clock().await?, &state);
+    let mut failures = Vec::new();
+
+    if amount > MAX_TRANSFER_AMOUNT {
+        failures.push(PreflightFailure::AmountOutOfRange(amount));
+    }
+    if state.is_paused(PAUSE_BURN) {
+        failures.push(PreflightFailure::Paused);
+    }
+    check_nonce(connection, program_id, &accounts[5], nonce, epoch, &mut failures).await?;
+    if state.record_burn(epoch, amount).is_err() {
+        failures.push(PreflightFailure::WithdrawalCapExceeded);
+    }
+
+    let (address, pending_address) = (accounts[1], accounts[2]);
+    let withdrawer = Some(&accounts[0]);
+    if let Some((account, pending)) =
+        check_account(connection, &address, &pending_address, withdrawer, &mut failures).await?
+    {
+        // The proof is checked against the account as rolled over in `epoch`
+        let mut view = AccountView {
+            address,
+            pending_address,
+            account,
+            pending,
+            current_epoch: epoch,
+        };
+        let (commitment_left, commitment_right) = view.effective_ciphertext()?;
+        view.account.commitment_left = commitment_left;
+        view.account.commitment_right = commitment_right;
+        view.account.last_rollover = view.account.last_rollover.max(epoch);
+        if let Err(check) = check_burn_proof(proof, &view.account, amount, nonce, epoch) {
+            failures.push(PreflightFailure::Proof(check));
+        }
+    }
+
+    Ok(PreflightReport { epoch, failures })
+}
+
+/// Check the nonce account is the unused one for `nonce` in `epoch`
+async fn check_nonce<C: Connection + ?Sized>(
+    connection: &mut C,
+    program_id: &Pubkey,
+    nonce_account: &Pubkey,
+    nonce: &[u8; 32],
+    epoch: u64,
+    failures: &mut Vec<PreflightFailure>,
+) -> Result<(), ClientError> {
+    let (expected, _) = NonceState::find_address(program_id, epoch, nonce);
+    if *nonce_account != expected {
+        failures.push(PreflightFailure::StaleEpoch { current: epoch });
+    } else if connection
+        .get_account_data(nonce_account)
+        .await?
+        .is_some_and(|data| !data.is_empty())
+    {
+        failures.push(PreflightFailure::NonceUsed);
+    }
+    Ok(())
+}
+
+/// Check a zerosol account the instruction updates, and if `spender` is
+/// given, that it may spend from it
+///
+/// Returns the account and its pending account if both exist.
+async fn check_account<C: Connection + ?Sized>(
+    connection: &mut C,
+    address: &Pubkey,
+    pending_address: &Pubkey,
+    spender: Option<&Pubkey>,
+    failures: &mut Vec<PreflightFailure>,
+) -> Result<Option<(ZerosolAccount, PendingAccount)>, ClientError> {
+    let Some(account) = fetch_optional::<ZerosolAccount, _>(connection, address).await? else {
+        failures.push(PreflightFailure::MissingAccount(*address));
+        return Ok(None);
+    };
+    if !account.is_registered {
+        failures.push(PreflightFailure::AccountNotRegistered(*address));
+    }
+    if account.is_locked() && spender.is_some_and(|spender| *spender != account.lock_authority(address)) {
+        failures.push(PreflightFailure::AccountLocked(*address));
+    }
+    if account.pending_account != *pending_address {
+        failures.push(PreflightFailure::PendingAccountMismatch(*address));
+    }
+    let Some(pending) = fetch_optional(connection, pending_address).await? else {
+        failures.push(PreflightFailure::MissingAccount(*pending_address));
+        return Ok(None);
+    };
+    Ok(Some((account, pending)))
+}
+
+/// Like `fetch_state`, with `None` for accounts the program could not decode
+async fn fetch_optional<T: BorshDeserialize, C: Connection + ?Sized>(
+    connection: &mut C,
+    address: &Pubkey,
+) -> Result<Option<T>, ClientError> {
+    match fetch_state(connection, address).await {
+        Ok(state) => Ok(Some(state)),
+        Err(ClientError::AccountNotFound(_) | ClientError::InvalidAccountData(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,93 @@
+use rand::{seq::SliceRandom, CryptoRng, RngCore};
+use solana_sdk::pubkey::Pubkey;
+use zerosol_solana::state::{PendingAccount, ZerosolAccount};
+
+use crate::{account_view::RingMember, error::ClientError};
+
+/// An account considered as a decoy, with its on-chain state
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub member: RingMember,
+    pub account: ZerosolAccount,
+    pub pending: PendingAccount,
+}
+
+impl Candidate {
+    /// Whether the pending account received anything since the last rollover
+    pub fn is_touched(&self) -> bool {
+        // The identity compresses to all zeros
+        self.pending.commitment_left != [0u8; 32] || self.pending.commitment_right != [0u8; 32]
+    }
+
+    /// Whether the account can be a decoy in `epoch`
+    ///
+    /// Decoys must be registered, unlocked, rolled over in `epoch` and
+    /// untouched since, so their ciphertexts are settled and no other
+    /// transfer of the epoch already changed them.
+    pub fn is_eligible(&self, epoch: u64) -> bool {
+        self.account.is_registered
+            && self.account.locked_to == Pubkey::default()
+            && self.account.last_rollover == epoch
+            && !self.is_touched()
+    }
+}
+
+/// Smallest ring the proof system accepts with at least `members` members
+pub fn ring_size_for(members: usize) -> usize {
+    members.max(2).next_power_of_two()
+}
+
+/// A shuffled ring with the positions of the real sender and recipient
+#[derive(Clone, Debug)]
+pub struct Ring {
+    pub members: Vec<RingMember>,
+    pub sender_index: usize,
+    pub recipient_index: usize,
+}
+
+/// Build a ring of `ring_size` members around `sender` and `recipient`
+///
+/// Decoys are drawn uniformly without replacement from the eligible
+/// `candidates`, then all positions are shuffled so neither real party sits
+/// at a predictable index.
+pub fn select_ring<R: RngCore + CryptoRng>(
+    sender: RingMember,
+    recipient: RingMember,
+    candidates: &[Candidate],
+    epoch: u64,
+    ring_size: usize,
+    rng: &mut R,
+) -> Result<Ring, ClientError> {
+    if ring_size < 2 || !ring_size.is_power_of_two() {
+        return Err(ClientError::InvalidRingSize(ring_size));
+    }
+
+    let mut eligible: Vec<RingMember> = candidates
+        .iter()
+        .filter(|c| c.is_eligible(epoch))
+        .map(|c| c.member)
+        .filter(|m| m.public_key != sender.public_key && m.public_key != recipient.public_key)
+        .collect();
+    eligible.sort_by_key(|m| m.public_key);
+    eligible.dedup_by_key(|m| m.public_key);
+
+    let needed = ring_size - 2;
+    if eligible.len() < needed {
+        return Err(ClientError::NotEnoughDecoys {
+            available: eligible.len(),
+            required: needed,
+        });
+    }
+
+    let mut members = vec![sender, recipient];
+    members.extend(eligible.choose_multiple(rng, needed).copied());
+    members.shuffle(rng);
+
+    let sender_index = members.iter().position(|m| *m == sender).unwrap();
+    let recipient_index = members.iter().position(|m| *m == recipient).unwrap();
+    Ok(Ring {
+        members,
+        sender_index,
+        recipient_index,
+    })
+}
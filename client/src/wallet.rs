@@ -0,0 +1,111 @@
+use curve25519_dalek::scalar::Scalar;
+use rand::{CryptoRng, RngCore};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use zerosol_solana::{
+    instruction,
+    state::{PendingAccount, ZerosolAccount},
+    utils::{account_action_message, register_account_message, schnorr_sign, G1Point},
+};
+
+/// A zerosol key pair
+///
+/// The secret key decrypts the balance and signs registrations and
+/// account-signed actions; the Solana fee payer is a separate keypair.
+#[derive(Clone)]
+pub struct Wallet {
+    secret_key: Scalar,
+    public_key: [u8; 32],
+}
+
+impl Wallet {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self::from_secret_key(random_scalar(rng))
+    }
+
+    pub fn from_secret_key(secret_key: Scalar) -> Self {
+        let public_key = G1Point::generator().mul(&secret_key).to_bytes();
+        Self { secret_key, public_key }
+    }
+
+    /// Load a wallet from a canonical scalar encoding
+    pub fn from_secret_bytes(bytes: &[u8; 32]) -> Option<Self> {
+        Option::<Scalar>::from(Scalar::from_canonical_bytes(*bytes)).map(Self::from_secret_key)
+    }
+
+    pub fn secret_key(&self) -> &Scalar {
+        &self.secret_key
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key
+    }
+
+    /// Addresses of the `(zerosol account, pending account)` pair `FundToKey`
+    /// creates for this key
+    pub fn key_accounts(&self, program_id: &Pubkey) -> (Pubkey, Pubkey) {
+        (
+            ZerosolAccount::find_address(program_id, &self.public_key).0,
+            PendingAccount::find_address(program_id, &self.public_key).0,
+        )
+    }
+
+    /// Schnorr signature `(challenge, response)` over `message`
+    pub fn sign(&self, message: &[u8]) -> ([u8; 32], [u8; 32]) {
+        schnorr_sign(&self.secret_key, message)
+    }
+
+    /// Sign an account-signed action, see `account_action_message`
+    pub fn sign_action(
+        &self,
+        program_id: &Pubkey,
+        action: u8,
+        zerosol_account: &Pubkey,
+        epoch: u64,
+        action_nonce: u64,
+        params: &[u8],
+    ) -> ([u8; 32], [u8; 32]) {
+        self.sign(&account_action_message(
+            program_id,
+            action,
+            zerosol_account,
+            epoch,
+            action_nonce,
+            params,
+        ))
+    }
+
+    /// Build a signed `Register` instruction for freshly generated accounts
+    pub fn register(
+        &self,
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        zerosol_account: &Pubkey,
+        pending_account: &Pubkey,
+    ) -> Instruction {
+        let (challenge, response) =
+            self.sign(&register_account_message(program_id, zerosol_account, payer));
+        instruction::register(
+            program_id,
+            payer,
+            zerosol_account,
+            pending_account,
+            self.public_key,
+            challenge,
+            response,
+        )
+    }
+
+    /// Build a signed `ClaimAccount` instruction for the key's `FundToKey` account
+    pub fn claim(&self, program_id: &Pubkey, payer: &Pubkey) -> Instruction {
+        let (zerosol_account, _) = self.key_accounts(program_id);
+        let (challenge, response) =
+            self.sign(&register_account_message(program_id, &zerosol_account, payer));
+        instruction::claim_account(program_id, payer, &self.public_key, challenge, response)
+    }
+}
+
+fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+    let mut bytes = [0u8; 64];
+    rng.fill_bytes(&mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
@@ -0,0 +1,84 @@
+use rand::rngs::OsRng;
+use solana_program_test::*;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use zerosol_client::{AccountView, ClientError, Connection, Wallet};
+use zerosol_solana::{
+    client::balance::BabyStepTable,
+    instruction::initialize,
+    state::{GlobalState, EPOCH_SOURCE_TIMESTAMP},
+};
+
+#[tokio::test]
+async fn test_wallet_register_and_view() {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new(
+        "zerosol_solana",
+        program_id,
+        processor!(zerosol_solana::process_instruction),
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let (global_state, _) = GlobalState::find_address(&program_id);
+    let token_mint = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[initialize(
+            &program_id,
+            &payer.pubkey(),
+            &token_mint.pubkey(),
+            3600,
+            1,
+            EPOCH_SOURCE_TIMESTAMP,
+            0,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let wallet = Wallet::generate(&mut OsRng);
+    let zerosol_account = Keypair::new();
+    let pending_account = Keypair::new();
+    let instruction = wallet.register(
+        &program_id,
+        &payer.pubkey(),
+        &zerosol_account.pubkey(),
+        &pending_account.pubkey(),
+    );
+    let blockhash = Connection::get_latest_blockhash(&mut banks_client).await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[&payer, &zerosol_account, &pending_account],
+        blockhash,
+    );
+    Connection::send_transaction(&mut banks_client, transaction).await.unwrap();
+
+    let view = AccountView::fetch(
+        &mut banks_client,
+        &global_state,
+        zerosol_account.pubkey(),
+        pending_account.pubkey(),
+    )
+    .await
+    .unwrap();
+    assert!(view.account.is_registered);
+    assert_eq!(view.account.public_key, wallet.public_key());
+
+    let balance = view.balance(&wallet, &BabyStepTable::new(8)).unwrap();
+    assert_eq!((balance.spendable, balance.pending), (0, 0));
+
+    // Key-derived accounts do not exist until someone funds the key
+    let result = AccountView::fetch_for_key(
+        &mut banks_client,
+        &program_id,
+        &global_state,
+        &wallet.public_key(),
+    )
+    .await;
+    assert!(matches!(result, Err(ClientError::AccountNotFound(_))));
+}
@@ -0,0 +1,82 @@
+use solana_sdk::{pubkey::Pubkey, sysvar};
+use zerosol_client::{
+    derivation::{derive_secret_key, derive_wallet, generate_mnemonic, mnemonic_to_seed, DerivationPath},
+    to_hex as hex,
+};
+
+const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+#[test]
+fn test_seed_matches_bip39_vector() {
+    let seed = mnemonic_to_seed(PHRASE, "TREZOR").unwrap();
+    assert_eq!(
+        hex(&seed),
+        "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+    );
+
+    // Bad checksum
+    assert!(mnemonic_to_seed(&PHRASE.replace("about", "abandon"), "").is_err());
+}
+
+#[test]
+fn test_derivation_vectors() {
+    let seed = mnemonic_to_seed(PHRASE, "TREZOR").unwrap();
+    let vectors = [
+        (
+            DerivationPath::new(Pubkey::default(), 0),
+            "0efd4d2125c892bc63bcf875989677bc2157c12c511b92b229b73b50d68e580f",
+            "64193d052352090856cadecd8565b4b030592ef73320e5221e62b4bb31275e2d",
+        ),
+        (
+            DerivationPath::new(Pubkey::default(), 1),
+            "724e2aea88b6e99ac0206589e59435b6747269cf5632f521da86cbf6a9228e06",
+            "528ea048b7fdaf4e891994da89ad5d5f48604b496d29f9001501b2e69fa0687a",
+        ),
+        (
+            DerivationPath::new(sysvar::clock::id(), 0),
+            "93a97a9e74b1c833600ff0d094cb97b15be06b51f1e6be1a96f731f87bd07e0a",
+            "e05214940ce4a37ee59fa940ffc94285207cef076497f15f11a4595baaacfc6f",
+        ),
+    ];
+
+    for (path, secret_key, public_key) in vectors {
+        assert_eq!(hex(derive_secret_key(&seed, &path).as_bytes()), secret_key, "{}", path);
+        assert_eq!(hex(&derive_wallet(&seed, &path).public_key()), public_key, "{}", path);
+    }
+
+    let seed = mnemonic_to_seed(
+        "legal winner thank year wave sausage worth useful legal winner thank yellow",
+        "",
+    )
+    .unwrap();
+    assert_eq!(
+        hex(&derive_wallet(&seed, &DerivationPath::new(Pubkey::default(), 0)).public_key()),
+        "d4c885ace643a0e3d3e3dcac1323edc277b76b0c078efa85798492460f0ed33c"
+    );
+}
+
+#[test]
+fn test_derivation_path_round_trip() {
+    let path = DerivationPath::new(sysvar::clock::id(), 7);
+    assert_eq!(path.to_string(), format!("m/gargantua/{}/7", sysvar::clock::id()));
+    assert_eq!(path.to_string().parse::<DerivationPath>().unwrap(), path);
+
+    assert!("m/gargantua/not-a-pubkey/0".parse::<DerivationPath>().is_err());
+    assert!(format!("m/gargantua/{}", Pubkey::default()).parse::<DerivationPath>().is_err());
+    assert!(format!("m/other/{}/0", Pubkey::default()).parse::<DerivationPath>().is_err());
+}
+
+#[test]
+fn test_generated_mnemonic_restores_keys() {
+    let mnemonic = generate_mnemonic(&mut rand::rngs::OsRng);
+    assert_eq!(mnemonic.word_count(), 24);
+
+    let path = DerivationPath::new(Pubkey::new_unique(), 3);
+    let original = derive_wallet(&mnemonic_to_seed(&mnemonic.to_string(), "pw").unwrap(), &path);
+    let restored = derive_wallet(&mnemonic_to_seed(&mnemonic.to_string(), "pw").unwrap(), &path);
+    assert_eq!(original.public_key(), restored.public_key());
+
+    // Another passphrase gives other keys
+    let other = derive_wallet(&mnemonic_to_seed(&mnemonic.to_string(), "").unwrap(), &path);
+    assert_ne!(original.public_key(), other.public_key());
+}
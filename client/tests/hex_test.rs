@@ -0,0 +1,18 @@
+use zerosol_client::{from_hex, to_hex, HexError};
+
+#[test]
+fn test_hex_round_trip() {
+    let bytes: Vec<u8> = (0..=255).collect();
+    let hex = to_hex(&bytes);
+    assert_eq!(&hex[..8], "00010203");
+    assert_eq!(from_hex(&hex).unwrap(), bytes);
+    assert_eq!(from_hex("DEADbeef").unwrap(), vec![0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(from_hex("").unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn test_hex_rejects_malformed_input() {
+    for input in ["0", "0g", "+f", "-1", " 00", "é0"] {
+        assert_eq!(from_hex(input), Err(HexError), "{:?}", input);
+    }
+}
@@ -0,0 +1,87 @@
+use rand::rngs::OsRng;
+use zerosol_client::{
+    keystore::{BalanceHint, KeystoreError, KeystoreFile, ScryptParams},
+    to_hex, Wallet,
+};
+
+/// Cheap parameters; the default cost is too slow for tests
+const PARAMS: ScryptParams = ScryptParams { log_n: 8, r: 8, p: 1 };
+
+#[test]
+fn test_keystore_round_trip() {
+    let wallet = Wallet::generate(&mut OsRng);
+    let mut file = KeystoreFile::encrypt_with_params(&wallet, "hunter2", PARAMS, &mut OsRng).unwrap();
+    file.pool = Some("pool".to_string());
+    file.registered = true;
+    file.balance_hint = Some(BalanceHint {
+        spendable: 10,
+        pending: 2,
+        epoch: 4,
+    });
+
+    let dir = std::env::temp_dir().join(format!("zerosol-keystore-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("key.json");
+    file.save(&path).unwrap();
+
+    let loaded = KeystoreFile::load(&path).unwrap();
+    assert_eq!(loaded.public_key().unwrap(), wallet.public_key());
+    assert_eq!(loaded.pool.as_deref(), Some("pool"));
+    assert!(loaded.registered);
+    assert_eq!(loaded.balance_hint.unwrap().spendable, 10);
+
+    // The secret key never appears in the clear
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains(&to_hex(wallet.secret_key().as_bytes())));
+
+    let restored = loaded.decrypt("hunter2").unwrap();
+    assert_eq!(restored.secret_key(), wallet.secret_key());
+    assert!(matches!(loaded.decrypt("hunter3"), Err(KeystoreError::WrongPassword)));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_keystore_change_password() {
+    let wallet = Wallet::generate(&mut OsRng);
+    let mut file = KeystoreFile::encrypt_with_params(&wallet, "old", PARAMS, &mut OsRng).unwrap();
+    let old_crypto = file.crypto.clone();
+
+    assert!(matches!(
+        file.change_password("wrong", "new", &mut OsRng),
+        Err(KeystoreError::WrongPassword)
+    ));
+    file.change_password("old", "new", &mut OsRng).unwrap();
+
+    assert_ne!(file.crypto.salt, old_crypto.salt);
+    assert_eq!(file.crypto.kdf_params, PARAMS);
+    assert!(file.decrypt("old").is_err());
+    assert_eq!(file.decrypt("new").unwrap().public_key(), wallet.public_key());
+}
+
+#[test]
+fn test_keystore_rejects_tampering() {
+    let wallet = Wallet::generate(&mut OsRng);
+    let other = Wallet::generate(&mut OsRng);
+    let file = KeystoreFile::encrypt_with_params(&wallet, "pw", PARAMS, &mut OsRng).unwrap();
+
+    // The ciphertext is bound to the public key
+    let mut swapped = file.clone();
+    swapped.public_key = KeystoreFile::encrypt_with_params(&other, "pw", PARAMS, &mut OsRng)
+        .unwrap()
+        .public_key;
+    assert!(matches!(swapped.decrypt("pw"), Err(KeystoreError::WrongPassword)));
+
+    let mut flipped = file.clone();
+    let last = flipped.crypto.ciphertext.pop().unwrap();
+    flipped.crypto.ciphertext.push(if last == '0' { '1' } else { '0' });
+    assert!(matches!(flipped.decrypt("pw"), Err(KeystoreError::WrongPassword)));
+
+    let mut future = file.clone();
+    future.version = 2;
+    assert!(matches!(future.decrypt("pw"), Err(KeystoreError::UnsupportedVersion(2))));
+
+    let mut cipher = file;
+    cipher.crypto.cipher = "aes-128-ctr".to_string();
+    assert!(matches!(cipher.decrypt("pw"), Err(KeystoreError::Unsupported("cipher", _))));
+}
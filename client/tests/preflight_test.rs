@@ -0,0 +1,315 @@
+use borsh::BorshSerialize;
+use rand::rngs::OsRng;
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use zerosol_client::{preflight, ClientError, PreflightFailure, RingMember, Wallet};
+use zerosol_solana::{
+    instruction::{burn, initialize, set_pause, transfer},
+    processor::ProofCheck,
+    state::{BurnProof, InnerProductProof, NonceState, ZerosolProof, EPOCH_SOURCE_SLOT, PAUSE_BURN},
+    utils::G1Point,
+};
+
+const USED_NONCE: [u8; 32] = [9u8; 32];
+
+fn ip_proof() -> InnerProductProof {
+    InnerProductProof {
+        l_points: vec![],
+        r_points: vec![],
+        a: [0u8; 32],
+        b: [0u8; 32],
+    }
+}
+
+/// A transfer proof whose challenge matches no statement
+fn transfer_proof() -> ZerosolProof {
+    ZerosolProof {
+        ba: [0u8; 32],
+        bs: [0u8; 32],
+        a: [0u8; 32],
+        b: [0u8; 32],
+        cln_g: vec![],
+        crn_g: vec![],
+        c_0g: vec![],
+        dg: vec![],
+        y_0g: vec![],
+        gg: vec![],
+        c_xg: vec![],
+        y_xg: vec![],
+        f: vec![],
+        z_a: [0u8; 32],
+        t_1: [0u8; 32],
+        t_2: [0u8; 32],
+        t_hat: [0u8; 32],
+        mu: [0u8; 32],
+        c: [1u8; 32],
+        s_sk: [0u8; 32],
+        s_r: [0u8; 32],
+        s_b: [0u8; 32],
+        s_tau: [0u8; 32],
+        ip_proof: ip_proof(),
+    }
+}
+
+fn burn_proof() -> BurnProof {
+    BurnProof {
+        ba: [0u8; 32],
+        bs: [0u8; 32],
+        t_1: [0u8; 32],
+        t_2: [0u8; 32],
+        t_hat: [0u8; 32],
+        mu: [0u8; 32],
+        c: [1u8; 32],
+        s_sk: [0u8; 32],
+        s_b: [0u8; 32],
+        s_tau: [0u8; 32],
+        ip_proof: ip_proof(),
+    }
+}
+
+struct Pool {
+    banks_client: BanksClient,
+    payer: Keypair,
+    program_id: Pubkey,
+    members: Vec<RingMember>,
+}
+
+impl Pool {
+    async fn send(&mut self, instruction: Instruction, signers: &[&Keypair]) {
+        let mut keypairs = vec![&self.payer];
+        keypairs.extend_from_slice(signers);
+        let blockhash = self.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction =
+            Transaction::new_signed_with_payer(&[instruction], Some(&self.payer.pubkey()), &keypairs, blockhash);
+        self.banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    /// Transfer from the first member to `participants`, paying the first
+    /// member a fee of `fee`
+    fn transfer(
+        &self,
+        participants: &[RingMember],
+        commitments_c: Vec<[u8; 32]>,
+        epoch: u64,
+        nonce: [u8; 32],
+        fee: u64,
+    ) -> Instruction {
+        let beneficiary = self.members[0];
+        let pairs: Vec<_> = participants
+            .iter()
+            .map(|member| (member.zerosol_account, member.pending_account))
+            .collect();
+        transfer(
+            &self.program_id,
+            &self.payer.pubkey(),
+            &beneficiary.zerosol_account,
+            &beneficiary.pending_account,
+            &pairs,
+            None,
+            epoch,
+            commitments_c,
+            G1Point::generator().to_bytes(),
+            participants.iter().map(|member| member.public_key).collect(),
+            nonce,
+            beneficiary.public_key,
+            fee,
+            transfer_proof(),
+        )
+    }
+}
+
+/// A pool with a fee of 1 whose epoch 0 lasts the whole test, two registered
+/// members and `USED_NONCE` already consumed
+async fn start() -> Pool {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new(
+        "zerosol_solana",
+        program_id,
+        processor!(zerosol_solana::process_instruction),
+    );
+    let (nonce_address, bump) = NonceState::find_address(&program_id, 0, &USED_NONCE);
+    let mut nonce_state = NonceState::new(USED_NONCE, 0, Pubkey::new_unique(), bump);
+    nonce_state.used = true;
+    program_test.add_account(
+        nonce_address,
+        Account {
+            lamports: 1_000_000,
+            data: nonce_state.try_to_vec().unwrap(),
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let (banks_client, payer, _) = program_test.start().await;
+
+    let mut pool = Pool {
+        banks_client,
+        payer,
+        program_id,
+        members: Vec::new(),
+    };
+    let initialize = initialize(
+        &program_id,
+        &pool.payer.pubkey(),
+        &Pubkey::new_unique(),
+        1_000_000,
+        1,
+        EPOCH_SOURCE_SLOT,
+        0,
+    );
+    pool.send(initialize, &[]).await;
+
+    for _ in 0..2 {
+        let wallet = Wallet::generate(&mut OsRng);
+        let zerosol_account = Keypair::new();
+        let pending_account = Keypair::new();
+        let register = wallet.register(
+            &program_id,
+            &pool.payer.pubkey(),
+            &zerosol_account.pubkey(),
+            &pending_account.pubkey(),
+        );
+        pool.send(register, &[&zerosol_account, &pending_account]).await;
+        pool.members.push(RingMember {
+            public_key: wallet.public_key(),
+            zerosol_account: zerosol_account.pubkey(),
+            pending_account: pending_account.pubkey(),
+        });
+    }
+    pool
+}
+
+#[tokio::test]
+async fn test_preflight_transfer() {
+    let mut pool = start().await;
+    let point = G1Point::generator().to_bytes();
+    let stranger = RingMember {
+        public_key: point,
+        zerosol_account: Pubkey::new_unique(),
+        pending_account: Pubkey::new_unique(),
+    };
+
+    // Only the proof and the unknown ring member are wrong
+    let ring = [pool.members[1], stranger];
+    let forged = pool.transfer(&ring, vec![point, point], 0, [1u8; 32], 1);
+    let report = preflight(&mut pool.banks_client, &forged).await.unwrap();
+    assert_eq!(report.epoch, 0);
+    assert!(!report.is_ok());
+    assert_eq!(
+        report.failures,
+        vec![
+            PreflightFailure::Proof(ProofCheck::SigmaEquation),
+            PreflightFailure::MissingAccount(stranger.zerosol_account),
+        ]
+    );
+
+    // The identity is rejected before the statement is looked at
+    let members = pool.members.clone();
+    let identity = pool.transfer(&members, vec![point, [0u8; 32]], 0, [1u8; 32], 1);
+    let report = preflight(&mut pool.banks_client, &identity).await.unwrap();
+    assert_eq!(report.failures, vec![PreflightFailure::Proof(ProofCheck::CommitmentRange(1))]);
+
+    let stale = pool.transfer(&members, vec![point, point], 1, [1u8; 32], 1);
+    let report = preflight(&mut pool.banks_client, &stale).await.unwrap();
+    assert!(report
+        .failures
+        .contains(&PreflightFailure::StaleEpoch { current: 0 }));
+
+    let mut replayed = pool.transfer(&members, vec![point, point], 0, USED_NONCE, 0);
+    replayed.accounts[1].pubkey = members[1].zerosol_account;
+    replayed.accounts[2].pubkey = members[1].pending_account;
+    let report = preflight(&mut pool.banks_client, &replayed).await.unwrap();
+    assert_eq!(
+        report.failures,
+        vec![
+            PreflightFailure::AmountOutOfRange(0),
+            PreflightFailure::Proof(ProofCheck::SigmaEquation),
+            PreflightFailure::NonceUsed,
+            PreflightFailure::BeneficiaryMismatch,
+        ]
+    );
+
+    // The second member passed with the first member's pending account
+    let mut swapped = pool.transfer(&members, vec![point, point], 0, [2u8; 32], 1);
+    let last = swapped.accounts.len() - 1;
+    swapped.accounts[last].pubkey = members[0].pending_account;
+    let report = preflight(&mut pool.banks_client, &swapped).await.unwrap();
+    assert_eq!(
+        report.failures,
+        vec![
+            PreflightFailure::Proof(ProofCheck::SigmaEquation),
+            PreflightFailure::PendingAccountMismatch(members[1].zerosol_account),
+        ]
+    );
+
+    // The second member listed under a key it does not hold
+    let impostor = RingMember {
+        public_key: point,
+        ..members[1]
+    };
+    let mislabeled = pool.transfer(&[members[0], impostor], vec![point, point], 0, [3u8; 32], 1);
+    let report = preflight(&mut pool.banks_client, &mislabeled).await.unwrap();
+    assert_eq!(
+        report.failures,
+        vec![
+            PreflightFailure::Proof(ProofCheck::SigmaEquation),
+            PreflightFailure::ParticipantKeyMismatch(members[1].zerosol_account),
+        ]
+    );
+
+    // The fee credited to a pending account the beneficiary does not own
+    let mut diverted = pool.transfer(&members, vec![point, point], 0, [4u8; 32], 1);
+    diverted.accounts[2].pubkey = members[1].pending_account;
+    let report = preflight(&mut pool.banks_client, &diverted).await.unwrap();
+    assert_eq!(
+        report.failures,
+        vec![
+            PreflightFailure::Proof(ProofCheck::SigmaEquation),
+            PreflightFailure::PendingAccountMismatch(members[0].zerosol_account),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_preflight_burn() {
+    let mut pool = start().await;
+    let member = pool.members[0];
+    let withdrawal = burn(
+        &pool.program_id,
+        &pool.payer.pubkey(),
+        &member.zerosol_account,
+        &member.pending_account,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        0,
+        10,
+        [2u8; 32],
+        burn_proof(),
+    );
+    let report = preflight(&mut pool.banks_client, &withdrawal).await.unwrap();
+    assert_eq!(report.failures, vec![PreflightFailure::Proof(ProofCheck::SigmaEquation)]);
+
+    let pause = set_pause(&pool.program_id, &pool.payer.pubkey(), false, PAUSE_BURN);
+    pool.send(pause, &[]).await;
+    let report = preflight(&mut pool.banks_client, &withdrawal).await.unwrap();
+    assert_eq!(
+        report.failures,
+        vec![
+            PreflightFailure::Paused,
+            PreflightFailure::Proof(ProofCheck::SigmaEquation),
+        ]
+    );
+
+    // Only transfers and burns are checked
+    let pause_all = set_pause(&pool.program_id, &pool.payer.pubkey(), true, 0);
+    assert!(matches!(
+        preflight(&mut pool.banks_client, &pause_all).await,
+        Err(ClientError::UnsupportedInstruction)
+    ));
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use rand::{rngs::StdRng, SeedableRng};
+use solana_sdk::pubkey::Pubkey;
+use zerosol_client::{
+    ring::{ring_size_for, select_ring},
+    Candidate, ClientError, RingMember,
+};
+use zerosol_solana::state::{PendingAccount, ZerosolAccount};
+
+fn member(tag: u8) -> RingMember {
+    RingMember {
+        public_key: [tag; 32],
+        zerosol_account: Pubkey::new_unique(),
+        pending_account: Pubkey::new_unique(),
+    }
+}
+
+fn candidate(tag: u8, epoch: u64) -> Candidate {
+    let member = member(tag);
+    let mut account = ZerosolAccount::new([tag; 32], member.pending_account);
+    account.last_rollover = epoch;
+    account.is_registered = true;
+    Candidate {
+        member,
+        account,
+        pending: PendingAccount::new(),
+    }
+}
+
+#[test]
+fn test_candidate_eligibility() {
+    assert!(candidate(1, 5).is_eligible(5));
+
+    // Rolled over in an earlier epoch
+    assert!(!candidate(1, 4).is_eligible(5));
+
+    let mut unregistered = candidate(1, 5);
+    unregistered.account.is_registered = false;
+    assert!(!unregistered.is_eligible(5));
+
+    let mut locked = candidate(1, 5);
+    locked.account.locked_to = Pubkey::new_unique();
+    assert!(!locked.is_eligible(5));
+
+    // Already received a transfer this epoch
+    let mut touched = candidate(1, 5);
+    touched.pending.commitment_right = [7u8; 32];
+    assert!(touched.is_touched());
+    assert!(!touched.is_eligible(5));
+}
+
+#[test]
+fn test_ring_size() {
+    assert_eq!(ring_size_for(0), 2);
+    assert_eq!(ring_size_for(2), 2);
+    assert_eq!(ring_size_for(3), 4);
+    assert_eq!(ring_size_for(5), 8);
+    assert_eq!(ring_size_for(16), 16);
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let candidates: Vec<Candidate> = (10..20).map(|tag| candidate(tag, 5)).collect();
+    for size in [0, 1, 3, 6] {
+        assert!(matches!(
+            select_ring(member(1), member(2), &candidates, 5, size, &mut rng),
+            Err(ClientError::InvalidRingSize(s)) if s == size
+        ));
+    }
+
+    // Ineligible and duplicate candidates do not count
+    let mut few = vec![candidate(10, 5), candidate(10, 5), candidate(11, 4)];
+    few.push(candidate(1, 5));
+    assert!(matches!(
+        select_ring(member(1), member(2), &few, 5, 4, &mut rng),
+        Err(ClientError::NotEnoughDecoys { available: 1, required: 2 })
+    ));
+}
+
+#[test]
+fn test_select_ring_distribution() {
+    const ROUNDS: usize = 4000;
+    let mut rng = StdRng::seed_from_u64(42);
+    let sender = member(1);
+    let recipient = member(2);
+    let mut candidates: Vec<Candidate> = (10..18).map(|tag| candidate(tag, 5)).collect();
+    candidates.push(candidate(30, 4));
+
+    let mut sender_positions = [0usize; 4];
+    let mut recipient_positions = [0usize; 4];
+    let mut picks: HashMap<u8, usize> = HashMap::new();
+    for _ in 0..ROUNDS {
+        let ring = select_ring(sender, recipient, &candidates, 5, 4, &mut rng).unwrap();
+        assert_eq!(ring.members.len(), 4);
+        assert_eq!(ring.members[ring.sender_index], sender);
+        assert_eq!(ring.members[ring.recipient_index], recipient);
+        sender_positions[ring.sender_index] += 1;
+        recipient_positions[ring.recipient_index] += 1;
+        for decoy in &ring.members {
+            if *decoy != sender && *decoy != recipient {
+                *picks.entry(decoy.public_key[0]).or_default() += 1;
+            }
+        }
+    }
+
+    // Every position is equally likely for both real parties
+    for count in sender_positions.iter().chain(&recipient_positions) {
+        assert!((900..1100).contains(count), "position count {}", count);
+    }
+
+    // Each of the 8 eligible decoys fills 2 of 8 slots, the stale one never
+    assert_eq!(picks.len(), 8);
+    assert!(!picks.contains_key(&30));
+    for count in picks.values() {
+        assert!((900..1100).contains(count), "decoy count {}", count);
+    }
+}
@@ -0,0 +1,546 @@
+use std::{path::Path, str::FromStr};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use solana_sdk::{clock::Clock, pubkey::Pubkey};
+use zerosol_client::RingMember;
+use zerosol_solana::state::{epoch_at, GlobalState};
+
+use crate::error::IndexerError;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    block_time INTEGER
+);
+CREATE TABLE IF NOT EXISTS pools (
+    global_state TEXT PRIMARY KEY,
+    token_mint TEXT NOT NULL,
+    epoch_length INTEGER NOT NULL,
+    epoch_source INTEGER NOT NULL,
+    epoch_offset INTEGER NOT NULL,
+    fee INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS accounts (
+    zerosol_account TEXT PRIMARY KEY,
+    pending_account TEXT NOT NULL,
+    global_state TEXT NOT NULL,
+    public_key BLOB NOT NULL,
+    registered INTEGER NOT NULL,
+    locked_to TEXT,
+    closed INTEGER NOT NULL DEFAULT 0,
+    last_rollover INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS accounts_by_key ON accounts (public_key);
+CREATE TABLE IF NOT EXISTS pending_updates (
+    id INTEGER PRIMARY KEY,
+    zerosol_account TEXT NOT NULL,
+    epoch INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    amount INTEGER,
+    commitment_left BLOB,
+    commitment_right BLOB,
+    signature TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS pending_updates_by_account ON pending_updates (zerosol_account, epoch);
+CREATE TABLE IF NOT EXISTS nonces (
+    nonce_account TEXT PRIMARY KEY,
+    global_state TEXT NOT NULL,
+    nonce BLOB NOT NULL,
+    epoch INTEGER NOT NULL,
+    reclaimed INTEGER NOT NULL DEFAULT 0,
+    signature TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS rollovers (
+    zerosol_account TEXT NOT NULL,
+    epoch INTEGER NOT NULL,
+    signature TEXT NOT NULL,
+    PRIMARY KEY (zerosol_account, epoch)
+);
+CREATE TABLE IF NOT EXISTS proof_buffers (
+    address TEXT PRIMARY KEY,
+    data BLOB NOT NULL
+);
+";
+
+/// Pending update kinds
+pub const UPDATE_DEPOSIT: &str = "deposit";
+pub const UPDATE_TRANSFER: &str = "transfer";
+pub const UPDATE_FEE: &str = "fee";
+
+/// Epoch configuration of an indexed pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pool {
+    pub global_state: Pubkey,
+    pub token_mint: Pubkey,
+    pub epoch_length: u64,
+    pub epoch_source: u8,
+    pub epoch_offset: u64,
+    pub fee: u64,
+}
+
+impl Pool {
+    pub fn from_state(global_state: Pubkey, state: &GlobalState) -> Self {
+        Self {
+            global_state,
+            token_mint: state.token_mint,
+            epoch_length: state.epoch_length,
+            epoch_source: state.epoch_source,
+            epoch_offset: state.epoch_offset,
+            fee: state.fee,
+        }
+    }
+
+    /// Epoch of a transaction in `slot` at `block_time`, as the program
+    /// computes it from the clock
+    pub fn epoch_at(&self, slot: u64, block_time: i64) -> u64 {
+        let mut state = GlobalState::new(Pubkey::default(), self.token_mint, self.epoch_length, self.fee);
+        state.epoch_source = self.epoch_source;
+        state.epoch_offset = self.epoch_offset;
+        let clock = Clock {
+            slot,
+            unix_timestamp: block_time,
+            ..Clock::default()
+        };
+        epoch_at(&clock, &state)
+    }
+}
+
+/// An account as the index knows it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedAccount {
+    pub zerosol_account: Pubkey,
+    pub pending_account: Pubkey,
+    pub global_state: Pubkey,
+    pub public_key: [u8; 32],
+    pub registered: bool,
+    pub locked_to: Option<Pubkey>,
+    pub closed: bool,
+    pub last_rollover: u64,
+}
+
+impl IndexedAccount {
+    pub fn ring_member(&self) -> RingMember {
+        RingMember {
+            public_key: self.public_key,
+            zerosol_account: self.zerosol_account,
+            pending_account: self.pending_account,
+        }
+    }
+}
+
+/// Something added to an account's pending ciphertext
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingUpdate {
+    pub epoch: u64,
+    pub kind: String,
+    /// Public amount of deposits and relayer fees
+    pub amount: Option<u64>,
+    /// `C_i` and `D` of a transfer
+    pub commitments: Option<([u8; 32], [u8; 32])>,
+    pub signature: String,
+}
+
+/// SQLite store of everything the indexer reconstructed
+pub struct Database {
+    connection: Connection,
+}
+
+impl Database {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, IndexerError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, IndexerError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> Result<Self, IndexerError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Start writing one transaction; nothing is stored until `Batch::commit`
+    pub fn begin(&mut self) -> Result<Batch<'_>, IndexerError> {
+        Ok(Batch {
+            tx: self.connection.transaction()?,
+        })
+    }
+
+    pub fn is_indexed(&self, signature: &str) -> Result<bool, IndexerError> {
+        Ok(self
+            .connection
+            .query_row("SELECT 1 FROM transactions WHERE signature = ?1", [signature], |_| Ok(()))
+            .optional()?
+            .is_some())
+    }
+
+    /// Most recently indexed transaction, where RPC polling resumes
+    pub fn last_signature(&self) -> Result<Option<String>, IndexerError> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT signature FROM transactions ORDER BY slot DESC, rowid DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn pool(&self, global_state: &Pubkey) -> Result<Option<Pool>, IndexerError> {
+        query_pool(&self.connection, global_state)
+    }
+
+    pub fn account(&self, zerosol_account: &Pubkey) -> Result<Option<IndexedAccount>, IndexerError> {
+        query_account(&self.connection, zerosol_account)
+    }
+
+    /// Every account ever opened for `public_key`, for wallet sync
+    pub fn accounts_for_key(&self, public_key: &[u8; 32]) -> Result<Vec<IndexedAccount>, IndexerError> {
+        let mut statement = self
+            .connection
+            .prepare(&format!("{} WHERE public_key = ?1 ORDER BY rowid", SELECT_ACCOUNT))?;
+        let accounts = statement.query_map([&public_key[..]], account_from_row)?;
+        Ok(accounts.collect::<Result<_, _>>()?)
+    }
+
+    pub fn registered_accounts(&self, global_state: &Pubkey) -> Result<Vec<IndexedAccount>, IndexerError> {
+        let mut statement = self.connection.prepare(&format!(
+            "{} WHERE global_state = ?1 AND registered = 1 AND closed = 0 ORDER BY rowid",
+            SELECT_ACCOUNT
+        ))?;
+        let accounts = statement.query_map([global_state.to_string()], account_from_row)?;
+        Ok(accounts.collect::<Result<_, _>>()?)
+    }
+
+    /// Accounts the index considers eligible decoys in `epoch`
+    ///
+    /// Mirrors `ring::Candidate::is_eligible`: registered, open, unlocked,
+    /// rolled over in `epoch` and nothing pending since.
+    pub fn decoys(&self, global_state: &Pubkey, epoch: u64) -> Result<Vec<RingMember>, IndexerError> {
+        let mut statement = self.connection.prepare(&format!(
+            "{} WHERE global_state = ?1 AND registered = 1 AND closed = 0 AND locked_to IS NULL
+               AND last_rollover = ?2
+               AND NOT EXISTS (SELECT 1 FROM pending_updates p
+                               WHERE p.zerosol_account = accounts.zerosol_account AND p.epoch = ?2)
+             ORDER BY rowid",
+            SELECT_ACCOUNT
+        ))?;
+        let accounts = statement.query_map(params![global_state.to_string(), epoch], account_from_row)?;
+        accounts
+            .map(|account| Ok(account?.ring_member()))
+            .collect()
+    }
+
+    /// Updates made to the pending account of `zerosol_account` in `epoch`
+    pub fn pending_updates(
+        &self,
+        zerosol_account: &Pubkey,
+        epoch: u64,
+    ) -> Result<Vec<PendingUpdate>, IndexerError> {
+        let mut statement = self.connection.prepare(
+            "SELECT epoch, kind, amount, commitment_left, commitment_right, signature
+             FROM pending_updates WHERE zerosol_account = ?1 AND epoch = ?2 ORDER BY id",
+        )?;
+        let updates = statement.query_map(params![zerosol_account.to_string(), epoch], |row| {
+            let left: Option<[u8; 32]> = row.get(3)?;
+            let right: Option<[u8; 32]> = row.get(4)?;
+            Ok(PendingUpdate {
+                epoch: row.get(0)?,
+                kind: row.get(1)?,
+                amount: row.get(2)?,
+                commitments: left.zip(right),
+                signature: row.get(5)?,
+            })
+        })?;
+        Ok(updates.collect::<Result<_, _>>()?)
+    }
+
+    /// Epochs `zerosol_account` was rolled over in, oldest first
+    pub fn rollovers(&self, zerosol_account: &Pubkey) -> Result<Vec<u64>, IndexerError> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT epoch FROM rollovers WHERE zerosol_account = ?1 ORDER BY epoch")?;
+        let epochs = statement.query_map([zerosol_account.to_string()], |row| row.get(0))?;
+        Ok(epochs.collect::<Result<_, _>>()?)
+    }
+
+    /// Whether a transfer or burn consumed `nonce_account`
+    pub fn nonce_used(&self, nonce_account: &Pubkey) -> Result<bool, IndexerError> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT 1 FROM nonces WHERE nonce_account = ?1",
+                [nonce_account.to_string()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+}
+
+/// Writes of one transaction, applied atomically
+pub struct Batch<'a> {
+    tx: rusqlite::Transaction<'a>,
+}
+
+impl Batch<'_> {
+    pub fn commit(self) -> Result<(), IndexerError> {
+        Ok(self.tx.commit()?)
+    }
+
+    pub fn record_transaction(
+        &self,
+        signature: &str,
+        slot: u64,
+        block_time: Option<i64>,
+    ) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "INSERT INTO transactions (signature, slot, block_time) VALUES (?1, ?2, ?3)",
+            params![signature, slot, block_time],
+        )?;
+        Ok(())
+    }
+
+    pub fn pool(&self, global_state: &Pubkey) -> Result<Option<Pool>, IndexerError> {
+        query_pool(&self.tx, global_state)
+    }
+
+    pub fn put_pool(&self, pool: &Pool) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "INSERT OR REPLACE INTO pools (global_state, token_mint, epoch_length, epoch_source, epoch_offset, fee)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                pool.global_state.to_string(),
+                pool.token_mint.to_string(),
+                pool.epoch_length,
+                pool.epoch_source,
+                pool.epoch_offset,
+                pool.fee
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn account(&self, zerosol_account: &Pubkey) -> Result<Option<IndexedAccount>, IndexerError> {
+        query_account(&self.tx, zerosol_account)
+    }
+
+    /// Add a new account, or for one created by `FundToKey`, leave it as is
+    pub fn open_account(
+        &self,
+        zerosol_account: &Pubkey,
+        pending_account: &Pubkey,
+        global_state: &Pubkey,
+        public_key: &[u8; 32],
+        registered: bool,
+    ) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "INSERT OR IGNORE INTO accounts (zerosol_account, pending_account, global_state, public_key, registered)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                zerosol_account.to_string(),
+                pending_account.to_string(),
+                global_state.to_string(),
+                &public_key[..],
+                registered
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_registered(&self, zerosol_account: &Pubkey) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "UPDATE accounts SET registered = 1 WHERE zerosol_account = ?1",
+            [zerosol_account.to_string()],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_locked(&self, zerosol_account: &Pubkey, locked_to: Option<&Pubkey>) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "UPDATE accounts SET locked_to = ?2 WHERE zerosol_account = ?1",
+            params![zerosol_account.to_string(), locked_to.map(|key| key.to_string())],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_closed(&self, zerosol_account: &Pubkey) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "UPDATE accounts SET closed = 1 WHERE zerosol_account = ?1",
+            [zerosol_account.to_string()],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_public_key(&self, zerosol_account: &Pubkey, public_key: &[u8; 32]) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "UPDATE accounts SET public_key = ?2 WHERE zerosol_account = ?1",
+            params![zerosol_account.to_string(), &public_key[..]],
+        )?;
+        Ok(())
+    }
+
+    /// Roll `zerosol_account` over to `epoch` the way the program does before
+    /// touching an account, settling its pending updates
+    pub fn touch(&self, zerosol_account: &Pubkey, epoch: u64, signature: &str) -> Result<(), IndexerError> {
+        let rolled = self.tx.execute(
+            "UPDATE accounts SET last_rollover = ?2 WHERE zerosol_account = ?1 AND last_rollover < ?2",
+            params![zerosol_account.to_string(), epoch],
+        )?;
+        if rolled > 0 {
+            self.tx.execute(
+                "INSERT OR IGNORE INTO rollovers (zerosol_account, epoch, signature) VALUES (?1, ?2, ?3)",
+                params![zerosol_account.to_string(), epoch, signature],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn add_pending_update(
+        &self,
+        zerosol_account: &Pubkey,
+        epoch: u64,
+        kind: &str,
+        amount: Option<u64>,
+        commitments: Option<(&[u8; 32], &[u8; 32])>,
+        signature: &str,
+    ) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "INSERT INTO pending_updates
+                 (zerosol_account, epoch, kind, amount, commitment_left, commitment_right, signature)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                zerosol_account.to_string(),
+                epoch,
+                kind,
+                amount,
+                commitments.map(|(left, _)| &left[..]),
+                commitments.map(|(_, right)| &right[..]),
+                signature
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn use_nonce(
+        &self,
+        nonce_account: &Pubkey,
+        global_state: &Pubkey,
+        nonce: &[u8; 32],
+        epoch: u64,
+        signature: &str,
+    ) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "INSERT OR REPLACE INTO nonces (nonce_account, global_state, nonce, epoch, signature)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![nonce_account.to_string(), global_state.to_string(), &nonce[..], epoch, signature],
+        )?;
+        Ok(())
+    }
+
+    pub fn reclaim_nonce(&self, nonce_account: &Pubkey) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "UPDATE nonces SET reclaimed = 1 WHERE nonce_account = ?1",
+            [nonce_account.to_string()],
+        )?;
+        Ok(())
+    }
+
+    pub fn create_proof_buffer(&self, address: &Pubkey, data_len: u32) -> Result<(), IndexerError> {
+        self.tx.execute(
+            "INSERT OR REPLACE INTO proof_buffers (address, data) VALUES (?1, zeroblob(?2))",
+            params![address.to_string(), data_len],
+        )?;
+        Ok(())
+    }
+
+    pub fn write_proof_buffer(&self, address: &Pubkey, offset: u32, data: &[u8]) -> Result<(), IndexerError> {
+        if let Some(mut buffer) = self.proof_buffer(address)? {
+            let start = offset as usize;
+            if let Some(target) = buffer.get_mut(start..start + data.len()) {
+                target.copy_from_slice(data);
+                self.tx.execute(
+                    "UPDATE proof_buffers SET data = ?2 WHERE address = ?1",
+                    params![address.to_string(), buffer],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn proof_buffer(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, IndexerError> {
+        Ok(self
+            .tx
+            .query_row(
+                "SELECT data FROM proof_buffers WHERE address = ?1",
+                [address.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn close_proof_buffer(&self, address: &Pubkey) -> Result<(), IndexerError> {
+        self.tx
+            .execute("DELETE FROM proof_buffers WHERE address = ?1", [address.to_string()])?;
+        Ok(())
+    }
+}
+
+const SELECT_ACCOUNT: &str = "SELECT zerosol_account, pending_account, global_state, public_key, registered,
+    locked_to, closed, last_rollover FROM accounts";
+
+fn query_pool(connection: &Connection, global_state: &Pubkey) -> Result<Option<Pool>, IndexerError> {
+    Ok(connection
+        .query_row(
+            "SELECT global_state, token_mint, epoch_length, epoch_source, epoch_offset, fee
+             FROM pools WHERE global_state = ?1",
+            [global_state.to_string()],
+            pool_from_row,
+        )
+        .optional()?)
+}
+
+fn query_account(connection: &Connection, zerosol_account: &Pubkey) -> Result<Option<IndexedAccount>, IndexerError> {
+    Ok(connection
+        .query_row(
+            &format!("{} WHERE zerosol_account = ?1", SELECT_ACCOUNT),
+            [zerosol_account.to_string()],
+            account_from_row,
+        )
+        .optional()?)
+}
+
+fn pubkey(row: &Row, index: usize) -> rusqlite::Result<Pubkey> {
+    let text: String = row.get(index)?;
+    Pubkey::from_str(&text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+fn pool_from_row(row: &Row) -> rusqlite::Result<Pool> {
+    Ok(Pool {
+        global_state: pubkey(row, 0)?,
+        token_mint: pubkey(row, 1)?,
+        epoch_length: row.get(2)?,
+        epoch_source: row.get(3)?,
+        epoch_offset: row.get(4)?,
+        fee: row.get(5)?,
+    })
+}
+
+fn account_from_row(row: &Row) -> rusqlite::Result<IndexedAccount> {
+    let locked_to = match row.get::<_, Option<String>>(5)? {
+        Some(_) => Some(pubkey(row, 5)?),
+        None => None,
+    };
+    Ok(IndexedAccount {
+        zerosol_account: pubkey(row, 0)?,
+        pending_account: pubkey(row, 1)?,
+        global_state: pubkey(row, 2)?,
+        public_key: row.get(3)?,
+        registered: row.get(4)?,
+        locked_to,
+        closed: row.get(6)?,
+        last_rollover: row.get(7)?,
+    })
+}
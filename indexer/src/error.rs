@@ -0,0 +1,25 @@
+use std::io;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum IndexerError {
+    #[error("Database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Malformed record: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("RPC error: {0}")]
+    Rpc(Box<solana_client::client_error::ClientError>),
+    #[error("Cannot decode transaction {0}")]
+    Undecodable(String),
+    #[error("Transaction {0} has no block time but its pool counts epochs in seconds")]
+    MissingBlockTime(String),
+}
+
+impl From<solana_client::client_error::ClientError> for IndexerError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        IndexerError::Rpc(Box::new(e))
+    }
+}
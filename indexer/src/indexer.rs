@@ -0,0 +1,264 @@
+use borsh::BorshDeserialize;
+use solana_sdk::pubkey::Pubkey;
+use zerosol_solana::{
+    instruction::ZerosolInstruction,
+    state::{GlobalState, TransferPayload, EPOCH_SOURCE_SLOT},
+};
+
+use crate::{
+    db::{Batch, Database, Pool, UPDATE_DEPOSIT, UPDATE_FEE, UPDATE_TRANSFER},
+    error::IndexerError,
+    source::{IndexedInstruction, IndexedTransaction},
+};
+
+/// Replays zerosol instructions into a `Database`
+///
+/// Transactions must be fed oldest first, and only successful ones: the
+/// indexer trusts that every instruction it sees was executed. Pools are
+/// known from their `Initialize`, or from `add_pool` when indexing starts
+/// later; instructions of unknown pools are skipped.
+pub struct Indexer {
+    program_id: Pubkey,
+    db: Database,
+}
+
+impl Indexer {
+    pub fn new(program_id: Pubkey, db: Database) -> Self {
+        Self { program_id, db }
+    }
+
+    pub fn db(&self) -> &Database {
+        &self.db
+    }
+
+    /// Track a pool initialized before the first indexed transaction
+    pub fn add_pool(&mut self, global_state: Pubkey, state: &GlobalState) -> Result<(), IndexerError> {
+        let batch = self.db.begin()?;
+        batch.put_pool(&Pool::from_state(global_state, state))?;
+        batch.commit()
+    }
+
+    /// Apply `transaction`, returning false if it was already indexed
+    pub fn ingest(&mut self, transaction: &IndexedTransaction) -> Result<bool, IndexerError> {
+        if self.db.is_indexed(&transaction.signature)? {
+            return Ok(false);
+        }
+
+        let program_id = self.program_id;
+        let batch = self.db.begin()?;
+        batch.record_transaction(&transaction.signature, transaction.slot, transaction.block_time)?;
+        let context = Context {
+            batch: &batch,
+            transaction,
+        };
+        for instruction in &transaction.instructions {
+            if instruction.program_id == program_id {
+                context.apply(instruction)?;
+            }
+        }
+        batch.commit()?;
+        Ok(true)
+    }
+}
+
+struct Context<'a, 'b> {
+    batch: &'a Batch<'b>,
+    transaction: &'a IndexedTransaction,
+}
+
+impl Context<'_, '_> {
+    fn signature(&self) -> &str {
+        &self.transaction.signature
+    }
+
+    /// Epoch of the transaction in the pool at `global_state`, `None` if the
+    /// pool is not indexed
+    fn epoch(&self, global_state: &Pubkey) -> Result<Option<u64>, IndexerError> {
+        let Some(pool) = self.batch.pool(global_state)? else {
+            return Ok(None);
+        };
+        let block_time = match (pool.epoch_source, self.transaction.block_time) {
+            (EPOCH_SOURCE_SLOT, _) => 0,
+            (_, Some(block_time)) => block_time,
+            (_, None) => return Err(IndexerError::MissingBlockTime(self.transaction.signature.clone())),
+        };
+        Ok(Some(pool.epoch_at(self.transaction.slot, block_time)))
+    }
+
+    fn apply(&self, instruction: &IndexedInstruction) -> Result<(), IndexerError> {
+        // Instructions the indexer cannot decode cannot have succeeded
+        let Ok(decoded) = ZerosolInstruction::try_from_slice(&instruction.data) else {
+            return Ok(());
+        };
+        let accounts = &instruction.accounts;
+        let account = |index: usize| {
+            accounts
+                .get(index)
+                .ok_or_else(|| IndexerError::Undecodable(self.signature().to_string()))
+        };
+
+        match decoded {
+            ZerosolInstruction::Initialize {
+                epoch_length,
+                fee,
+                epoch_source,
+                epoch_offset,
+            } => {
+                self.batch.put_pool(&Pool {
+                    global_state: *account(1)?,
+                    token_mint: *account(2)?,
+                    epoch_length,
+                    epoch_source,
+                    epoch_offset,
+                    fee,
+                })?;
+            }
+            ZerosolInstruction::Register { public_key, .. } => {
+                if self.batch.pool(account(3)?)?.is_some() {
+                    self.batch
+                        .open_account(account(1)?, account(2)?, account(3)?, &public_key, true)?;
+                }
+            }
+            ZerosolInstruction::Fund { amount } => {
+                self.deposit(account(1)?, account(6)?, amount)?;
+            }
+            ZerosolInstruction::FundToKey { public_key, amount } => {
+                if self.batch.pool(account(6)?)?.is_some() {
+                    self.batch
+                        .open_account(account(1)?, account(2)?, account(6)?, &public_key, false)?;
+                }
+                self.deposit(account(1)?, account(6)?, amount)?;
+            }
+            ZerosolInstruction::ClaimAccount { .. } => {
+                self.batch.set_registered(account(1)?)?;
+            }
+            ZerosolInstruction::Transfer {
+                commitments_c,
+                commitment_d,
+                public_keys,
+                nonce,
+                beneficiary,
+                fee,
+                tip_lamports,
+                proof,
+            } => {
+                let payload = TransferPayload {
+                    commitments_c,
+                    commitment_d,
+                    public_keys,
+                    nonce,
+                    beneficiary,
+                    fee,
+                    tip_lamports,
+                    proof,
+                };
+                self.transfer(&payload, accounts.get(1..).unwrap_or_default())?;
+            }
+            ZerosolInstruction::ExecuteTransfer => {
+                let buffer = *account(1)?;
+                let payload = self
+                    .batch
+                    .proof_buffer(&buffer)?
+                    .and_then(|data| TransferPayload::try_from_slice(&data).ok());
+                if let Some(payload) = payload {
+                    self.transfer(&payload, accounts.get(2..).unwrap_or_default())?;
+                }
+                self.batch.close_proof_buffer(&buffer)?;
+            }
+            ZerosolInstruction::Burn { nonce, .. } => {
+                if let Some(epoch) = self.epoch(account(7)?)? {
+                    self.batch.touch(account(1)?, epoch, self.signature())?;
+                    self.batch
+                        .use_nonce(account(5)?, account(7)?, &nonce, epoch, self.signature())?;
+                }
+            }
+            ZerosolInstruction::RollOver => {
+                if let Some(epoch) = self.epoch(account(3)?)? {
+                    self.batch.touch(account(1)?, epoch, self.signature())?;
+                }
+            }
+            ZerosolInstruction::CloseAccount { .. } => {
+                if let Some(epoch) = self.epoch(account(3)?)? {
+                    self.batch.touch(account(0)?, epoch, self.signature())?;
+                }
+                self.batch.set_closed(account(0)?)?;
+            }
+            ZerosolInstruction::Lock { program, .. } => {
+                self.batch.set_locked(account(0)?, Some(&program))?;
+            }
+            ZerosolInstruction::Unlock { .. } => {
+                self.batch.set_locked(account(0)?, None)?;
+            }
+            ZerosolInstruction::RotateKey { new_public_key, .. } => {
+                if let Some(epoch) = self.epoch(account(2)?)? {
+                    self.batch.touch(account(0)?, epoch, self.signature())?;
+                }
+                self.batch.set_public_key(account(0)?, &new_public_key)?;
+            }
+            ZerosolInstruction::ReclaimNonces => {
+                for pair in accounts.get(1..).unwrap_or_default().chunks(2) {
+                    self.batch.reclaim_nonce(&pair[0])?;
+                }
+            }
+            ZerosolInstruction::CreateProofBuffer { data_len, .. } => {
+                self.batch.create_proof_buffer(account(1)?, data_len)?;
+            }
+            ZerosolInstruction::WriteProofChunk { offset, data } => {
+                self.batch.write_proof_buffer(account(1)?, offset, &data)?;
+            }
+            ZerosolInstruction::CloseProofBuffer => {
+                self.batch.close_proof_buffer(account(1)?)?;
+            }
+            ZerosolInstruction::SetGuardian { .. }
+            | ZerosolInstruction::SetPause { .. }
+            | ZerosolInstruction::SetWithdrawalCap { .. }
+            | ZerosolInstruction::VerifyProofStep { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn deposit(&self, zerosol_account: &Pubkey, global_state: &Pubkey, amount: u64) -> Result<(), IndexerError> {
+        if let Some(epoch) = self.epoch(global_state)? {
+            let signature = self.signature();
+            self.batch.touch(zerosol_account, epoch, signature)?;
+            self.batch
+                .add_pending_update(zerosol_account, epoch, UPDATE_DEPOSIT, Some(amount), None, signature)?;
+        }
+        Ok(())
+    }
+
+    /// Apply a transfer whose accounts, from the beneficiary on, are `accounts`
+    fn transfer(&self, payload: &TransferPayload, accounts: &[Pubkey]) -> Result<(), IndexerError> {
+        // beneficiary account, beneficiary pending, nonce, global state,
+        // system program, then the tip payer and participant pairs
+        if accounts.len() < 5 {
+            return Err(IndexerError::Undecodable(self.signature().to_string()));
+        }
+        let global_state = &accounts[3];
+        let Some(epoch) = self.epoch(global_state)? else {
+            return Ok(());
+        };
+        let signature = self.signature();
+
+        self.batch
+            .use_nonce(&accounts[2], global_state, &payload.nonce, epoch, signature)?;
+        self.batch.touch(&accounts[0], epoch, signature)?;
+        self.batch
+            .add_pending_update(&accounts[0], epoch, UPDATE_FEE, Some(payload.fee), None, signature)?;
+
+        let first = if payload.tip_lamports > 0 { 6 } else { 5 };
+        let participants = accounts.get(first..).unwrap_or_default();
+        for (pair, commitment) in participants.chunks(2).zip(&payload.commitments_c) {
+            self.batch.touch(&pair[0], epoch, signature)?;
+            self.batch.add_pending_update(
+                &pair[0],
+                epoch,
+                UPDATE_TRANSFER,
+                None,
+                Some((commitment, &payload.commitment_d)),
+                signature,
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+pub mod db;
+pub mod error;
+pub mod indexer;
+pub mod source;
+
+pub use db::Database;
+pub use error::IndexerError;
+pub use indexer::Indexer;
+pub use source::{IndexedInstruction, IndexedTransaction};
@@ -0,0 +1,111 @@
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use clap::{Parser, Subcommand};
+use gargantua_indexer::{source, Database, Indexer};
+use serde_json::json;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use zerosol_client::{account_view::fetch_state, to_hex};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Rebuilds Gargantua pool state from transactions into a SQLite database
+#[derive(Parser)]
+#[command(name = "indexer", version)]
+struct Args {
+    /// SQLite database, created if missing
+    #[arg(long, default_value = "gargantua-index.sqlite")]
+    db: PathBuf,
+
+    #[arg(long)]
+    program_id: Pubkey,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index a JSON-lines file of transactions, oldest first
+    Import { file: PathBuf },
+    /// Follow the program's transactions over RPC
+    Poll {
+        #[arg(long, env = "GARGANTUA_URL", default_value = "http://localhost:8899")]
+        url: String,
+        /// Pool initialized before the indexed history, read from the chain
+        #[arg(long)]
+        global_state: Option<Pubkey>,
+        #[arg(long, default_value_t = 5)]
+        interval_secs: u64,
+    },
+    /// Print the accounts eligible as decoys in `epoch`
+    Decoys {
+        #[arg(long)]
+        global_state: Pubkey,
+        #[arg(long)]
+        epoch: u64,
+    },
+}
+
+async fn run(args: Args) -> Result<()> {
+    let mut indexer = Indexer::new(args.program_id, Database::open(&args.db)?);
+    match args.command {
+        Command::Import { file } => {
+            let mut indexed = 0;
+            for transaction in source::read_json_lines(file)? {
+                indexed += indexer.ingest(&transaction)? as usize;
+            }
+            println!("Indexed {} transactions", indexed);
+        }
+        Command::Poll {
+            url,
+            global_state,
+            interval_secs,
+        } => {
+            let mut rpc = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+            if let Some(global_state) = global_state {
+                if indexer.db().pool(&global_state)?.is_none() {
+                    let state = fetch_state(&mut rpc, &global_state).await?;
+                    indexer.add_pool(global_state, &state)?;
+                }
+            }
+            loop {
+                let until = indexer
+                    .db()
+                    .last_signature()?
+                    .map(|signature| Signature::from_str(&signature))
+                    .transpose()?;
+                for transaction in source::poll_rpc(&rpc, &args.program_id, until).await? {
+                    if indexer.ingest(&transaction)? {
+                        println!("{} {}", transaction.slot, transaction.signature);
+                    }
+                }
+                tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+            }
+        }
+        Command::Decoys { global_state, epoch } => {
+            let decoys: Vec<_> = indexer
+                .db()
+                .decoys(&global_state, epoch)?
+                .iter()
+                .map(|member| {
+                    json!({
+                        "public_key": to_hex(&member.public_key),
+                        "zerosol_account": member.zerosol_account.to_string(),
+                        "pending_account": member.pending_account.to_string(),
+                    })
+                })
+                .collect();
+            println!("{}", json!(decoys));
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run(Args::parse()).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
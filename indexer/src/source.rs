@@ -0,0 +1,172 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{UiLoadedAddresses, UiTransactionEncoding};
+
+use crate::error::IndexerError;
+
+/// A top-level instruction with its accounts resolved
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct IndexedInstruction {
+    #[serde(with = "pubkey_string")]
+    pub program_id: Pubkey,
+    #[serde(with = "pubkey_strings")]
+    pub accounts: Vec<Pubkey>,
+    #[serde(with = "base64_bytes")]
+    pub data: Vec<u8>,
+}
+
+impl From<&Instruction> for IndexedInstruction {
+    fn from(instruction: &Instruction) -> Self {
+        Self {
+            program_id: instruction.program_id,
+            accounts: instruction.accounts.iter().map(|meta| meta.pubkey).collect(),
+            data: instruction.data.clone(),
+        }
+    }
+}
+
+/// A successful transaction, one JSON object per line in `.jsonl` files
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub instructions: Vec<IndexedInstruction>,
+}
+
+/// Read transactions from a JSON-lines file, oldest first
+pub fn read_json_lines<P: AsRef<Path>>(path: P) -> Result<Vec<IndexedTransaction>, IndexerError> {
+    let mut transactions = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            transactions.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(transactions)
+}
+
+/// Fetch the successful transactions of `program_id` after `until`, oldest first
+///
+/// Only top-level instructions are returned; instructions a program makes
+/// through CPI, such as spends of locked accounts, are not indexed.
+pub async fn poll_rpc(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    until: Option<Signature>,
+) -> Result<Vec<IndexedTransaction>, IndexerError> {
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: None,
+            commitment: Some(rpc.commitment()),
+        };
+        let page = rpc.get_signatures_for_address_with_config(program_id, config).await?;
+        let Some(last) = page.last() else { break };
+        let last = &last.signature;
+        before = Some(Signature::from_str(last).map_err(|_| IndexerError::Undecodable(last.clone()))?);
+        signatures.extend(
+            page.into_iter()
+                .filter(|status| status.err.is_none())
+                .map(|status| status.signature),
+        );
+    }
+    signatures.reverse();
+
+    let mut transactions = Vec::with_capacity(signatures.len());
+    for signature in signatures {
+        let undecodable = || IndexerError::Undecodable(signature.clone());
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(rpc.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        let parsed = Signature::from_str(&signature).map_err(|_| undecodable())?;
+        let confirmed = rpc.get_transaction_with_config(&parsed, config).await?;
+        let transaction = confirmed.transaction.transaction.decode().ok_or_else(undecodable)?;
+
+        let mut keys = transaction.message.static_account_keys().to_vec();
+        let loaded: Option<UiLoadedAddresses> = confirmed
+            .transaction
+            .meta
+            .and_then(|meta| Option::from(meta.loaded_addresses));
+        if let Some(loaded) = loaded {
+            for address in loaded.writable.iter().chain(&loaded.readonly) {
+                keys.push(Pubkey::from_str(address).map_err(|_| undecodable())?);
+            }
+        }
+        let key = |index: u8| keys.get(index as usize).copied().ok_or_else(undecodable);
+
+        let mut instructions = Vec::new();
+        for compiled in transaction.message.instructions() {
+            instructions.push(IndexedInstruction {
+                program_id: key(compiled.program_id_index)?,
+                accounts: compiled.accounts.iter().map(|&i| key(i)).collect::<Result<_, _>>()?,
+                data: compiled.data.clone(),
+            });
+        }
+        transactions.push(IndexedTransaction {
+            signature,
+            slot: confirmed.slot,
+            block_time: confirmed.block_time,
+            instructions,
+        });
+    }
+    Ok(transactions)
+}
+
+mod pubkey_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&key.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        Pubkey::from_str(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+mod pubkey_strings {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(keys: &[Pubkey], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(keys.iter().map(|key| key.to_string()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Pubkey>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|key| Pubkey::from_str(key).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+mod base64_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        STANDARD
+            .decode(String::deserialize(deserializer)?)
+            .map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,306 @@
+use std::{collections::HashSet, io::Write};
+
+use borsh::BorshDeserialize;
+use gargantua_indexer::{
+    db::{UPDATE_FEE, UPDATE_TRANSFER},
+    source, Database, IndexedInstruction, IndexedTransaction, Indexer,
+};
+use rand::rngs::OsRng;
+use solana_program_test::*;
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use zerosol_client::Wallet;
+use zerosol_solana::{
+    instruction::{initialize, roll_over, transfer},
+    state::{
+        GlobalState, InnerProductProof, NonceState, ZerosolAccount, ZerosolProof, EPOCH_SOURCE_SLOT,
+    },
+    utils::G1Point,
+};
+
+const EPOCH_SLOTS: u64 = 100;
+
+fn proof() -> ZerosolProof {
+    ZerosolProof {
+        ba: [0u8; 32],
+        bs: [0u8; 32],
+        a: [0u8; 32],
+        b: [0u8; 32],
+        cln_g: vec![],
+        crn_g: vec![],
+        c_0g: vec![],
+        dg: vec![],
+        y_0g: vec![],
+        gg: vec![],
+        c_xg: vec![],
+        y_xg: vec![],
+        f: vec![],
+        z_a: [0u8; 32],
+        t_1: [0u8; 32],
+        t_2: [0u8; 32],
+        t_hat: [0u8; 32],
+        mu: [0u8; 32],
+        c: [0u8; 32],
+        s_sk: [0u8; 32],
+        s_r: [0u8; 32],
+        s_b: [0u8; 32],
+        s_tau: [0u8; 32],
+        ip_proof: InnerProductProof {
+            l_points: vec![],
+            r_points: vec![],
+            a: [0u8; 32],
+            b: [0u8; 32],
+        },
+    }
+}
+
+/// Records the transactions of a program-test session the way an RPC node
+/// would report them
+struct Session {
+    context: ProgramTestContext,
+    log: Vec<IndexedTransaction>,
+}
+
+impl Session {
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> bool {
+        let payer = &self.context.payer;
+        let mut keypairs = vec![payer];
+        keypairs.extend_from_slice(signers);
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &keypairs, blockhash);
+        let signature = transaction.signatures[0].to_string();
+        if self.context.banks_client.process_transaction(transaction).await.is_err() {
+            return false;
+        }
+
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        self.log.push(IndexedTransaction {
+            signature,
+            slot: clock.slot,
+            block_time: Some(clock.unix_timestamp),
+            instructions: instructions.iter().map(IndexedInstruction::from).collect(),
+        });
+        true
+    }
+
+    async fn account(&mut self, address: &Pubkey) -> ZerosolAccount {
+        let account = self.context.banks_client.get_account(*address).await.unwrap().unwrap();
+        ZerosolAccount::try_from_slice(&account.data).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_replay_program_test_session() {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new(
+        "zerosol_solana",
+        program_id,
+        processor!(zerosol_solana::process_instruction),
+    );
+    let mut session = Session {
+        context: program_test.start_with_context().await,
+        log: Vec::new(),
+    };
+    let payer = session.context.payer.pubkey();
+
+    let (global_state, _) = GlobalState::find_address(&program_id);
+    assert!(
+        session
+            .send(
+                &[initialize(
+                    &program_id,
+                    &payer,
+                    &Pubkey::new_unique(),
+                    EPOCH_SLOTS,
+                    1,
+                    EPOCH_SOURCE_SLOT,
+                    0,
+                )],
+                &[],
+            )
+            .await
+    );
+
+    let mut accounts = Vec::new();
+    for _ in 0..3 {
+        let wallet = Wallet::generate(&mut OsRng);
+        let zerosol_account = Keypair::new();
+        let pending_account = Keypair::new();
+        let register = wallet.register(
+            &program_id,
+            &payer,
+            &zerosol_account.pubkey(),
+            &pending_account.pubkey(),
+        );
+        assert!(session.send(&[register], &[&zerosol_account, &pending_account]).await);
+        accounts.push((wallet, zerosol_account.pubkey(), pending_account.pubkey()));
+    }
+
+    // Epoch 2: the first two accounts roll over, the third stays behind
+    session.context.warp_to_slot(2 * EPOCH_SLOTS + 10).unwrap();
+    for (_, zerosol_account, pending_account) in &accounts[..2] {
+        let rollover = roll_over(&program_id, &payer, zerosol_account, pending_account);
+        assert!(session.send(&[rollover], &[]).await);
+    }
+
+    // A rejected transfer never reaches the log
+    let point = G1Point::generator().to_bytes();
+    let (first, first_pending) = (accounts[0].1, accounts[0].2);
+    let forged = transfer(
+        &program_id,
+        &payer,
+        &first,
+        &first_pending,
+        &[(first, first_pending), (accounts[1].1, accounts[1].2)],
+        None,
+        2,
+        vec![point, point],
+        point,
+        vec![point, point],
+        [1u8; 32],
+        accounts[0].0.public_key(),
+        1,
+        proof(),
+    );
+    assert!(!session.send(&[forged], &[]).await);
+
+    // Round-trip the session through a JSON-lines file
+    let path = std::env::temp_dir().join(format!("zerosol-index-{}.jsonl", std::process::id()));
+    let mut file = std::fs::File::create(&path).unwrap();
+    for transaction in &session.log {
+        writeln!(file, "{}", serde_json::to_string(transaction).unwrap()).unwrap();
+    }
+    drop(file);
+    let transactions = source::read_json_lines(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(transactions.len(), 6);
+
+    let mut indexer = Indexer::new(program_id, Database::open_in_memory().unwrap());
+    for transaction in &transactions {
+        assert!(indexer.ingest(transaction).unwrap());
+    }
+    // Replaying is idempotent
+    assert!(!indexer.ingest(&transactions[0]).unwrap());
+
+    // The index agrees with the chain
+    let db = indexer.db();
+    let pool = db.pool(&global_state).unwrap().unwrap();
+    assert_eq!(pool.epoch_length, EPOCH_SLOTS);
+    assert_eq!(db.registered_accounts(&global_state).unwrap().len(), 3);
+    for (wallet, zerosol_account, pending_account) in &accounts {
+        let on_chain = session.account(zerosol_account).await;
+        let indexed = db.account(zerosol_account).unwrap().unwrap();
+        assert_eq!(indexed.pending_account, *pending_account);
+        assert_eq!(indexed.public_key, wallet.public_key());
+        assert_eq!(indexed.public_key, on_chain.public_key);
+        assert_eq!(indexed.registered, on_chain.is_registered);
+        assert_eq!(indexed.last_rollover, on_chain.last_rollover);
+        assert_eq!(db.accounts_for_key(&wallet.public_key()).unwrap(), vec![indexed]);
+    }
+    assert_eq!(db.rollovers(&accounts[0].1).unwrap(), vec![2]);
+    assert!(db.rollovers(&accounts[2].1).unwrap().is_empty());
+
+    let decoys: HashSet<Pubkey> = db
+        .decoys(&global_state, 2)
+        .unwrap()
+        .iter()
+        .map(|member| member.zerosol_account)
+        .collect();
+    assert_eq!(decoys, HashSet::from([accounts[0].1, accounts[1].1]));
+    assert!(!db.nonce_used(&NonceState::find_address(&program_id, 2, &[1u8; 32]).0).unwrap());
+}
+
+#[test]
+fn test_transfer_updates() {
+    let program_id = Pubkey::new_unique();
+    let (global_state, _) = GlobalState::find_address(&program_id);
+    let mut indexer = Indexer::new(program_id, Database::open_in_memory().unwrap());
+    let mut log = Vec::new();
+    let mut record = |slot: u64, instruction: Instruction| {
+        log.push(IndexedTransaction {
+            signature: format!("sig{}", slot),
+            slot,
+            block_time: None,
+            instructions: vec![IndexedInstruction::from(&instruction)],
+        });
+    };
+
+    let authority = Pubkey::new_unique();
+    record(
+        1,
+        initialize(
+            &program_id,
+            &authority,
+            &Pubkey::new_unique(),
+            EPOCH_SLOTS,
+            1,
+            EPOCH_SOURCE_SLOT,
+            0,
+        ),
+    );
+    let wallets: Vec<Wallet> = (0..3).map(|_| Wallet::generate(&mut OsRng)).collect();
+    let members: Vec<(Pubkey, Pubkey)> = (0..3)
+        .map(|_| (Pubkey::new_unique(), Pubkey::new_unique()))
+        .collect();
+    for (i, (wallet, (zerosol_account, pending_account))) in wallets.iter().zip(&members).enumerate() {
+        record(
+            2 + i as u64,
+            wallet.register(&program_id, &authority, zerosol_account, pending_account),
+        );
+    }
+    for (i, (zerosol_account, pending_account)) in members.iter().enumerate() {
+        record(
+            EPOCH_SLOTS + i as u64,
+            roll_over(&program_id, &authority, zerosol_account, pending_account),
+        );
+    }
+
+    // Epoch 1: the first account relays a transfer between the other two
+    let c = G1Point::generator().to_bytes();
+    let d = G1Point::generator().mul(&2u64.into()).to_bytes();
+    let nonce = [3u8; 32];
+    record(
+        EPOCH_SLOTS + 10,
+        transfer(
+            &program_id,
+            &authority,
+            &members[0].0,
+            &members[0].1,
+            &members[1..],
+            None,
+            1,
+            vec![c, c],
+            d,
+            vec![wallets[1].public_key(), wallets[2].public_key()],
+            nonce,
+            wallets[0].public_key(),
+            4,
+            proof(),
+        ),
+    );
+    for transaction in &log {
+        indexer.ingest(transaction).unwrap();
+    }
+
+    let db = indexer.db();
+    let fees = db.pending_updates(&members[0].0, 1).unwrap();
+    assert_eq!(fees.len(), 1);
+    assert_eq!((fees[0].kind.as_str(), fees[0].amount), (UPDATE_FEE, Some(4)));
+    for (zerosol_account, _) in &members[1..] {
+        let updates = db.pending_updates(zerosol_account, 1).unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].kind, UPDATE_TRANSFER);
+        assert_eq!(updates[0].commitments, Some((c, d)));
+    }
+    assert!(db.nonce_used(&NonceState::find_address(&program_id, 1, &nonce).0).unwrap());
+
+    // Every account received something this epoch, so none is a decoy
+    assert!(db.decoys(&global_state, 1).unwrap().is_empty());
+    assert!(db.pending_updates(&members[0].0, 0).unwrap().is_empty());
+}
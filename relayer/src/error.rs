@@ -0,0 +1,37 @@
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+use zerosol_client::ClientError;
+
+#[derive(Error, Debug)]
+pub enum RelayError {
+    #[error("Malformed request: {0}")]
+    Malformed(String),
+    #[error("Instruction is not for this relayer's program")]
+    WrongProgram,
+    #[error("Instruction is not a transfer")]
+    NotATransfer,
+    #[error("Transfer instruction has {0} accounts, expected at least 6")]
+    MissingAccounts(usize),
+    #[error("Transfer must be signed by relayer {0}")]
+    WrongRelayer(Pubkey),
+    #[error("Transfer is for another pool")]
+    WrongPool,
+    #[error("Account {0} would have to sign; tips cannot be relayed")]
+    UnexpectedSigner(Pubkey),
+    #[error("Transfers are paused")]
+    Paused,
+    #[error("Fee {offered} is below the relayer minimum {required}")]
+    FeeTooLow { offered: u64, required: u64 },
+    #[error("Fee must be paid to the relayer's account")]
+    WrongBeneficiary,
+    #[error("Transfer was not built for the current epoch {0}")]
+    StaleEpoch(u64),
+    #[error("Nonce already used")]
+    NonceUsed,
+    #[error("Proof verification failed")]
+    InvalidProof,
+    #[error("Too many requests")]
+    RateLimited,
+    #[error(transparent)]
+    Client(#[from] ClientError),
+}
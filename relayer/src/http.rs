@@ -0,0 +1,93 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::instruction::Instruction;
+use zerosol_client::{to_hex, ClientError, Connection};
+
+use crate::{error::RelayError, policy::RateLimiter, service::Relayer};
+
+/// Body of `POST /transfer`
+#[derive(Serialize, Deserialize)]
+pub struct RelayRequest {
+    /// Base64 of the bincode-serialized `Transfer` instruction
+    pub instruction: String,
+}
+
+pub struct AppState<C> {
+    pub relayer: Relayer<C>,
+    pub limiter: Mutex<RateLimiter>,
+}
+
+/// `GET /info` describes the relayer and its fee policy, `POST /transfer`
+/// relays a transfer and returns its signature
+pub fn router<C: Connection + Clone + Sync + 'static>(state: Arc<AppState<C>>) -> Router {
+    Router::new()
+        .route("/info", get(info::<C>))
+        .route("/transfer", post(transfer::<C>))
+        .with_state(state)
+}
+
+async fn info<C: Connection + Clone>(State(state): State<Arc<AppState<C>>>) -> Json<Value> {
+    let relayer = &state.relayer;
+    let beneficiary = relayer.policy().beneficiary.map(|member| {
+        json!({
+            "public_key": to_hex(&member.public_key),
+            "zerosol_account": member.zerosol_account.to_string(),
+            "pending_account": member.pending_account.to_string(),
+        })
+    });
+    Json(json!({
+        "relayer": relayer.pubkey().to_string(),
+        "program_id": relayer.program_id().to_string(),
+        "global_state": relayer.global_state().to_string(),
+        "min_fee": relayer.policy().min_fee,
+        "beneficiary": beneficiary,
+    }))
+}
+
+async fn transfer<C: Connection + Clone + Sync>(
+    State(state): State<Arc<AppState<C>>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Json(request): Json<RelayRequest>,
+) -> Result<Json<Value>, RelayError> {
+    if !state.limiter.lock().unwrap().check(peer.ip(), Instant::now()) {
+        return Err(RelayError::RateLimited);
+    }
+
+    let bytes = STANDARD
+        .decode(&request.instruction)
+        .map_err(|e| RelayError::Malformed(e.to_string()))?;
+    let instruction: Instruction = bincode::deserialize(&bytes).map_err(|e| RelayError::Malformed(e.to_string()))?;
+
+    let signature = state.relayer.relay(instruction).await?;
+    Ok(Json(json!({ "signature": signature.to_string() })))
+}
+
+impl IntoResponse for RelayError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            RelayError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            RelayError::Client(ClientError::Simulation { .. }) => StatusCode::UNPROCESSABLE_ENTITY,
+            RelayError::Client(_) => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let mut body = json!({ "error": self.to_string() });
+        if let RelayError::Client(ClientError::Simulation { logs, .. }) = &self {
+            body["logs"] = json!(logs);
+        }
+        (status, Json(body)).into_response()
+    }
+}
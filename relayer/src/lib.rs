@@ -0,0 +1,8 @@
+pub mod error;
+pub mod http;
+pub mod policy;
+pub mod service;
+
+pub use error::RelayError;
+pub use policy::{FeePolicy, RateLimiter};
+pub use service::Relayer;
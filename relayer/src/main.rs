@@ -0,0 +1,125 @@
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use gargantua_relayer::{
+    http::{router, AppState},
+    FeePolicy, RateLimiter, Relayer,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::read_keypair_file};
+use zerosol_client::{from_hex, RingMember};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// HTTP relayer for Gargantua transfers
+///
+/// Submitting a transfer from the sender's own fee payer would link the payer
+/// to the ring; the relayer pays instead and is paid back by the transfer fee.
+#[derive(Parser)]
+#[command(name = "relayer", version)]
+struct Args {
+    /// RPC endpoint
+    #[arg(long, env = "GARGANTUA_URL", default_value = "http://localhost:8899")]
+    url: String,
+
+    /// Keypair signing and paying for relayed transfers
+    #[arg(long, env = "GARGANTUA_RELAYER_KEYPAIR")]
+    keypair: PathBuf,
+
+    #[arg(long)]
+    program_id: Pubkey,
+
+    /// Global state of the pool to relay for
+    #[arg(long)]
+    global_state: Pubkey,
+
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+
+    /// Smallest fee accepted, on top of the pool minimum
+    #[arg(long, default_value_t = 0)]
+    min_fee: u64,
+
+    /// Hex public key the fee must be paid to
+    #[arg(long, requires_all = ["beneficiary_account", "beneficiary_pending"])]
+    beneficiary: Option<String>,
+
+    /// Zerosol account of the beneficiary
+    #[arg(long)]
+    beneficiary_account: Option<Pubkey>,
+
+    /// Pending account of the beneficiary
+    #[arg(long)]
+    beneficiary_pending: Option<Pubkey>,
+
+    /// Requests a client may send at once
+    #[arg(long, default_value_t = 10)]
+    burst: u32,
+
+    /// Seconds for a client to earn one more request, 0 to disable the limit
+    #[arg(long, default_value_t = 6)]
+    refill_secs: u64,
+}
+
+fn parse_hex32(s: &str) -> Result<[u8; 32]> {
+    from_hex(s)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("`{}` is not a 32-byte hex key", s).into())
+}
+
+async fn run(args: Args) -> Result<()> {
+    let keypair = read_keypair_file(&args.keypair)
+        .map_err(|e| format!("cannot read keypair {}: {}", args.keypair.display(), e))?;
+    let beneficiary = match (args.beneficiary, args.beneficiary_account, args.beneficiary_pending) {
+        (Some(key), Some(zerosol_account), Some(pending_account)) => Some(RingMember {
+            public_key: parse_hex32(&key)?,
+            zerosol_account,
+            pending_account,
+        }),
+        _ => None,
+    };
+    let policy = FeePolicy {
+        min_fee: args.min_fee,
+        beneficiary,
+    };
+
+    let rpc = Arc::new(RpcClient::new_with_commitment(args.url, CommitmentConfig::confirmed()));
+    let relayer = Relayer::new(rpc, keypair, args.program_id, args.global_state, policy);
+    let state = Arc::new(AppState {
+        relayer,
+        limiter: Mutex::new(RateLimiter::new(args.burst, Duration::from_secs(args.refill_secs))),
+    });
+
+    let pruned = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            pruned.limiter.lock().unwrap().prune(Instant::now());
+        }
+    });
+
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    println!("Relaying for {} on {}", args.global_state, args.listen);
+    axum::serve(
+        listener,
+        router(state).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run(Args::parse()).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use zerosol_client::RingMember;
+
+/// Fees the relayer accepts
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FeePolicy {
+    /// Smallest fee relayed; the pool's own minimum applies if it is higher
+    pub min_fee: u64,
+    /// Account the fee must be paid to, any account if `None`
+    pub beneficiary: Option<RingMember>,
+}
+
+impl FeePolicy {
+    /// The fee a transfer must pay in a pool whose minimum is `pool_fee`
+    pub fn required_fee(&self, pool_fee: u64) -> u64 {
+        self.min_fee.max(pool_fee)
+    }
+}
+
+/// Per-client token bucket
+///
+/// Each client may send `burst` requests at once, then one more every
+/// `interval`. A zero `interval` disables the limit.
+pub struct RateLimiter {
+    burst: u32,
+    interval: Duration,
+    buckets: HashMap<IpAddr, (u32, Instant)>,
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, interval: Duration) -> Self {
+        Self {
+            burst,
+            interval,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Take a token for `client` at `now`, returning false if it has none left
+    pub fn check(&mut self, client: IpAddr, now: Instant) -> bool {
+        if self.interval.is_zero() {
+            return true;
+        }
+        let (tokens, refilled) = self.buckets.entry(client).or_insert((self.burst, now));
+        let elapsed = now.saturating_duration_since(*refilled);
+        let earned = (elapsed.as_nanos() / self.interval.as_nanos()).min(u32::MAX as u128) as u32;
+        if earned > 0 {
+            *tokens = tokens.saturating_add(earned).min(self.burst);
+            *refilled = if *tokens == self.burst {
+                now
+            } else {
+                *refilled + self.interval * earned
+            };
+        }
+        if *tokens == 0 {
+            return false;
+        }
+        *tokens -= 1;
+        true
+    }
+
+    /// Forget clients whose bucket has refilled, bounding memory use
+    pub fn prune(&mut self, now: Instant) {
+        let interval = self.interval;
+        let burst = self.burst;
+        self.buckets.retain(|_, (tokens, refilled)| {
+            now.saturating_duration_since(*refilled) < interval * (burst - *tokens)
+        });
+    }
+}
@@ -0,0 +1,158 @@
+use borsh::BorshDeserialize;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use zerosol_client::{account_view::fetch_state, Connection};
+use zerosol_solana::{
+    instruction::ZerosolInstruction,
+    processor::verify_transfer_proof,
+    state::{epoch_at, GlobalState, NonceState, TransferPayload, PAUSE_TRANSFER},
+};
+
+use crate::{error::RelayError, policy::FeePolicy};
+
+/// Accounts every transfer starts with, before the tip payer and participants
+const TRANSFER_ACCOUNTS: usize = 6;
+
+/// Checks transfers built for this relayer and submits them, paying the
+/// transaction fee and the nonce account's rent
+///
+/// Each call works on its own clone of the connection, so concurrent
+/// requests share one `Relayer` without waiting on each other.
+pub struct Relayer<C> {
+    connection: C,
+    keypair: Keypair,
+    program_id: Pubkey,
+    global_state: Pubkey,
+    policy: FeePolicy,
+}
+
+impl<C: Connection + Clone> Relayer<C> {
+    pub fn new(connection: C, keypair: Keypair, program_id: Pubkey, global_state: Pubkey, policy: FeePolicy) -> Self {
+        Self {
+            connection,
+            keypair,
+            program_id,
+            global_state,
+            policy,
+        }
+    }
+
+    /// The address clients pass as the transfer's relayer
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    pub fn global_state(&self) -> &Pubkey {
+        &self.global_state
+    }
+
+    pub fn policy(&self) -> &FeePolicy {
+        &self.policy
+    }
+
+    /// Check that `instruction` is a transfer this relayer accepts and that
+    /// its proof verifies for the current epoch, without submitting it
+    pub async fn check(&self, instruction: &Instruction) -> Result<u64, RelayError> {
+        if instruction.program_id != self.program_id {
+            return Err(RelayError::WrongProgram);
+        }
+        let payload = match ZerosolInstruction::try_from_slice(&instruction.data) {
+            Ok(ZerosolInstruction::Transfer {
+                commitments_c,
+                commitment_d,
+                public_keys,
+                nonce,
+                beneficiary,
+                fee,
+                tip_lamports,
+                proof,
+            }) => TransferPayload {
+                commitments_c,
+                commitment_d,
+                public_keys,
+                nonce,
+                beneficiary,
+                fee,
+                tip_lamports,
+                proof,
+            },
+            _ => return Err(RelayError::NotATransfer),
+        };
+
+        let accounts = &instruction.accounts;
+        if accounts.len() < TRANSFER_ACCOUNTS {
+            return Err(RelayError::MissingAccounts(accounts.len()));
+        }
+        if accounts[0].pubkey != self.pubkey() || !accounts[0].is_signer {
+            return Err(RelayError::WrongRelayer(self.pubkey()));
+        }
+        if accounts[4].pubkey != self.global_state {
+            return Err(RelayError::WrongPool);
+        }
+        // A tip payer would have to sign the transaction the relayer pays for
+        if let Some(signer) = accounts[1..].iter().find(|a| a.is_signer) {
+            return Err(RelayError::UnexpectedSigner(signer.pubkey));
+        }
+
+        let mut connection = self.connection.clone();
+        let global_state: GlobalState = fetch_state(&mut connection, &self.global_state).await?;
+        if global_state.is_paused(PAUSE_TRANSFER) {
+            return Err(RelayError::Paused);
+        }
+        let required = self.policy.required_fee(global_state.fee);
+        if payload.fee < required {
+            return Err(RelayError::FeeTooLow {
+                offered: payload.fee,
+                required,
+            });
+        }
+        if let Some(beneficiary) = self.policy.beneficiary {
+            if payload.beneficiary != beneficiary.public_key
+                || accounts[1].pubkey != beneficiary.zerosol_account
+                || accounts[2].pubkey != beneficiary.pending_account
+            {
+                return Err(RelayError::WrongBeneficiary);
+            }
+        }
+
+        // The nonce account is derived from the epoch the proof was built for
+        let clock = connection.get_clock().await?;
+        let epoch = epoch_at(&clock, &global_state);
+        let (nonce_address, _) = NonceState::find_address(&self.program_id, epoch, &payload.nonce);
+        if accounts[3].pubkey != nonce_address {
+            return Err(RelayError::StaleEpoch(epoch));
+        }
+        if connection
+            .get_account_data(&nonce_address)
+            .await?
+            .is_some_and(|data| !data.is_empty())
+        {
+            return Err(RelayError::NonceUsed);
+        }
+
+        if !verify_transfer_proof(&payload, epoch) {
+            return Err(RelayError::InvalidProof);
+        }
+        Ok(epoch)
+    }
+
+    /// Check `instruction`, then sign, simulate and submit it
+    pub async fn relay(&self, instruction: Instruction) -> Result<Signature, RelayError> {
+        self.check(&instruction).await?;
+
+        let mut connection = self.connection.clone();
+        let blockhash = connection.get_latest_blockhash().await?;
+        let transaction =
+            Transaction::new_signed_with_payer(&[instruction], Some(&self.pubkey()), &[&self.keypair], blockhash);
+        connection.simulate_transaction(&transaction).await?;
+        Ok(connection.send_transaction(transaction).await?)
+    }
+}
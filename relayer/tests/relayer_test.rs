@@ -0,0 +1,223 @@
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    time::{Duration, Instant},
+};
+
+use borsh::BorshSerialize;
+use gargantua_relayer::{FeePolicy, RateLimiter, RelayError, Relayer};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use zerosol_client::RingMember;
+use zerosol_solana::{
+    instruction::{initialize, set_pause, transfer},
+    state::{GlobalState, InnerProductProof, NonceState, ZerosolProof, EPOCH_SOURCE_SLOT},
+    utils::G1Point,
+};
+
+const USED_NONCE: [u8; 32] = [9u8; 32];
+
+fn proof() -> ZerosolProof {
+    ZerosolProof {
+        ba: [0u8; 32],
+        bs: [0u8; 32],
+        a: [0u8; 32],
+        b: [0u8; 32],
+        cln_g: vec![],
+        crn_g: vec![],
+        c_0g: vec![],
+        dg: vec![],
+        y_0g: vec![],
+        gg: vec![],
+        c_xg: vec![],
+        y_xg: vec![],
+        f: vec![],
+        z_a: [0u8; 32],
+        t_1: [0u8; 32],
+        t_2: [0u8; 32],
+        t_hat: [0u8; 32],
+        mu: [0u8; 32],
+        c: [1u8; 32],
+        s_sk: [0u8; 32],
+        s_r: [0u8; 32],
+        s_b: [0u8; 32],
+        s_tau: [0u8; 32],
+        ip_proof: InnerProductProof {
+            l_points: vec![],
+            r_points: vec![],
+            a: [0u8; 32],
+            b: [0u8; 32],
+        },
+    }
+}
+
+struct Pool {
+    program_id: Pubkey,
+    global_state: Pubkey,
+    beneficiary: RingMember,
+}
+
+impl Pool {
+    /// Transfer through `relayer` whose proof does not verify
+    fn transfer(
+        &self,
+        relayer: &Pubkey,
+        epoch: u64,
+        nonce: [u8; 32],
+        fee: u64,
+        tip: Option<(Pubkey, u64)>,
+    ) -> Instruction {
+        let point = G1Point::generator().to_bytes();
+        transfer(
+            &self.program_id,
+            relayer,
+            &self.beneficiary.zerosol_account,
+            &self.beneficiary.pending_account,
+            &[
+                (Pubkey::new_unique(), Pubkey::new_unique()),
+                (Pubkey::new_unique(), Pubkey::new_unique()),
+            ],
+            tip,
+            epoch,
+            vec![point, point],
+            point,
+            vec![point, point],
+            nonce,
+            self.beneficiary.public_key,
+            fee,
+            proof(),
+        )
+    }
+}
+
+/// A pool with a minimum fee of 1 whose epoch 0 lasts the whole test, and
+/// `USED_NONCE` already consumed in it
+async fn start() -> (Relayer<BanksClient>, Pool) {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new(
+        "zerosol_solana",
+        program_id,
+        processor!(zerosol_solana::process_instruction),
+    );
+    let (nonce_address, bump) = NonceState::find_address(&program_id, 0, &USED_NONCE);
+    let mut nonce_state = NonceState::new(USED_NONCE, 0, Pubkey::new_unique(), bump);
+    nonce_state.used = true;
+    program_test.add_account(
+        nonce_address,
+        Account {
+            lamports: 1_000_000,
+            data: nonce_state.try_to_vec().unwrap(),
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let (global_state, _) = GlobalState::find_address(&program_id);
+    let transaction = Transaction::new_signed_with_payer(
+        &[initialize(
+            &program_id,
+            &payer.pubkey(),
+            &Pubkey::new_unique(),
+            1_000_000,
+            1,
+            EPOCH_SOURCE_SLOT,
+            0,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let pool = Pool {
+        program_id,
+        global_state,
+        beneficiary: RingMember {
+            public_key: G1Point::generator().to_bytes(),
+            zerosol_account: Pubkey::new_unique(),
+            pending_account: Pubkey::new_unique(),
+        },
+    };
+    let policy = FeePolicy {
+        min_fee: 5,
+        beneficiary: Some(pool.beneficiary),
+    };
+    let relayer_keypair = Keypair::from_bytes(&payer.to_bytes()).unwrap();
+    let relayer = Relayer::new(banks_client, relayer_keypair, program_id, pool.global_state, policy);
+    (relayer, pool)
+}
+
+#[tokio::test]
+async fn test_relayer_rejects_transfers() {
+    let (relayer, pool) = start().await;
+    let me = relayer.pubkey();
+
+    let mut other_program = pool.transfer(&me, 0, [1u8; 32], 5, None);
+    other_program.program_id = Pubkey::new_unique();
+    assert!(matches!(relayer.check(&other_program).await, Err(RelayError::WrongProgram)));
+
+    let pause = set_pause(&pool.program_id, &me, true, 0);
+    assert!(matches!(relayer.check(&pause).await, Err(RelayError::NotATransfer)));
+
+    let other_relayer = pool.transfer(&Pubkey::new_unique(), 0, [1u8; 32], 5, None);
+    assert!(matches!(
+        relayer.check(&other_relayer).await,
+        Err(RelayError::WrongRelayer(key)) if key == me
+    ));
+
+    let tipped = pool.transfer(&me, 0, [1u8; 32], 5, Some((Pubkey::new_unique(), 10)));
+    assert!(matches!(relayer.check(&tipped).await, Err(RelayError::UnexpectedSigner(_))));
+
+    // The relayer's minimum applies above the pool's
+    let cheap = pool.transfer(&me, 0, [1u8; 32], 4, None);
+    assert!(matches!(
+        relayer.check(&cheap).await,
+        Err(RelayError::FeeTooLow { offered: 4, required: 5 })
+    ));
+
+    let mut redirected = pool.transfer(&me, 0, [1u8; 32], 5, None);
+    redirected.accounts[1].pubkey = Pubkey::new_unique();
+    assert!(matches!(relayer.check(&redirected).await, Err(RelayError::WrongBeneficiary)));
+
+    let stale = pool.transfer(&me, 1, [1u8; 32], 5, None);
+    assert!(matches!(relayer.check(&stale).await, Err(RelayError::StaleEpoch(0))));
+
+    let replayed = pool.transfer(&me, 0, USED_NONCE, 5, None);
+    assert!(matches!(relayer.check(&replayed).await, Err(RelayError::NonceUsed)));
+
+    // Everything else is in order, so the proof is what fails, before any
+    // transaction is sent
+    let forged = pool.transfer(&me, 0, [1u8; 32], 5, None);
+    assert!(matches!(relayer.relay(forged).await, Err(RelayError::InvalidProof)));
+}
+
+#[test]
+fn test_rate_limiter() {
+    let client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    let start = Instant::now();
+    let mut limiter = RateLimiter::new(2, Duration::from_secs(10));
+
+    assert!(limiter.check(client, start));
+    assert!(limiter.check(client, start));
+    assert!(!limiter.check(client, start + Duration::from_secs(9)));
+    // Clients have separate buckets
+    assert!(limiter.check(other, start));
+
+    // One token per interval, up to the burst
+    assert!(limiter.check(client, start + Duration::from_secs(10)));
+    assert!(!limiter.check(client, start + Duration::from_secs(11)));
+    assert!(limiter.check(client, start + Duration::from_secs(100)));
+    assert!(limiter.check(client, start + Duration::from_secs(100)));
+    assert!(!limiter.check(client, start + Duration::from_secs(100)));
+
+    let mut unlimited = RateLimiter::new(0, Duration::ZERO);
+    assert!((0..100).all(|_| unlimited.check(client, start)));
+}
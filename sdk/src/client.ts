@@ -33,6 +33,7 @@ import {
   createBurnInstruction,
   createRolloverInstruction,
 } from './instructions';
+import { combineBytes } from './utils';
 
 export interface GargantuaClientConfig {
   programId?: PublicKey;
@@ -68,12 +69,10 @@ export class GargantuaClient {
     epochLength: number = 3600,
     fee: number = 1000
   ): Promise<string> {
-    const globalStateKeypair = Keypair.generate();
-    
     const instruction = createInitializeInstruction(
       this.programId,
       authority.publicKey,
-      globalStateKeypair.publicKey,
+      await this.getGlobalStateAddress(),
       tokenMint,
       epochLength,
       fee
@@ -81,10 +80,7 @@ export class GargantuaClient {
 
     const transaction = new Transaction().add(instruction);
     
-    return await this.sendAndConfirmTransaction(
-      transaction,
-      [authority, globalStateKeypair]
-    );
+    return await this.sendAndConfirmTransaction(transaction, [authority]);
   }
 
   /**
@@ -95,8 +91,13 @@ export class GargantuaClient {
     const zerosolAccountKeypair = Keypair.generate();
     const pendingAccountKeypair = Keypair.generate();
 
-    // Generate Schnorr signature for registration
-    const message = this.programId.toBytes();
+    // Sign "zerosol_register" || program id || zerosol account || payer
+    const message = combineBytes(
+      new TextEncoder().encode('zerosol_register'),
+      this.programId.toBytes(),
+      zerosolAccountKeypair.publicKey.toBytes(),
+      payer.publicKey.toBytes()
+    );
     const signature = account.signMessage(message);
 
     const instruction = createRegisterInstruction(
@@ -429,6 +430,9 @@ export class GargantuaClient {
       publicKey: Array.from(data.slice(64, 96)),
       lastRollover: data.readBigUInt64LE(96),
       isRegistered: data.readUInt8(104) === 1,
+      lockedTo: new PublicKey(data.slice(105, 137)),
+      actionNonce: data.readBigUInt64LE(137),
+      pendingAccount: new PublicKey(data.slice(145, 177)),
     };
   }
 
@@ -19,8 +19,8 @@ export const DEFAULT_RANGE_BITS = 32;
 export const MAX_RANGE_BITS = 64;
 
 // Account sizes (in bytes)
-export const GLOBAL_STATE_SIZE = 96;
-export const ZEROSOL_ACCOUNT_SIZE = 105;
+export const GLOBAL_STATE_SIZE = 164;
+export const ZEROSOL_ACCOUNT_SIZE = 177;
 export const PENDING_ACCOUNT_SIZE = 64;
 export const NONCE_STATE_SIZE = 41;
 
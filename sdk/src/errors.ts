@@ -1,3 +1,5 @@
+import { PROGRAM_ERRORS } from './programErrors';
+
 export enum ErrorCode {
   // General errors
   INVALID_ARGUMENT = 'INVALID_ARGUMENT',
@@ -9,6 +11,9 @@ export enum ErrorCode {
   ACCOUNT_ALREADY_REGISTERED = 'ACCOUNT_ALREADY_REGISTERED',
   ACCOUNT_NOT_FOUND = 'ACCOUNT_NOT_FOUND',
   INVALID_ACCOUNT_DATA = 'INVALID_ACCOUNT_DATA',
+  WRONG_ACCOUNT_OWNER = 'WRONG_ACCOUNT_OWNER',
+  INVALID_PDA = 'INVALID_PDA',
+  MINT_MISMATCH = 'MINT_MISMATCH',
   
   // Proof errors
   INVALID_PROOF_STRUCTURE = 'INVALID_PROOF_STRUCTURE',
@@ -27,21 +32,33 @@ export enum ErrorCode {
   // Epoch errors
   INVALID_EPOCH = 'INVALID_EPOCH',
   EPOCH_TRANSITION_ERROR = 'EPOCH_TRANSITION_ERROR',
+  STALE_EPOCH = 'STALE_EPOCH',
   
   // Nonce errors
   NONCE_ALREADY_USED = 'NONCE_ALREADY_USED',
   INVALID_NONCE = 'INVALID_NONCE',
+
+  // Admin errors
+  POOL_PAUSED = 'POOL_PAUSED',
+  WITHDRAWAL_CAP_EXCEEDED = 'WITHDRAWAL_CAP_EXCEEDED',
+  UNAUTHORIZED = 'UNAUTHORIZED',
+  ACCOUNT_NOT_EMPTY = 'ACCOUNT_NOT_EMPTY',
+  ACCOUNT_LOCKED = 'ACCOUNT_LOCKED',
+  INVALID_KEY_ROTATION_PROOF = 'INVALID_KEY_ROTATION_PROOF',
 }
 
 export class GargantuaError extends Error {
   public readonly code: ErrorCode;
   public readonly details?: any;
+  /** Program error code, when the error came from the program */
+  public readonly programCode?: number;
 
-  constructor(message: string, code: ErrorCode, details?: any) {
+  constructor(message: string, code: ErrorCode, details?: any, programCode?: number) {
     super(message);
     this.name = 'GargantuaError';
     this.code = code;
     this.details = details;
+    this.programCode = programCode;
 
     // Maintains proper stack trace for where our error was thrown (only available on V8)
     if (Error.captureStackTrace) {
@@ -55,7 +72,9 @@ export class GargantuaError extends Error {
   static fromProgramError(error: any): GargantuaError {
     if (error.code !== undefined) {
       const code = mapProgramErrorToCode(error.code);
-      return new GargantuaError(error.message || 'Program error', code, error);
+      const info = PROGRAM_ERRORS[error.code];
+      const message = info ? `${info.name}: ${info.message}` : error.message || 'Program error';
+      return new GargantuaError(message, code, error, error.code);
     }
 
     return new GargantuaError(
@@ -81,6 +100,7 @@ export class GargantuaError extends Error {
       message: this.message,
       code: this.code,
       details: this.details,
+      programCode: this.programCode,
       stack: this.stack,
     };
   }
@@ -119,7 +139,7 @@ function mapProgramErrorToCode(programErrorCode: number): ErrorCode {
       return ErrorCode.INVALID_ACCOUNT_DATA;
     case 13:
       return ErrorCode.INVALID_PROOF_STRUCTURE;
-    case 14:
+    case 14: // retired, range proofs now fail with 31
       return ErrorCode.RANGE_PROOF_FAILED;
     case 15:
       return ErrorCode.PROOF_VERIFICATION_FAILED;
@@ -133,6 +153,28 @@ function mapProgramErrorToCode(programErrorCode: number): ErrorCode {
       return ErrorCode.INVALID_PROOF_STRUCTURE;
     case 20:
       return ErrorCode.EPOCH_TRANSITION_ERROR;
+    case 21:
+      return ErrorCode.POOL_PAUSED;
+    case 22:
+      return ErrorCode.WITHDRAWAL_CAP_EXCEEDED;
+    case 23:
+      return ErrorCode.UNAUTHORIZED;
+    case 24:
+      return ErrorCode.ACCOUNT_NOT_EMPTY;
+    case 25:
+      return ErrorCode.ACCOUNT_LOCKED;
+    case 26:
+      return ErrorCode.INVALID_KEY_ROTATION_PROOF;
+    case 27:
+      return ErrorCode.WRONG_ACCOUNT_OWNER;
+    case 28:
+      return ErrorCode.INVALID_PDA;
+    case 29:
+      return ErrorCode.MINT_MISMATCH;
+    case 30:
+      return ErrorCode.STALE_EPOCH;
+    case 31:
+      return ErrorCode.RANGE_PROOF_FAILED;
     default:
       return ErrorCode.TRANSACTION_FAILED;
   }
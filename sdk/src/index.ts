@@ -4,6 +4,7 @@ export * from './proof';
 export * from './types';
 export * from './utils';
 export * from './errors';
+export * from './programErrors';
 export * from './constants';
 
 // Re-export commonly used types from dependencies
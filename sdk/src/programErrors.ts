@@ -0,0 +1,44 @@
+// Generated from `ZerosolError` by tests/error_codes_test.rs. Do not edit.
+// Regenerate with `UPDATE_ERRORS=1 cargo test --test error_codes_test`.
+
+export interface ProgramErrorInfo {
+  name: string;
+  message: string;
+  /** The program no longer returns this code */
+  retired?: boolean;
+}
+
+export const PROGRAM_ERRORS: Record<number, ProgramErrorInfo> = {
+  0: { name: 'InvalidInstruction', message: 'Invalid instruction' },
+  1: { name: 'AccountNotRegistered', message: 'Account not registered' },
+  2: { name: 'AccountAlreadyRegistered', message: 'Account already registered' },
+  3: { name: 'InvalidRegistrationSignature', message: 'Invalid registration signature' },
+  4: { name: 'TransferAmountOutOfRange', message: 'Transfer amount out of range' },
+  5: { name: 'NonceAlreadySeen', message: 'Nonce already seen' },
+  6: { name: 'TransferProofVerificationFailed', message: 'Transfer proof verification failed' },
+  7: { name: 'BurnProofVerificationFailed', message: 'Burn proof verification failed' },
+  8: { name: 'InnerProductProofVerificationFailed', message: 'Inner product proof verification failed' },
+  9: { name: 'SigmaProtocolChallengeFailed', message: 'Sigma protocol challenge equality failure' },
+  10: { name: 'InvalidEpoch', message: 'Invalid epoch' },
+  11: { name: 'InsufficientFunds', message: 'Insufficient funds' },
+  12: { name: 'InvalidAccountData', message: 'Invalid account data' },
+  13: { name: 'InvalidProofStructure', message: 'Invalid proof structure' },
+  14: { name: 'RangeProofVerificationFailed', message: 'Range proof verification failed', retired: true },
+  15: { name: 'ConstraintSystemVerificationFailed', message: 'Constraint system verification failed' },
+  16: { name: 'BalanceConservationFailed', message: 'Balance conservation check failed' },
+  17: { name: 'PolynomialEvaluationFailed', message: 'Polynomial evaluation verification failed' },
+  18: { name: 'ArithmeticConstraintFailed', message: 'Arithmetic constraint verification failed' },
+  19: { name: 'InvalidCommitment', message: 'Invalid commitment' },
+  20: { name: 'EpochTransitionError', message: 'Epoch transition error' },
+  21: { name: 'PoolPaused', message: 'Pool operation is paused' },
+  22: { name: 'WithdrawalCapExceeded', message: 'Per-epoch withdrawal cap exceeded' },
+  23: { name: 'Unauthorized', message: 'Signer is not authorized for this operation' },
+  24: { name: 'AccountNotEmpty', message: 'Account balance is not zero' },
+  25: { name: 'AccountLocked', message: 'Account is locked to another program' },
+  26: { name: 'InvalidKeyRotationProof', message: 'Key rotation proof verification failed' },
+  27: { name: 'WrongAccountOwner', message: 'Account is not owned by the expected program' },
+  28: { name: 'InvalidPda', message: 'Account is not the expected program address' },
+  29: { name: 'MintMismatch', message: 'Token account mint does not match the pool' },
+  30: { name: 'StaleEpoch', message: 'Proof was verified in an earlier epoch' },
+  31: { name: 'RangeProofFailed', message: 'Range proof failed' },
+};
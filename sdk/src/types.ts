@@ -16,6 +16,9 @@ export interface ZerosolAccount {
   publicKey: number[];
   lastRollover: bigint;
   isRegistered: boolean;
+  lockedTo: PublicKey;
+  actionNonce: bigint;
+  pendingAccount: PublicKey;
 }
 
 export interface PendingAccount {
@@ -27,6 +30,8 @@ export interface NonceState {
   nonce: number[];
   epoch: bigint;
   used: boolean;
+  payer: PublicKey;
+  bump: number;
 }
 
 // Cryptographic types
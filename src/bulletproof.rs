@@ -615,7 +615,7 @@ impl OptimizedBulletproofVerifier {
             }
             
             // Execute batch operation
-            let _batch_result = ops.execute_batch();
+            let _batch_result = ops.execute_batch()?;
         }
         
         for ((commitment, proof, bit_length), coeff) in proofs.iter().zip(coefficients.iter()) {
@@ -0,0 +1,240 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT, ristretto::RistrettoPoint, scalar::Scalar,
+    traits::Identity,
+};
+use thiserror::Error;
+
+use crate::{
+    state::{PendingAccount, ZerosolAccount},
+    utils::G1Point,
+};
+
+/// Balances are 32-bit; see `utils::MAX_TRANSFER_AMOUNT`
+const BALANCE_BITS: u8 = 32;
+
+/// File magic of a serialized `BabyStepTable`
+const TABLE_MAGIC: &[u8; 8] = b"zsbstab1";
+
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum BalanceError {
+    #[error("Ciphertext is not a valid point")]
+    InvalidCiphertext,
+    #[error("Balance is not in [0, 2^32); wrong key?")]
+    OutOfRange,
+}
+
+/// Decrypted balances of an account
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AccountBalance {
+    pub spendable: u64,  // usable by a proof in the current epoch
+    pub pending: i64,    // received minus spent this epoch, merged at the next rollover
+}
+
+/// Baby steps `g^j` for `j` in `[0, 2^bits)`, keyed by the first 8 bytes of
+/// the compressed point
+///
+/// Solving a discrete log takes up to `2^(32 - bits)` giant steps, so larger
+/// tables trade memory (about 16 bytes per entry) for faster decryption.
+pub struct BabyStepTable {
+    bits: u8,
+    steps: HashMap<u64, u32>,
+    /// Later steps whose truncated key is already in `steps`; rare, but
+    /// likely somewhere in a table of `2^32` steps
+    collisions: HashMap<u64, Vec<u32>>,
+}
+
+impl BabyStepTable {
+    pub const DEFAULT_BITS: u8 = 16;
+
+    /// Compute a table of `2^bits` baby steps; `bits` is clamped to `1..=32`
+    pub fn new(bits: u8) -> Self {
+        let bits = bits.clamp(1, BALANCE_BITS);
+        let mut table = Self::with_capacity(bits);
+        let mut point = RistrettoPoint::identity();
+        for j in 0..1u64 << bits {
+            table.insert(Self::key(&point), j as u32);
+            point += RISTRETTO_BASEPOINT_POINT;
+        }
+
+        table
+    }
+
+    fn with_capacity(bits: u8) -> Self {
+        Self {
+            bits,
+            steps: HashMap::with_capacity(1usize << bits),
+            collisions: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, key: u64, j: u32) {
+        match self.steps.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(j);
+            }
+            Entry::Occupied(_) => self.collisions.entry(key).or_default().push(j),
+        }
+    }
+
+    /// Every baby step as `(key, j)`
+    fn entries(&self) -> impl Iterator<Item = (u64, u32)> + '_ {
+        let collisions = self.collisions.iter().flat_map(|(key, js)| js.iter().map(move |j| (*key, *j)));
+        self.steps.iter().map(|(key, j)| (*key, *j)).chain(collisions)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Find `b` in `[0, 2^32)` with `g^b == point`
+    pub fn solve(&self, point: &RistrettoPoint) -> Option<u64> {
+        let giant_step = -(RISTRETTO_BASEPOINT_POINT * Scalar::from(1u64 << self.bits));
+        let giant_steps = 1u64 << (BALANCE_BITS - self.bits);
+
+        let mut current = *point;
+        for i in 0..giant_steps {
+            let key = Self::key(&current);
+            let colliding = self.collisions.get(&key).into_iter().flatten();
+            for &j in self.steps.get(&key).into_iter().chain(colliding) {
+                let candidate = (i << self.bits) + j as u64;
+                // Keys are truncated, so confirm the match
+                if RISTRETTO_BASEPOINT_POINT * Scalar::from(candidate) == *point {
+                    return Some(candidate);
+                }
+            }
+            current += giant_step;
+        }
+
+        None
+    }
+
+    /// Find `b` in `(-2^32, 2^32)` with `g^b == point`
+    ///
+    /// Pending balances go negative when an account spends: burns and
+    /// outgoing transfers add `g^-amount` to them.
+    pub fn solve_signed(&self, point: &RistrettoPoint) -> Option<i64> {
+        self.solve(point)
+            .map(|b| b as i64)
+            .or_else(|| self.solve(&-point).map(|b| -(b as i64)))
+    }
+
+    /// Serialize the table: magic, `bits`, then the key of every baby step in
+    /// order
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut keys = vec![0u64; 1usize << self.bits];
+        for (key, j) in self.entries() {
+            keys[j as usize] = key;
+        }
+
+        writer.write_all(TABLE_MAGIC)?;
+        writer.write_all(&[self.bits])?;
+        for key in keys {
+            writer.write_all(&key.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read a table written by `write_to`
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        let mut bits = [0u8; 1];
+        reader.read_exact(&mut bits)?;
+        let bits = bits[0];
+        if &magic != TABLE_MAGIC || bits == 0 || bits > BALANCE_BITS {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a baby-step table"));
+        }
+
+        let mut table = Self::with_capacity(bits);
+        let mut key = [0u8; 8];
+        for j in 0..1u64 << bits {
+            reader.read_exact(&mut key)?;
+            table.insert(u64::from_le_bytes(key), j as u32);
+        }
+
+        Ok(table)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// Load the table at `path`, or compute it and save it there
+    pub fn load_or_create<P: AsRef<Path>>(path: P, bits: u8) -> io::Result<Self> {
+        match Self::load(&path) {
+            Ok(table) if table.bits == bits.clamp(1, BALANCE_BITS) => Ok(table),
+            _ => {
+                let table = Self::new(bits);
+                table.save(&path)?;
+                Ok(table)
+            }
+        }
+    }
+
+    fn key(point: &RistrettoPoint) -> u64 {
+        let bytes = point.compress().to_bytes();
+        u64::from_le_bytes(bytes[..8].try_into().unwrap())
+    }
+}
+
+/// Decrypt `(CLn, CRn)` to `g^b = CLn / CRn^sk`
+pub fn decrypt(
+    secret_key: &Scalar,
+    commitment_left: &[u8; 32],
+    commitment_right: &[u8; 32],
+) -> Result<RistrettoPoint, BalanceError> {
+    let left = G1Point::from_bytes(commitment_left).map_err(|_| BalanceError::InvalidCiphertext)?;
+    let right = G1Point::from_bytes(commitment_right).map_err(|_| BalanceError::InvalidCiphertext)?;
+    Ok(left.point - right.point * secret_key)
+}
+
+/// Decrypt a ciphertext to its balance
+pub fn decrypt_balance(
+    table: &BabyStepTable,
+    secret_key: &Scalar,
+    commitment_left: &[u8; 32],
+    commitment_right: &[u8; 32],
+) -> Result<u64, BalanceError> {
+    let point = decrypt(secret_key, commitment_left, commitment_right)?;
+    table.solve(&point).ok_or(BalanceError::OutOfRange)
+}
+
+/// Balances of an account as the program sees them in `current_epoch`
+///
+/// If the account has not been rolled over this epoch, the program merges
+/// the pending balance into the main balance before any proof is checked, so
+/// the pending balance counts as spendable.
+pub fn account_balance(
+    table: &BabyStepTable,
+    secret_key: &Scalar,
+    zerosol_account: &ZerosolAccount,
+    pending_account: &PendingAccount,
+    current_epoch: u64,
+) -> Result<AccountBalance, BalanceError> {
+    let main = decrypt(secret_key, &zerosol_account.commitment_left, &zerosol_account.commitment_right)?;
+    let pending = decrypt(secret_key, &pending_account.commitment_left, &pending_account.commitment_right)?;
+
+    let (spendable, pending) = if zerosol_account.last_rollover < current_epoch {
+        (main + pending, RistrettoPoint::identity())
+    } else {
+        (main, pending)
+    };
+
+    Ok(AccountBalance {
+        spendable: table.solve(&spendable).ok_or(BalanceError::OutOfRange)?,
+        pending: table.solve_signed(&pending).ok_or(BalanceError::OutOfRange)?,
+    })
+}
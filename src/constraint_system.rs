@@ -116,7 +116,7 @@ impl R1CSVerifier {
             
             // Create Pedersen commitment
             let commitment = if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
-                G1Point { point: ops.pedersen_commit(&witness_val, &blinding) }
+                G1Point { point: ops.pedersen_commit(&witness_val, &blinding)? }
             } else {
                 let g = G1Point::generator();
                 let h = crate::utils::get_h_generator();
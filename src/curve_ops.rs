@@ -5,11 +5,176 @@ use curve25519_dalek::{
     traits::{Identity, VartimeMultiscalarMul},
 };
 use sha2::{Digest, Sha256};
-use solana_program::program_error::ProgramError;
+use solana_program::{msg, program_error::ProgramError};
 use std::collections::HashMap;
 
 use crate::utils::G1Point;
 
+/// Ristretto group operations used by `G1Point` and `CurveOpsManager`
+///
+/// An operation fails only if the backend rejects its input encoding.
+pub trait CurveBackend {
+    fn add(a: &RistrettoPoint, b: &RistrettoPoint) -> Result<RistrettoPoint, ProgramError>;
+    fn mul(point: &RistrettoPoint, scalar: &Scalar) -> Result<RistrettoPoint, ProgramError>;
+    fn multiscalar_mul(scalars: &[Scalar], points: &[RistrettoPoint]) -> Result<RistrettoPoint, ProgramError>;
+}
+
+/// Group operations computed in-process with curve25519-dalek
+pub struct DalekBackend;
+
+impl CurveBackend for DalekBackend {
+    fn add(a: &RistrettoPoint, b: &RistrettoPoint) -> Result<RistrettoPoint, ProgramError> {
+        Ok(a + b)
+    }
+
+    fn mul(point: &RistrettoPoint, scalar: &Scalar) -> Result<RistrettoPoint, ProgramError> {
+        Ok(point * scalar)
+    }
+
+    fn multiscalar_mul(scalars: &[Scalar], points: &[RistrettoPoint]) -> Result<RistrettoPoint, ProgramError> {
+        if scalars.len() != points.len() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(RistrettoPoint::vartime_multiscalar_mul(scalars.iter(), points.iter()))
+    }
+}
+
+/// Curve id and group operation codes of the `sol_curve_*` syscalls
+const CURVE25519_RISTRETTO: u64 = 1;
+const CURVE_OP_ADD: u64 = 0;
+const CURVE_OP_MUL: u64 = 2;
+
+/// Group operations delegated to the runtime's `sol_curve_group_op` and
+/// `sol_curve_multiscalar_mul` syscalls
+///
+/// Off-chain the syscalls are replaced by a host implementation with the
+/// runtime's semantics, so the encoding of inputs and results is exercised
+/// by host tests as well.
+pub struct SyscallBackend;
+
+impl SyscallBackend {
+    #[cfg(target_os = "solana")]
+    fn group_op(op: u64, left: &[u8; 32], right: &[u8; 32]) -> Option<[u8; 32]> {
+        let mut result = [0u8; 32];
+        let status = unsafe {
+            solana_program::syscalls::sol_curve_group_op(
+                CURVE25519_RISTRETTO,
+                op,
+                left.as_ptr(),
+                right.as_ptr(),
+                result.as_mut_ptr(),
+            )
+        };
+        if status == 0 {
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(target_os = "solana")]
+    fn multiscalar(scalars: &[[u8; 32]], points: &[[u8; 32]]) -> Option<[u8; 32]> {
+        let mut result = [0u8; 32];
+        let status = unsafe {
+            solana_program::syscalls::sol_curve_multiscalar_mul(
+                CURVE25519_RISTRETTO,
+                scalars.as_ptr() as *const u8,
+                points.as_ptr() as *const u8,
+                points.len() as u64,
+                result.as_mut_ptr(),
+            )
+        };
+        if status == 0 {
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn group_op(op: u64, left: &[u8; 32], right: &[u8; 32]) -> Option<[u8; 32]> {
+        // `MUL` takes the scalar as its left input
+        let result = match op {
+            CURVE_OP_ADD => Self::decompress(left)? + Self::decompress(right)?,
+            CURVE_OP_MUL => {
+                let scalar: Option<Scalar> = Scalar::from_canonical_bytes(*left).into();
+                Self::decompress(right)? * scalar?
+            }
+            _ => return None,
+        };
+        Some(result.compress().to_bytes())
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn multiscalar(scalars: &[[u8; 32]], points: &[[u8; 32]]) -> Option<[u8; 32]> {
+        if scalars.len() != points.len() {
+            return None;
+        }
+
+        let mut decoded_scalars = Vec::with_capacity(scalars.len());
+        for bytes in scalars {
+            let scalar: Option<Scalar> = Scalar::from_canonical_bytes(*bytes).into();
+            decoded_scalars.push(scalar?);
+        }
+        let mut decoded_points = Vec::with_capacity(points.len());
+        for bytes in points {
+            decoded_points.push(Self::decompress(bytes)?);
+        }
+
+        let result = RistrettoPoint::vartime_multiscalar_mul(decoded_scalars, decoded_points);
+        Some(result.compress().to_bytes())
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn decompress(bytes: &[u8; 32]) -> Option<RistrettoPoint> {
+        CompressedRistretto(*bytes).decompress()
+    }
+
+    /// Decode a syscall result; the inputs are encodings of valid points and
+    /// canonical scalars, so a failure means the runtime rejected them
+    fn result_point(result: Option<[u8; 32]>) -> Result<RistrettoPoint, ProgramError> {
+        match result.and_then(|bytes| CompressedRistretto(bytes).decompress()) {
+            Some(point) => Ok(point),
+            None => {
+                msg!("Curve syscall rejected its input");
+                Err(ProgramError::InvalidArgument)
+            }
+        }
+    }
+}
+
+impl CurveBackend for SyscallBackend {
+    fn add(a: &RistrettoPoint, b: &RistrettoPoint) -> Result<RistrettoPoint, ProgramError> {
+        let left = a.compress().to_bytes();
+        let right = b.compress().to_bytes();
+        Self::result_point(Self::group_op(CURVE_OP_ADD, &left, &right))
+    }
+
+    fn mul(point: &RistrettoPoint, scalar: &Scalar) -> Result<RistrettoPoint, ProgramError> {
+        let right = point.compress().to_bytes();
+        Self::result_point(Self::group_op(CURVE_OP_MUL, &scalar.to_bytes(), &right))
+    }
+
+    fn multiscalar_mul(scalars: &[Scalar], points: &[RistrettoPoint]) -> Result<RistrettoPoint, ProgramError> {
+        if scalars.len() != points.len() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if points.is_empty() {
+            return Ok(RistrettoPoint::identity());
+        }
+
+        let scalars: Vec<[u8; 32]> = scalars.iter().map(|s| s.to_bytes()).collect();
+        let points: Vec<[u8; 32]> = points.iter().map(|p| p.compress().to_bytes()).collect();
+        Self::result_point(Self::multiscalar(&scalars, &points))
+    }
+}
+
+/// Backend used by the program: syscalls on-chain, dalek on the host
+#[cfg(target_os = "solana")]
+pub type Backend = SyscallBackend;
+#[cfg(not(target_os = "solana"))]
+pub type Backend = DalekBackend;
+
 /// Precomputed table for faster scalar multiplication
 pub struct PrecomputedTable {
     /// Base point for the table
@@ -110,23 +275,35 @@ impl CurveOpsManager {
     }
     
     /// Fast scalar multiplication using precomputed tables
-    pub fn fast_scalar_mul(&self, point: &RistrettoPoint, scalar: &Scalar) -> RistrettoPoint {
+    pub fn fast_scalar_mul(&self, point: &RistrettoPoint, scalar: &Scalar) -> Result<RistrettoPoint, ProgramError> {
+        // The syscall is cheaper than any table walk in SBF
+        if cfg!(target_os = "solana") {
+            return Backend::mul(point, scalar);
+        }
+
         if *point == RISTRETTO_BASEPOINT_POINT {
-            self.generator_table.scalar_mul(scalar)
+            Ok(self.generator_table.scalar_mul(scalar))
         } else if *point == self.h_generator_table.base {
-            self.h_generator_table.scalar_mul(scalar)
+            Ok(self.h_generator_table.scalar_mul(scalar))
         } else {
             // Use standard multiplication for other points
-            point * scalar
+            Backend::mul(point, scalar)
         }
     }
     
     /// Optimized Pedersen commitment
-    pub fn pedersen_commit(&self, value: &Scalar, blinding: &Scalar) -> RistrettoPoint {
+    pub fn pedersen_commit(&self, value: &Scalar, blinding: &Scalar) -> Result<RistrettoPoint, ProgramError> {
+        if cfg!(target_os = "solana") {
+            return Backend::multiscalar_mul(
+                &[*value, *blinding],
+                &[self.generator_table.base, self.h_generator_table.base],
+            );
+        }
+
         // Use precomputed tables for both generators
         let value_part = self.generator_table.scalar_mul(value);
         let blinding_part = self.h_generator_table.scalar_mul(blinding);
-        value_part + blinding_part
+        Ok(value_part + blinding_part)
     }
     
     /// Add operation to batch buffer
@@ -135,34 +312,33 @@ impl CurveOpsManager {
     }
     
     /// Execute batched multi-scalar multiplication
-    pub fn execute_batch(&mut self) -> RistrettoPoint {
+    pub fn execute_batch(&mut self) -> Result<RistrettoPoint, ProgramError> {
         if self.batch_buffer.is_empty() {
-            return RistrettoPoint::identity();
+            return Ok(RistrettoPoint::identity());
         }
         
         let (scalars, points): (Vec<Scalar>, Vec<RistrettoPoint>) = 
             self.batch_buffer.drain(..).unzip();
         
-        // Use Dalek's optimized vartime multiscalar multiplication
-        RistrettoPoint::vartime_multiscalar_mul(scalars, points)
+        Backend::multiscalar_mul(&scalars, &points)
     }
     
     /// Optimized point addition with caching
-    pub fn cached_point_add(&mut self, p1: &RistrettoPoint, p2: &RistrettoPoint) -> RistrettoPoint {
+    pub fn cached_point_add(&mut self, p1: &RistrettoPoint, p2: &RistrettoPoint) -> Result<RistrettoPoint, ProgramError> {
         let key = self.compute_cache_key(p1, p2);
         
         if let Some(&cached_result) = self.point_cache.get(&key) {
-            return cached_result;
+            return Ok(cached_result);
         }
         
-        let result = p1 + p2;
+        let result = Backend::add(p1, p2)?;
         
         // Cache the result if we have space
         if self.point_cache.len() < 1000 {
             self.point_cache.insert(key, result);
         }
         
-        result
+        Ok(result)
     }
     
     /// Compute cache key for two points
@@ -202,8 +378,7 @@ impl CurveOpsManager {
             return Ok(RistrettoPoint::identity());
         }
         
-        // Use Dalek's optimized vartime multiscalar multiplication
-        Ok(RistrettoPoint::vartime_multiscalar_mul(coefficients.iter().cloned(), points.iter().cloned()))
+        Backend::multiscalar_mul(coefficients, points)
     }
     
     /// Clear the point cache
@@ -233,7 +408,7 @@ impl SpecializedOps {
         }
         
         for i in 0..commitments.len() {
-            let expected = ops_manager.pedersen_commit(&values[i], &blindings[i]);
+            let expected = ops_manager.pedersen_commit(&values[i], &blindings[i])?;
             if commitments[i] != expected {
                 return Ok(false);
             }
@@ -415,7 +590,7 @@ mod tests {
         let value = Scalar::from(42u64);
         let blinding = Scalar::from(123u64);
         
-        let commitment1 = manager.pedersen_commit(&value, &blinding);
+        let commitment1 = manager.pedersen_commit(&value, &blinding).unwrap();
         let commitment2 = RISTRETTO_BASEPOINT_POINT * value + manager.h_generator_table.base * blinding;
         
         assert_eq!(commitment1, commitment2);
@@ -428,12 +603,54 @@ mod tests {
         manager.add_to_batch(Scalar::from(1u64), RISTRETTO_BASEPOINT_POINT);
         manager.add_to_batch(Scalar::from(2u64), RISTRETTO_BASEPOINT_POINT);
         
-        let result = manager.execute_batch();
+        let result = manager.execute_batch().unwrap();
         let expected = RISTRETTO_BASEPOINT_POINT * Scalar::from(3u64);
         
         assert_eq!(result, expected);
     }
 
+    // Agreement with the runtime's syscalls is checked against the SBF build
+    // in tests/syscall_backend_test.rs; on the host this covers the encoding
+    #[test]
+    fn test_syscall_encoding() {
+        let scalars: Vec<Scalar> = (0u8..8)
+            .map(|i| Scalar::from_bytes_mod_order(Sha256::digest([i]).into()))
+            .chain([Scalar::zero(), Scalar::one()])
+            .collect();
+        let points: Vec<RistrettoPoint> = scalars
+            .iter()
+            .map(|s| RISTRETTO_BASEPOINT_POINT * s)
+            .collect();
+
+        // `MUL` takes the scalar on the left
+        let point = points[3].compress().to_bytes();
+        assert_eq!(
+            SyscallBackend::group_op(CURVE_OP_MUL, &scalars[2].to_bytes(), &point),
+            Some((points[3] * scalars[2]).compress().to_bytes())
+        );
+        assert_eq!(
+            SyscallBackend::multiscalar_mul(&scalars, &points).unwrap(),
+            RistrettoPoint::vartime_multiscalar_mul(scalars.iter(), points.iter())
+        );
+        assert_eq!(SyscallBackend::multiscalar_mul(&[], &[]).unwrap(), RistrettoPoint::identity());
+
+        // Rejected inputs are errors, not panics
+        let invalid_point = [0xffu8; 32];
+        let non_canonical_scalar = [0xffu8; 32];
+        assert_eq!(
+            SyscallBackend::result_point(SyscallBackend::group_op(CURVE_OP_ADD, &invalid_point, &point)),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            SyscallBackend::result_point(SyscallBackend::group_op(CURVE_OP_MUL, &non_canonical_scalar, &point)),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            SyscallBackend::multiscalar_mul(&scalars[..2], &points[..1]),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
     #[test]
     fn test_batch_invert() {
         let scalars = vec![
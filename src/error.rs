@@ -1,54 +1,178 @@
 use solana_program::program_error::ProgramError;
 use thiserror::Error;
 
-#[derive(Error, Debug, Copy, Clone)]
+/// Program errors, returned as `ProgramError::Custom(code)`
+///
+/// Codes are part of the client interface: new variants take the next free
+/// code and existing ones never change.
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
 pub enum ZerosolError {
     #[error("Invalid instruction")]
-    InvalidInstruction,
+    InvalidInstruction = 0,
     #[error("Account not registered")]
-    AccountNotRegistered,
+    AccountNotRegistered = 1,
     #[error("Account already registered")]
-    AccountAlreadyRegistered,
+    AccountAlreadyRegistered = 2,
     #[error("Invalid registration signature")]
-    InvalidRegistrationSignature,
+    InvalidRegistrationSignature = 3,
     #[error("Transfer amount out of range")]
-    TransferAmountOutOfRange,
+    TransferAmountOutOfRange = 4,
     #[error("Nonce already seen")]
-    NonceAlreadySeen,
+    NonceAlreadySeen = 5,
     #[error("Transfer proof verification failed")]
-    TransferProofVerificationFailed,
+    TransferProofVerificationFailed = 6,
     #[error("Burn proof verification failed")]
-    BurnProofVerificationFailed,
+    BurnProofVerificationFailed = 7,
     #[error("Inner product proof verification failed")]
-    InnerProductProofVerificationFailed,
+    InnerProductProofVerificationFailed = 8,
     #[error("Sigma protocol challenge equality failure")]
-    SigmaProtocolChallengeFailed,
+    SigmaProtocolChallengeFailed = 9,
     #[error("Invalid epoch")]
-    InvalidEpoch,
+    InvalidEpoch = 10,
     #[error("Insufficient funds")]
-    InsufficientFunds,
+    InsufficientFunds = 11,
     #[error("Invalid account data")]
-    InvalidAccountData,
+    InvalidAccountData = 12,
     #[error("Invalid proof structure")]
-    InvalidProofStructure,
+    InvalidProofStructure = 13,
+    /// No longer returned, range proofs fail with `RangeProofFailed`; kept so
+    /// the code is not reused
     #[error("Range proof verification failed")]
-    RangeProofVerificationFailed,
+    RangeProofVerificationFailed = 14,
     #[error("Constraint system verification failed")]
-    ConstraintSystemVerificationFailed,
+    ConstraintSystemVerificationFailed = 15,
     #[error("Balance conservation check failed")]
-    BalanceConservationFailed,
+    BalanceConservationFailed = 16,
     #[error("Polynomial evaluation verification failed")]
-    PolynomialEvaluationFailed,
+    PolynomialEvaluationFailed = 17,
     #[error("Arithmetic constraint verification failed")]
-    ArithmeticConstraintFailed,
+    ArithmeticConstraintFailed = 18,
     #[error("Invalid commitment")]
-    InvalidCommitment,
+    InvalidCommitment = 19,
     #[error("Epoch transition error")]
-    EpochTransitionError,
+    EpochTransitionError = 20,
+    #[error("Pool operation is paused")]
+    PoolPaused = 21,
+    #[error("Per-epoch withdrawal cap exceeded")]
+    WithdrawalCapExceeded = 22,
+    #[error("Signer is not authorized for this operation")]
+    Unauthorized = 23,
+    #[error("Account balance is not zero")]
+    AccountNotEmpty = 24,
+    #[error("Account is locked to another program")]
+    AccountLocked = 25,
+    #[error("Key rotation proof verification failed")]
+    InvalidKeyRotationProof = 26,
+    #[error("Account is not owned by the expected program")]
+    WrongAccountOwner = 27,
+    #[error("Account is not the expected program address")]
+    InvalidPda = 28,
+    #[error("Token account mint does not match the pool")]
+    MintMismatch = 29,
+    #[error("Proof was verified in an earlier epoch")]
+    StaleEpoch = 30,
+    /// `which` is logged by the program and reported by preflight checks;
+    /// clients that only see the code get the generic message
+    #[error("Range proof failed")]
+    RangeProofFailed { which: RangeProofTarget } = 31,
+}
+
+/// The commitment a failed range proof was checked for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RangeProofTarget {
+    /// `commitments_c[i]` of a transfer
+    Commitment(usize),
+    /// Commitment D of a transfer, or the amount of a burn
+    Amount,
+}
+
+impl ZerosolError {
+    /// One error per code, in code order, for generating client error tables
+    pub const ALL: [ZerosolError; 32] = [
+        ZerosolError::InvalidInstruction,
+        ZerosolError::AccountNotRegistered,
+        ZerosolError::AccountAlreadyRegistered,
+        ZerosolError::InvalidRegistrationSignature,
+        ZerosolError::TransferAmountOutOfRange,
+        ZerosolError::NonceAlreadySeen,
+        ZerosolError::TransferProofVerificationFailed,
+        ZerosolError::BurnProofVerificationFailed,
+        ZerosolError::InnerProductProofVerificationFailed,
+        ZerosolError::SigmaProtocolChallengeFailed,
+        ZerosolError::InvalidEpoch,
+        ZerosolError::InsufficientFunds,
+        ZerosolError::InvalidAccountData,
+        ZerosolError::InvalidProofStructure,
+        ZerosolError::RangeProofVerificationFailed,
+        ZerosolError::ConstraintSystemVerificationFailed,
+        ZerosolError::BalanceConservationFailed,
+        ZerosolError::PolynomialEvaluationFailed,
+        ZerosolError::ArithmeticConstraintFailed,
+        ZerosolError::InvalidCommitment,
+        ZerosolError::EpochTransitionError,
+        ZerosolError::PoolPaused,
+        ZerosolError::WithdrawalCapExceeded,
+        ZerosolError::Unauthorized,
+        ZerosolError::AccountNotEmpty,
+        ZerosolError::AccountLocked,
+        ZerosolError::InvalidKeyRotationProof,
+        ZerosolError::WrongAccountOwner,
+        ZerosolError::InvalidPda,
+        ZerosolError::MintMismatch,
+        ZerosolError::StaleEpoch,
+        ZerosolError::RangeProofFailed {
+            which: RangeProofTarget::Amount,
+        },
+    ];
+
+    /// The stable code clients see in `ProgramError::Custom`
+    pub fn code(&self) -> u32 {
+        match self {
+            ZerosolError::InvalidInstruction => 0,
+            ZerosolError::AccountNotRegistered => 1,
+            ZerosolError::AccountAlreadyRegistered => 2,
+            ZerosolError::InvalidRegistrationSignature => 3,
+            ZerosolError::TransferAmountOutOfRange => 4,
+            ZerosolError::NonceAlreadySeen => 5,
+            ZerosolError::TransferProofVerificationFailed => 6,
+            ZerosolError::BurnProofVerificationFailed => 7,
+            ZerosolError::InnerProductProofVerificationFailed => 8,
+            ZerosolError::SigmaProtocolChallengeFailed => 9,
+            ZerosolError::InvalidEpoch => 10,
+            ZerosolError::InsufficientFunds => 11,
+            ZerosolError::InvalidAccountData => 12,
+            ZerosolError::InvalidProofStructure => 13,
+            ZerosolError::RangeProofVerificationFailed => 14,
+            ZerosolError::ConstraintSystemVerificationFailed => 15,
+            ZerosolError::BalanceConservationFailed => 16,
+            ZerosolError::PolynomialEvaluationFailed => 17,
+            ZerosolError::ArithmeticConstraintFailed => 18,
+            ZerosolError::InvalidCommitment => 19,
+            ZerosolError::EpochTransitionError => 20,
+            ZerosolError::PoolPaused => 21,
+            ZerosolError::WithdrawalCapExceeded => 22,
+            ZerosolError::Unauthorized => 23,
+            ZerosolError::AccountNotEmpty => 24,
+            ZerosolError::AccountLocked => 25,
+            ZerosolError::InvalidKeyRotationProof => 26,
+            ZerosolError::WrongAccountOwner => 27,
+            ZerosolError::InvalidPda => 28,
+            ZerosolError::MintMismatch => 29,
+            ZerosolError::StaleEpoch => 30,
+            ZerosolError::RangeProofFailed { .. } => 31,
+        }
+    }
+
+    /// Codes kept only so they are never reused; the program no longer
+    /// returns them
+    pub fn is_retired(&self) -> bool {
+        matches!(self, ZerosolError::RangeProofVerificationFailed)
+    }
 }
 
 impl From<ZerosolError> for ProgramError {
     fn from(e: ZerosolError) -> Self {
-        ProgramError::Custom(e as u32)
+        ProgramError::Custom(e.code())
     }
-}
\ No newline at end of file
+}
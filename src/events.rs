@@ -0,0 +1,167 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+/// A program event, logged as `DISCRIMINATOR || borsh(event)` through `sol_log_data`
+///
+/// Discriminators are the first 8 bytes of `sha256("zerosol:event:<Name>")`
+/// and must never change once released.
+pub trait Event: BorshSerialize {
+    const DISCRIMINATOR: [u8; 8];
+
+    fn emit(&self) {
+        let mut data = Self::DISCRIMINATOR.to_vec();
+        // Serializing into a Vec cannot fail
+        self.serialize(&mut data).unwrap();
+        sol_log_data(&[&data]);
+    }
+}
+
+/// An account was registered
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct Registered {
+    pub account: Pubkey,
+    pub public_key: [u8; 32],
+}
+
+impl Event for Registered {
+    const DISCRIMINATOR: [u8; 8] = [211, 11, 60, 139, 171, 137, 150, 94];
+}
+
+/// Tokens were deposited into an account's pending balance
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct Funded {
+    pub account: Pubkey,
+    pub amount: u64,
+    pub pending_left: [u8; 32],      // pending ciphertext after the deposit
+    pub pending_right: [u8; 32],
+}
+
+impl Event for Funded {
+    const DISCRIMINATOR: [u8; 8] = [233, 236, 205, 75, 30, 23, 150, 48];
+}
+
+/// An anonymous transfer added `(commitments_c[i], commitment_d)` to the
+/// pending balance of `accounts[i]`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct Transferred {
+    pub accounts: Vec<Pubkey>,
+    pub public_keys: Vec<[u8; 32]>, // anonymity set
+    pub commitments_c: Vec<[u8; 32]>,
+    pub commitment_d: [u8; 32],
+    pub nonce: [u8; 32],
+    pub epoch: u64,
+    pub beneficiary: [u8; 32],
+    pub fee: u64,
+}
+
+impl Event for Transferred {
+    const DISCRIMINATOR: [u8; 8] = [179, 224, 37, 54, 89, 7, 120, 230];
+}
+
+/// Tokens were withdrawn from an account
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct Burned {
+    pub account: Pubkey,
+    pub amount: u64,
+    pub nonce: [u8; 32],
+    pub epoch: u64,
+    pub pending_left: [u8; 32],      // pending ciphertext after the withdrawal
+    pub pending_right: [u8; 32],
+}
+
+impl Event for Burned {
+    const DISCRIMINATOR: [u8; 8] = [234, 83, 80, 127, 166, 251, 147, 33];
+}
+
+/// An account's pending balance was merged into its main balance
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct RolledOver {
+    pub account: Pubkey,
+    pub epoch: u64,
+    pub commitment_left: [u8; 32],   // main ciphertext after the rollover
+    pub commitment_right: [u8; 32],
+}
+
+impl Event for RolledOver {
+    const DISCRIMINATOR: [u8; 8] = [121, 250, 252, 82, 173, 183, 199, 182];
+}
+
+/// An account moved to a new key; carries the re-encrypted main ciphertext
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct KeyRotated {
+    pub account: Pubkey,
+    pub old_public_key: [u8; 32],
+    pub new_public_key: [u8; 32],
+    pub epoch: u64,
+    pub commitment_left: [u8; 32],
+    pub commitment_right: [u8; 32],
+}
+
+impl Event for KeyRotated {
+    const DISCRIMINATOR: [u8; 8] = [212, 97, 114, 137, 98, 119, 39, 182];
+}
+
+/// Global configuration changed; carries the resulting configuration
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct ConfigChanged {
+    pub signer: Pubkey,
+    pub guardian: Pubkey,
+    pub paused: bool,
+    pub pause_flags: u8,
+    pub burn_cap_per_epoch: u64,
+}
+
+impl Event for ConfigChanged {
+    const DISCRIMINATOR: [u8; 8] = [227, 96, 176, 107, 7, 30, 89, 221];
+}
+
+/// Any event emitted by the program
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZerosolEvent {
+    Registered(Registered),
+    Funded(Funded),
+    Transferred(Transferred),
+    Burned(Burned),
+    RolledOver(RolledOver),
+    ConfigChanged(ConfigChanged),
+    KeyRotated(KeyRotated),
+}
+
+impl ZerosolEvent {
+    /// Decode the payload of one `Program data:` log entry (after base64
+    /// decoding). Returns `None` for data that is not a Zerosol event.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+
+        let (discriminator, mut payload) = data.split_at(8);
+        let event = match discriminator {
+            d if d == Registered::DISCRIMINATOR => {
+                Self::Registered(Registered::deserialize(&mut payload).ok()?)
+            }
+            d if d == Funded::DISCRIMINATOR => Self::Funded(Funded::deserialize(&mut payload).ok()?),
+            d if d == Transferred::DISCRIMINATOR => {
+                Self::Transferred(Transferred::deserialize(&mut payload).ok()?)
+            }
+            d if d == Burned::DISCRIMINATOR => Self::Burned(Burned::deserialize(&mut payload).ok()?),
+            d if d == RolledOver::DISCRIMINATOR => {
+                Self::RolledOver(RolledOver::deserialize(&mut payload).ok()?)
+            }
+            d if d == ConfigChanged::DISCRIMINATOR => {
+                Self::ConfigChanged(ConfigChanged::deserialize(&mut payload).ok()?)
+            }
+            d if d == KeyRotated::DISCRIMINATOR => {
+                Self::KeyRotated(KeyRotated::deserialize(&mut payload).ok()?)
+            }
+            _ => return None,
+        };
+
+        // Trailing bytes mean a layout mismatch
+        if !payload.is_empty() {
+            return None;
+        }
+
+        Some(event)
+    }
+}
@@ -1,21 +1,40 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
-use crate::state::{ZerosolProof, BurnProof};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+use crate::state::{
+    find_token_authority_address, GlobalState, NonceState, ProofBuffer, TransferPayload, ZerosolProof,
+    BurnProof, ZeroBalanceProof, KeyRotationProof, ZerosolAccount, PendingAccount,
+};
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub enum ZerosolInstruction {
     /// Initialize the global state
+    ///
+    /// `fee` is the minimum relayer fee a transfer must pay. Epochs last
+    /// `epoch_length` units of `epoch_source` (seconds or slots), counted
+    /// from `epoch_offset`.
     /// Accounts:
     /// 0. [signer] Authority
-    /// 1. [writable] Global state account
+    /// 1. [writable] Global state, the PDA `[b"global_state"]`
     /// 2. [] Token mint
     /// 3. [] System program
     Initialize {
         epoch_length: u64,
         fee: u64,
+        epoch_source: u8,
+        epoch_offset: u64,
     },
 
     /// Register a new account
+    ///
+    /// `challenge` and `response` are a Schnorr signature by `public_key` over
+    /// `utils::register_account_message` (see `utils::schnorr_sign`). The
+    /// zerosol account records the pending account, and every later
+    /// instruction must pass that same pending account with it.
     /// Accounts:
     /// 0. [signer] Payer
     /// 1. [writable] Zerosol account
@@ -36,40 +55,53 @@ pub enum ZerosolInstruction {
     /// 3. [writable] Funder token account
     /// 4. [writable] Program token account
     /// 5. [] Token program
-    /// 6. [] Global state
+    /// 6. [] Global state, writable to refresh `current_epoch`
     Fund {
         amount: u64,
     },
 
     /// Perform an anonymous transfer
+    ///
+    /// If any participant is locked, the relayer must be that account's lock
+    /// authority (see `Lock`).
+    /// `fee` is paid out of the transferred value to the relayer's Zerosol
+    /// account identified by `beneficiary`; both are bound by the proof.
+    /// `tip_lamports` is an optional extra payment in SOL to the relayer.
     /// Accounts:
-    /// 0. [signer] Relayer
+    /// 0. [signer, writable] Relayer
     /// 1. [writable] Beneficiary account
     /// 2. [writable] Beneficiary pending account
-    /// 3. [writable] Nonce account
-    /// 4. [] Global state
+    /// 3. [writable] Nonce account, PDA of `NonceState::find_address(epoch, nonce)`
+    /// 4. [] Global state, writable to refresh `current_epoch`
     /// 5. [] System program
-    /// 6..N. [writable] Participant accounts and pending accounts
+    /// 6. [signer, writable] Tip payer, present only when `tip_lamports > 0`
+    /// 6..N (7..N with a tip). [writable] Participant account and pending account pairs,
+    ///    in the order of `public_keys`
     Transfer {
         commitments_c: Vec<[u8; 32]>,
         commitment_d: [u8; 32],
         public_keys: Vec<[u8; 32]>,
         nonce: [u8; 32],
         beneficiary: [u8; 32],
+        fee: u64,
+        tip_lamports: u64,
         proof: ZerosolProof,
     },
 
     /// Burn tokens (withdraw)
+    ///
+    /// If the account is locked, the withdrawer must be its lock authority.
     /// Accounts:
     /// 0. [signer] Withdrawer
     /// 1. [writable] Zerosol account
     /// 2. [writable] Pending account
     /// 3. [writable] Withdrawer token account
     /// 4. [writable] Program token account
-    /// 5. [writable] Nonce account
+    /// 5. [writable] Nonce account, PDA of `NonceState::find_address(epoch, nonce)`
     /// 6. [] Token program
-    /// 7. [] Global state
+    /// 7. [writable] Global state
     /// 8. [] System program
+    /// 9. [] Token authority, PDA of `state::find_token_authority_address`
     Burn {
         amount: u64,
         nonce: [u8; 32],
@@ -83,4 +115,657 @@ pub enum ZerosolInstruction {
     /// 2. [writable] Pending account
     /// 3. [writable] Global state
     RollOver,
-}
\ No newline at end of file
+
+    /// Set the guardian key that may pause the pool alongside the authority
+    /// Accounts:
+    /// 0. [signer] Authority
+    /// 1. [writable] Global state
+    SetGuardian {
+        guardian: Pubkey,
+    },
+
+    /// Pause or resume the pool, globally or per operation
+    ///
+    /// The guardian may only add pauses; lifting one takes the authority.
+    /// Accounts:
+    /// 0. [signer] Authority or guardian
+    /// 1. [writable] Global state
+    SetPause {
+        paused: bool,
+        pause_flags: u8,
+    },
+
+    /// Limit the amount that can be burned per epoch (0 disables the cap)
+    ///
+    /// The guardian may only set or lower a cap; raising or removing it takes
+    /// the authority.
+    /// Accounts:
+    /// 0. [signer] Authority or guardian
+    /// 1. [writable] Global state
+    SetWithdrawalCap {
+        burn_cap_per_epoch: u64,
+    },
+
+    /// Close a zero-balance account and its pending account, returning rent
+    ///
+    /// Requires an account-signed `state::ACTION_CLOSE` for the recipient and a
+    /// zero-balance proof over the same message. The pending account must be
+    /// empty after rollover, so funds received this epoch block closing.
+    /// Accounts:
+    /// 0. [writable] Zerosol account
+    /// 1. [writable] Pending account
+    /// 2. [writable] Rent recipient
+    /// 3. [] Global state, writable to refresh `current_epoch`
+    CloseAccount {
+        challenge: [u8; 32],
+        response: [u8; 32],
+        proof: ZeroBalanceProof,
+    },
+
+    /// Close nonce accounts of past epochs and refund whoever paid their rent
+    ///
+    /// Permissionless: proofs commit to the epoch, so old nonces can no
+    /// longer be replayed once the epoch has ended.
+    /// Accounts:
+    /// 0. [] Global state, writable to refresh `current_epoch`
+    /// 1..N. Pairs of [writable] nonce account, [writable] its `payer`
+    ReclaimNonces,
+
+    /// Lock an account so only `program` can transfer or burn from it
+    ///
+    /// The program spends by signing, via `invoke_signed`, as the lock
+    /// authority PDA `[ZerosolAccount::LOCK_SEED, zerosol_account]` of
+    /// `program`. Requires an account-signed `state::ACTION_LOCK` for `program`.
+    /// Accounts:
+    /// 0. [writable] Zerosol account
+    /// 1. [] Global state, writable to refresh `current_epoch`
+    Lock {
+        program: Pubkey,
+        challenge: [u8; 32],
+        response: [u8; 32],
+    },
+
+    /// Release a lock; requires both the account key and the lock authority
+    ///
+    /// Requires an account-signed `state::ACTION_UNLOCK` for the current lock holder.
+    /// Accounts:
+    /// 0. [writable] Zerosol account
+    /// 1. [signer] Lock authority
+    /// 2. [] Global state, writable to refresh `current_epoch`
+    Unlock {
+        challenge: [u8; 32],
+        response: [u8; 32],
+    },
+
+    /// Create a proof buffer for uploading a transfer too large for one
+    /// transaction
+    ///
+    /// The buffer holds a Borsh-encoded `TransferPayload` of `data_len` bytes,
+    /// at most `ProofBuffer::MAX_DATA_LEN`.
+    /// Accounts:
+    /// 0. [signer, writable] Owner (relayer), pays rent
+    /// 1. [writable] Proof buffer, PDA of `ProofBuffer::find_address(owner, buffer_id)`
+    /// 2. [] System program
+    CreateProofBuffer {
+        buffer_id: u64,
+        data_len: u32,
+    },
+
+    /// Write `data` into the buffer payload at `offset`
+    ///
+    /// Only allowed before the first `VerifyProofStep`.
+    /// Accounts:
+    /// 0. [signer] Owner
+    /// 1. [writable] Proof buffer
+    WriteProofChunk {
+        offset: u32,
+        data: Vec<u8>,
+    },
+
+    /// Run up to `max_steps` verification steps on the buffered proof
+    ///
+    /// Progress is checkpointed in the buffer. The first step freezes the
+    /// payload and binds it to the current epoch; verification and execution
+    /// must finish within that epoch.
+    /// Accounts:
+    /// 0. [signer] Owner
+    /// 1. [writable] Proof buffer
+    /// 2. [] Global state, writable to refresh `current_epoch`
+    VerifyProofStep {
+        max_steps: u8,
+    },
+
+    /// Apply a fully verified buffered transfer and close the buffer
+    ///
+    /// Accounts are those of `Transfer`, with the proof buffer inserted at 1;
+    /// the buffer's rent is returned to the relayer.
+    /// Accounts:
+    /// 0. [signer, writable] Relayer, owner of the buffer
+    /// 1. [writable] Proof buffer
+    /// 2. [writable] Beneficiary account
+    /// 3. [writable] Beneficiary pending account
+    /// 4. [writable] Nonce account, PDA of `NonceState::find_address(epoch, nonce)`
+    /// 5. [] Global state, writable to refresh `current_epoch`
+    /// 6. [] System program
+    /// 7. [signer, writable] Tip payer, present only when `tip_lamports > 0`
+    /// 7..N (8..N with a tip). [writable] Participant account and pending account pairs
+    ExecuteTransfer,
+
+    /// Close an unused or failed proof buffer and return its rent
+    /// Accounts:
+    /// 0. [signer, writable] Owner
+    /// 1. [writable] Proof buffer
+    CloseProofBuffer,
+
+    /// Move an account to `new_public_key`, re-encrypting its balance
+    ///
+    /// `challenge` and `response` sign `state::ACTION_ROTATE_KEY` for the new key
+    /// with the current key; `new_key_challenge` and `new_key_response` sign
+    /// the same message with the new key. `proof` carries the re-encrypted
+    /// ciphertext and shows it holds the balance of the current one. The
+    /// account is rolled over first; funds received this epoch block rotation.
+    /// Accounts:
+    /// 0. [writable] Zerosol account
+    /// 1. [writable] Pending account
+    /// 2. [] Global state, writable to refresh `current_epoch`
+    RotateKey {
+        new_public_key: [u8; 32],
+        challenge: [u8; 32],
+        response: [u8; 32],
+        new_key_challenge: [u8; 32],
+        new_key_response: [u8; 32],
+        proof: KeyRotationProof,
+    },
+
+    /// Deposit into the account of `public_key`, creating it if needed
+    ///
+    /// The accounts are PDAs of the key, so the holder does not have to be
+    /// registered. New accounts are unclaimed: they accumulate deposits and
+    /// roll over, but cannot transfer, burn or receive transfers until the
+    /// holder signs `ClaimAccount`. The funder pays their rent.
+    /// Accounts:
+    /// 0. [signer, writable] Funder
+    /// 1. [writable] Zerosol account, PDA of `ZerosolAccount::find_address(public_key)`
+    /// 2. [writable] Pending account, PDA of `PendingAccount::find_address(public_key)`
+    /// 3. [writable] Funder token account
+    /// 4. [writable] Program token account
+    /// 5. [] Token program
+    /// 6. [] Global state
+    /// 7. [] System program
+    FundToKey {
+        public_key: [u8; 32],
+        amount: u64,
+    },
+
+    /// Complete the registration of an account created by `FundToKey`,
+    /// keeping its balance
+    ///
+    /// `challenge` and `response` are a Schnorr signature by the account key
+    /// over `utils::register_account_message`, as for `Register`.
+    /// Accounts:
+    /// 0. [signer] Payer
+    /// 1. [writable] Zerosol account
+    /// 2. [] Global state
+    ClaimAccount {
+        challenge: [u8; 32],
+        response: [u8; 32],
+    },
+}
+
+/// Create an `Initialize` instruction for the program's global state at
+/// `GlobalState::find_address`
+pub fn initialize(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    token_mint: &Pubkey,
+    epoch_length: u64,
+    fee: u64,
+    epoch_source: u8,
+    epoch_offset: u64,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::Initialize {
+            epoch_length,
+            fee,
+            epoch_source,
+            epoch_offset,
+        },
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(global_state, false),
+            AccountMeta::new_readonly(*token_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `Register` instruction
+///
+/// `zerosol_account` and `pending_account` must be new keypair accounts and
+/// sign the transaction.
+pub fn register(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    zerosol_account: &Pubkey,
+    pending_account: &Pubkey,
+    public_key: [u8; 32],
+    challenge: [u8; 32],
+    response: [u8; 32],
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::Register {
+            public_key,
+            challenge,
+            response,
+        },
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*zerosol_account, true),
+            AccountMeta::new(*pending_account, true),
+            AccountMeta::new_readonly(global_state, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `Fund` instruction
+pub fn fund(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    zerosol_account: &Pubkey,
+    pending_account: &Pubkey,
+    funder_token_account: &Pubkey,
+    program_token_account: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::Fund { amount },
+        vec![
+            AccountMeta::new_readonly(*funder, true),
+            AccountMeta::new(*zerosol_account, false),
+            AccountMeta::new(*pending_account, false),
+            AccountMeta::new(*funder_token_account, false),
+            AccountMeta::new(*program_token_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(global_state, false),
+        ],
+    )
+}
+
+/// Create a `Transfer` instruction
+///
+/// `participants` holds the `(zerosol account, pending account)` pair of
+/// each ring member, in the order of `public_keys`. `epoch` selects the nonce
+/// account and must be the epoch the proof was built for. `tip` is an
+/// optional `(payer, lamports)` payment to the relayer; the payer must sign.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer(
+    program_id: &Pubkey,
+    relayer: &Pubkey,
+    beneficiary_account: &Pubkey,
+    beneficiary_pending: &Pubkey,
+    participants: &[(Pubkey, Pubkey)],
+    tip: Option<(Pubkey, u64)>,
+    epoch: u64,
+    commitments_c: Vec<[u8; 32]>,
+    commitment_d: [u8; 32],
+    public_keys: Vec<[u8; 32]>,
+    nonce: [u8; 32],
+    beneficiary: [u8; 32],
+    fee: u64,
+    proof: ZerosolProof,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    let (nonce_account, _) = NonceState::find_address(program_id, epoch, &nonce);
+
+    let mut accounts = vec![
+        AccountMeta::new(*relayer, true),
+        AccountMeta::new(*beneficiary_account, false),
+        AccountMeta::new(*beneficiary_pending, false),
+        AccountMeta::new(nonce_account, false),
+        AccountMeta::new_readonly(global_state, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let tip_lamports = match tip {
+        Some((tip_payer, lamports)) if lamports > 0 => {
+            accounts.push(AccountMeta::new(tip_payer, true));
+            lamports
+        }
+        _ => 0,
+    };
+
+    for (zerosol_account, pending_account) in participants {
+        accounts.push(AccountMeta::new(*zerosol_account, false));
+        accounts.push(AccountMeta::new(*pending_account, false));
+    }
+
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::Transfer {
+            commitments_c,
+            commitment_d,
+            public_keys,
+            nonce,
+            beneficiary,
+            fee,
+            tip_lamports,
+            proof,
+        },
+        accounts,
+    )
+}
+
+/// Create a `Burn` instruction
+///
+/// `epoch` selects the nonce account and must be the epoch the proof was
+/// built for.
+#[allow(clippy::too_many_arguments)]
+pub fn burn(
+    program_id: &Pubkey,
+    withdrawer: &Pubkey,
+    zerosol_account: &Pubkey,
+    pending_account: &Pubkey,
+    withdrawer_token_account: &Pubkey,
+    program_token_account: &Pubkey,
+    epoch: u64,
+    amount: u64,
+    nonce: [u8; 32],
+    proof: BurnProof,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    let (nonce_account, _) = NonceState::find_address(program_id, epoch, &nonce);
+    let (token_authority, _) = find_token_authority_address(program_id);
+
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::Burn {
+            amount,
+            nonce,
+            proof,
+        },
+        vec![
+            AccountMeta::new(*withdrawer, true),
+            AccountMeta::new(*zerosol_account, false),
+            AccountMeta::new(*pending_account, false),
+            AccountMeta::new(*withdrawer_token_account, false),
+            AccountMeta::new(*program_token_account, false),
+            AccountMeta::new(nonce_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(global_state, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(token_authority, false),
+        ],
+    )
+}
+
+/// Create a `RollOver` instruction
+pub fn roll_over(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    zerosol_account: &Pubkey,
+    pending_account: &Pubkey,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::RollOver,
+        vec![
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*zerosol_account, false),
+            AccountMeta::new(*pending_account, false),
+            AccountMeta::new(global_state, false),
+        ],
+    )
+}
+
+/// Create a `SetGuardian` instruction
+pub fn set_guardian(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    guardian: Pubkey,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::SetGuardian { guardian },
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(global_state, false),
+        ],
+    )
+}
+
+/// Create a `SetPause` instruction
+pub fn set_pause(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    paused: bool,
+    pause_flags: u8,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::SetPause { paused, pause_flags },
+        vec![
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(global_state, false),
+        ],
+    )
+}
+
+/// Create a `SetWithdrawalCap` instruction
+pub fn set_withdrawal_cap(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    burn_cap_per_epoch: u64,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::SetWithdrawalCap { burn_cap_per_epoch },
+        vec![
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(global_state, false),
+        ],
+    )
+}
+
+/// Create a `CreateProofBuffer` instruction
+pub fn create_proof_buffer(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    buffer_id: u64,
+    data_len: u32,
+) -> Instruction {
+    let (buffer, _) = ProofBuffer::find_address(program_id, owner, buffer_id);
+
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::CreateProofBuffer { buffer_id, data_len },
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(buffer, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create `WriteProofChunk` instructions uploading `payload` in chunks of
+/// at most `chunk_len` bytes
+pub fn write_proof_chunks(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    buffer_id: u64,
+    payload: &[u8],
+    chunk_len: usize,
+) -> Vec<Instruction> {
+    let (buffer, _) = ProofBuffer::find_address(program_id, owner, buffer_id);
+
+    payload
+        .chunks(chunk_len.max(1))
+        .enumerate()
+        .map(|(i, data)| {
+            new_instruction(
+                program_id,
+                &ZerosolInstruction::WriteProofChunk {
+                    offset: (i * chunk_len.max(1)) as u32,
+                    data: data.to_vec(),
+                },
+                vec![
+                    AccountMeta::new_readonly(*owner, true),
+                    AccountMeta::new(buffer, false),
+                ],
+            )
+        })
+        .collect()
+}
+
+/// Create a `VerifyProofStep` instruction
+pub fn verify_proof_step(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    buffer_id: u64,
+    max_steps: u8,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    let (buffer, _) = ProofBuffer::find_address(program_id, owner, buffer_id);
+
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::VerifyProofStep { max_steps },
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(buffer, false),
+            AccountMeta::new_readonly(global_state, false),
+        ],
+    )
+}
+
+/// Create an `ExecuteTransfer` instruction for a verified buffer
+///
+/// `payload` must be the transfer uploaded to the buffer; it is only used to
+/// derive the accounts. See `transfer` for `participants` and `epoch`.
+/// `tip_payer` must sign and is required when `payload.tip_lamports > 0`;
+/// without it this returns `NotEnoughAccountKeys`, as the program would.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_transfer(
+    program_id: &Pubkey,
+    relayer: &Pubkey,
+    buffer_id: u64,
+    beneficiary_account: &Pubkey,
+    beneficiary_pending: &Pubkey,
+    participants: &[(Pubkey, Pubkey)],
+    tip_payer: Option<&Pubkey>,
+    epoch: u64,
+    payload: &TransferPayload,
+) -> Result<Instruction, ProgramError> {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    let (buffer, _) = ProofBuffer::find_address(program_id, relayer, buffer_id);
+    let (nonce_account, _) = NonceState::find_address(program_id, epoch, &payload.nonce);
+
+    let mut accounts = vec![
+        AccountMeta::new(*relayer, true),
+        AccountMeta::new(buffer, false),
+        AccountMeta::new(*beneficiary_account, false),
+        AccountMeta::new(*beneficiary_pending, false),
+        AccountMeta::new(nonce_account, false),
+        AccountMeta::new_readonly(global_state, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    if payload.tip_lamports > 0 {
+        let tip_payer = tip_payer.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        accounts.push(AccountMeta::new(*tip_payer, true));
+    }
+
+    for (zerosol_account, pending_account) in participants {
+        accounts.push(AccountMeta::new(*zerosol_account, false));
+        accounts.push(AccountMeta::new(*pending_account, false));
+    }
+
+    Ok(new_instruction(program_id, &ZerosolInstruction::ExecuteTransfer, accounts))
+}
+
+/// Create a `CloseProofBuffer` instruction
+pub fn close_proof_buffer(program_id: &Pubkey, owner: &Pubkey, buffer_id: u64) -> Instruction {
+    let (buffer, _) = ProofBuffer::find_address(program_id, owner, buffer_id);
+
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::CloseProofBuffer,
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(buffer, false),
+        ],
+    )
+}
+
+/// Create a `FundToKey` instruction for the accounts of `public_key`
+pub fn fund_to_key(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    funder_token_account: &Pubkey,
+    program_token_account: &Pubkey,
+    public_key: [u8; 32],
+    amount: u64,
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    let (zerosol_account, _) = ZerosolAccount::find_address(program_id, &public_key);
+    let (pending_account, _) = PendingAccount::find_address(program_id, &public_key);
+
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::FundToKey { public_key, amount },
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new(zerosol_account, false),
+            AccountMeta::new(pending_account, false),
+            AccountMeta::new(*funder_token_account, false),
+            AccountMeta::new(*program_token_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(global_state, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `ClaimAccount` instruction for the account `FundToKey` created
+/// for `public_key`
+pub fn claim_account(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    public_key: &[u8; 32],
+    challenge: [u8; 32],
+    response: [u8; 32],
+) -> Instruction {
+    let (global_state, _) = GlobalState::find_address(program_id);
+    let (zerosol_account, _) = ZerosolAccount::find_address(program_id, public_key);
+
+    new_instruction(
+        program_id,
+        &ZerosolInstruction::ClaimAccount {
+            challenge,
+            response,
+        },
+        vec![
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new(zerosol_account, false),
+            AccountMeta::new_readonly(global_state, false),
+        ],
+    )
+}
+
+/// Build an instruction from `data` encoded with the crate's own borsh
+///
+/// `Instruction::new_with_borsh` expects the borsh version of the resolved
+/// `solana-program`, which need not be the one `ZerosolInstruction` derives.
+fn new_instruction(program_id: &Pubkey, data: &ZerosolInstruction, accounts: Vec<AccountMeta>) -> Instruction {
+    Instruction::new_with_bytes(*program_id, &data.try_to_vec().unwrap(), accounts)
+}
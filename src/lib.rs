@@ -1,11 +1,11 @@
 use solana_program::{
     account_info::AccountInfo,
-    entrypoint,
     entrypoint::ProgramResult,
     pubkey::Pubkey,
 };
 
 pub mod error;
+pub mod events;
 pub mod instruction;
 pub mod processor;
 pub mod state;
@@ -13,8 +13,11 @@ pub mod utils;
 pub mod bulletproof;
 pub mod curve_ops;
 pub mod constraint_system;
+#[cfg(not(target_os = "solana"))]
+pub mod client;
 
-entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -16,13 +16,24 @@ use spl_token::{instruction as token_instruction, state::Account as TokenAccount
 use curve25519_dalek::scalar::Scalar;
 
 use crate::{
-    error::ZerosolError,
+    error::{RangeProofTarget, ZerosolError},
+    events::{
+        Burned, ConfigChanged, Event, Funded, KeyRotated, Registered, RolledOver, Transferred,
+    },
     instruction::ZerosolInstruction,
-    state::{GlobalState, ZerosolAccount, PendingAccount, NonceState},
+    state::{
+        GlobalState, ZerosolAccount, PendingAccount, NonceState,
+        EPOCH_SOURCE_TIMESTAMP, EPOCH_SOURCE_SLOT, TOKEN_AUTHORITY_SEED, PAUSE_REGISTER, PAUSE_FUND,
+        PAUSE_TRANSFER, PAUSE_BURN, find_token_authority_address, ProofBuffer, TransferPayload,
+        PROOF_STAGE_WRITING, PROOF_STAGE_STATEMENT, PROOF_STAGE_CONSTRAINTS, PROOF_STAGE_RANGE,
+        PROOF_STAGE_BALANCE, PROOF_STAGE_VERIFIED, ACTION_LOCK, ACTION_UNLOCK, ACTION_CLOSE,
+        ACTION_ROTATE_KEY,
+    },
     utils::{
         G1Point, MAX_TRANSFER_AMOUNT, hash_to_scalar, verify_schnorr_signature,
         pedersen_commit, scalar_from_bytes, map_to_curve_with_index, multi_scalar_mul,
-        batch_scalar_mul,
+        batch_scalar_mul, transfer_statement_hash, burn_statement_hash, account_action_message,
+        verify_zero_balance_proof, verify_key_rotation_proof, register_account_message,
     },
     bulletproof::{BulletproofVerifier, RangeProof, InnerProductProof},
     curve_ops::{get_curve_ops, SpecializedOps},
@@ -42,9 +53,12 @@ pub fn process_instruction(
         .map_err(|_| ZerosolError::InvalidInstruction)?;
 
     match instruction {
-        ZerosolInstruction::Initialize { epoch_length, fee } => {
-            process_initialize(program_id, accounts, epoch_length, fee)
-        }
+        ZerosolInstruction::Initialize {
+            epoch_length,
+            fee,
+            epoch_source,
+            epoch_offset,
+        } => process_initialize(program_id, accounts, epoch_length, fee, epoch_source, epoch_offset),
         ZerosolInstruction::Register {
             public_key,
             challenge,
@@ -57,16 +71,22 @@ pub fn process_instruction(
             public_keys,
             nonce,
             beneficiary,
+            fee,
+            tip_lamports,
             proof,
         } => process_transfer(
             program_id,
             accounts,
-            commitments_c,
-            commitment_d,
-            public_keys,
-            nonce,
-            beneficiary,
-            proof,
+            TransferPayload {
+                commitments_c,
+                commitment_d,
+                public_keys,
+                nonce,
+                beneficiary,
+                fee,
+                tip_lamports,
+                proof,
+            },
         ),
         ZerosolInstruction::Burn {
             amount,
@@ -74,6 +94,66 @@ pub fn process_instruction(
             proof,
         } => process_burn(program_id, accounts, amount, nonce, proof),
         ZerosolInstruction::RollOver => process_rollover(program_id, accounts),
+        ZerosolInstruction::SetGuardian { guardian } => {
+            process_set_guardian(program_id, accounts, guardian)
+        }
+        ZerosolInstruction::SetPause {
+            paused,
+            pause_flags,
+        } => process_set_pause(program_id, accounts, paused, pause_flags),
+        ZerosolInstruction::SetWithdrawalCap { burn_cap_per_epoch } => {
+            process_set_withdrawal_cap(program_id, accounts, burn_cap_per_epoch)
+        }
+        ZerosolInstruction::CloseAccount {
+            challenge,
+            response,
+            proof,
+        } => process_close_account(program_id, accounts, challenge, response, proof),
+        ZerosolInstruction::ReclaimNonces => process_reclaim_nonces(program_id, accounts),
+        ZerosolInstruction::Lock {
+            program,
+            challenge,
+            response,
+        } => process_lock(program_id, accounts, program, challenge, response),
+        ZerosolInstruction::Unlock {
+            challenge,
+            response,
+        } => process_unlock(program_id, accounts, challenge, response),
+        ZerosolInstruction::CreateProofBuffer { buffer_id, data_len } => {
+            process_create_proof_buffer(program_id, accounts, buffer_id, data_len)
+        }
+        ZerosolInstruction::WriteProofChunk { offset, data } => {
+            process_write_proof_chunk(program_id, accounts, offset, data)
+        }
+        ZerosolInstruction::VerifyProofStep { max_steps } => {
+            process_verify_proof_step(program_id, accounts, max_steps)
+        }
+        ZerosolInstruction::ExecuteTransfer => process_execute_transfer(program_id, accounts),
+        ZerosolInstruction::CloseProofBuffer => process_close_proof_buffer(program_id, accounts),
+        ZerosolInstruction::RotateKey {
+            new_public_key,
+            challenge,
+            response,
+            new_key_challenge,
+            new_key_response,
+            proof,
+        } => process_rotate_key(
+            program_id,
+            accounts,
+            new_public_key,
+            challenge,
+            response,
+            new_key_challenge,
+            new_key_response,
+            proof,
+        ),
+        ZerosolInstruction::FundToKey { public_key, amount } => {
+            process_fund_to_key(program_id, accounts, public_key, amount)
+        }
+        ZerosolInstruction::ClaimAccount {
+            challenge,
+            response,
+        } => process_claim_account(program_id, accounts, challenge, response),
     }
 }
 
@@ -82,6 +162,8 @@ fn process_initialize(
     accounts: &[AccountInfo],
     epoch_length: u64,
     fee: u64,
+    epoch_source: u8,
+    epoch_offset: u64,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let authority_info = next_account_info(account_info_iter)?;
@@ -93,31 +175,42 @@ fn process_initialize(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let rent = Rent::get()?;
-    let space = GlobalState::LEN;
-    let lamports = rent.minimum_balance(space);
+    if epoch_length == 0 {
+        msg!("Epoch length must be non-zero");
+        return Err(ZerosolError::InvalidInstruction.into());
+    }
 
-    invoke(
-        &system_instruction::create_account(
-            authority_info.key,
-            global_state_info.key,
-            lamports,
-            space as u64,
-            program_id,
-        ),
-        &[
-            authority_info.clone(),
-            global_state_info.clone(),
-            system_program_info.clone(),
-        ],
+    if epoch_source != EPOCH_SOURCE_TIMESTAMP && epoch_source != EPOCH_SOURCE_SLOT {
+        msg!("Unknown epoch source {}", epoch_source);
+        return Err(ZerosolError::InvalidInstruction.into());
+    }
+
+    // One pool per program: every handler only accepts this address
+    let (expected_address, bump) = GlobalState::find_address(program_id);
+    if expected_address != *global_state_info.key {
+        msg!("Global state {} is not the program address {}", global_state_info.key, expected_address);
+        return Err(ZerosolError::InvalidPda.into());
+    }
+
+    create_pda_account(
+        program_id,
+        authority_info,
+        global_state_info,
+        system_program_info,
+        GlobalState::LEN,
+        &[GlobalState::SEED_PREFIX, &[bump]],
     )?;
 
-    let global_state = GlobalState::new(
+    let mut global_state = GlobalState::new(
         *authority_info.key,
         *token_mint_info.key,
         epoch_length,
         fee,
     );
+    global_state.epoch_source = epoch_source;
+    global_state.epoch_offset = epoch_offset;
+    global_state.bump = bump;
+    global_state.advance_epoch(&Clock::get()?);
 
     global_state.serialize(&mut &mut global_state_info.data.borrow_mut()[..])?;
 
@@ -143,13 +236,20 @@ fn process_register(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    let global_state = load_global_state(program_id, global_state_info)?;
+    if global_state.is_paused(PAUSE_REGISTER) {
+        msg!("Registration is paused");
+        return Err(ZerosolError::PoolPaused.into());
+    }
+
     // Verify Schnorr signature
     let public_key_point = G1Point::from_bytes(&public_key)?;
     let challenge_scalar = scalar_from_bytes(&challenge);
     let response_scalar = scalar_from_bytes(&response);
 
-    let message = program_id.to_bytes();
+    let message = register_account_message(program_id, zerosol_account_info.key, payer_info.key);
     if !verify_schnorr_signature(&public_key_point, &message, &challenge_scalar, &response_scalar) {
+        msg!("Registration signature does not verify for {}", zerosol_account_info.key);
         return Err(ZerosolError::InvalidRegistrationSignature.into());
     }
 
@@ -193,7 +293,7 @@ fn process_register(
     )?;
 
     // Initialize accounts
-    let mut zerosol_account = ZerosolAccount::new(public_key);
+    let mut zerosol_account = ZerosolAccount::new(public_key, *pending_account_info.key);
     zerosol_account.is_registered = true;
     zerosol_account.serialize(&mut &mut zerosol_account_info.data.borrow_mut()[..])?;
 
@@ -204,6 +304,12 @@ fn process_register(
     pending_account.set_commitment_right(&g);
     pending_account.serialize(&mut &mut pending_account_info.data.borrow_mut()[..])?;
 
+    Registered {
+        account: *zerosol_account_info.key,
+        public_key,
+    }
+    .emit();
+
     msg!("Account registered successfully");
     Ok(())
 }
@@ -226,25 +332,195 @@ fn process_fund(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if amount > MAX_TRANSFER_AMOUNT {
-        return Err(ZerosolError::TransferAmountOutOfRange.into());
-    }
-
     // Load accounts
     let mut zerosol_account = ZerosolAccount::try_from_slice(&zerosol_account_info.data.borrow())?;
     if !zerosol_account.is_registered {
+        msg!("Account {} is not registered", zerosol_account_info.key);
         return Err(ZerosolError::AccountNotRegistered.into());
     }
 
-    // Roll over if needed
-    let global_state = GlobalState::try_from_slice(&global_state_info.data.borrow())?;
-    let clock = Clock::get()?;
-    let current_epoch = clock.unix_timestamp as u64 / global_state.epoch_length;
-    
-    if zerosol_account.last_rollover < current_epoch {
-        rollover_account(&mut zerosol_account, pending_account_info, current_epoch)?;
+    deposit(
+        program_id,
+        funder_info,
+        zerosol_account_info,
+        &mut zerosol_account,
+        pending_account_info,
+        funder_token_info,
+        program_token_info,
+        token_program_info,
+        global_state_info,
+        amount,
+    )
+}
+
+fn process_fund_to_key(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    public_key: [u8; 32],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let funder_info = next_account_info(account_info_iter)?;
+    let zerosol_account_info = next_account_info(account_info_iter)?;
+    let pending_account_info = next_account_info(account_info_iter)?;
+    let funder_token_info = next_account_info(account_info_iter)?;
+    let program_token_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if !funder_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (zerosol_address, zerosol_bump) = ZerosolAccount::find_address(program_id, &public_key);
+    let (pending_address, pending_bump) = PendingAccount::find_address(program_id, &public_key);
+    if *zerosol_account_info.key != zerosol_address || *pending_account_info.key != pending_address {
+        msg!("Account {} is not the address derived from the public key", zerosol_account_info.key);
+        return Err(ZerosolError::InvalidPda.into());
+    }
+
+    let mut zerosol_account = if zerosol_account_info.data_len() == 0 {
+        // Reject keys that are not curve points before paying any rent
+        G1Point::from_bytes(&public_key)?;
+
+        for (info, space, seed, bump) in [
+            (zerosol_account_info, ZerosolAccount::LEN, ZerosolAccount::SEED_PREFIX, zerosol_bump),
+            (pending_account_info, PendingAccount::LEN, PendingAccount::SEED_PREFIX, pending_bump),
+        ] {
+            create_pda_account(
+                program_id,
+                funder_info,
+                info,
+                system_program_info,
+                space,
+                &[seed, &public_key, &[bump]],
+            )?;
+        }
+
+        // Unclaimed until the key holder signs `ClaimAccount`
+        let zerosol_account = ZerosolAccount::new(public_key, pending_address);
+        zerosol_account.serialize(&mut &mut zerosol_account_info.data.borrow_mut()[..])?;
+        PendingAccount::new().serialize(&mut &mut pending_account_info.data.borrow_mut()[..])?;
+        zerosol_account
+    } else {
+        if zerosol_account_info.owner != program_id {
+            msg!("Account {} is not owned by the program", zerosol_account_info.key);
+            return Err(ZerosolError::WrongAccountOwner.into());
+        }
+        ZerosolAccount::try_from_slice(&zerosol_account_info.data.borrow())?
+    };
+
+    deposit(
+        program_id,
+        funder_info,
+        zerosol_account_info,
+        &mut zerosol_account,
+        pending_account_info,
+        funder_token_info,
+        program_token_info,
+        token_program_info,
+        global_state_info,
+        amount,
+    )
+}
+
+fn process_claim_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    challenge: [u8; 32],
+    response: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let zerosol_account_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if zerosol_account_info.owner != program_id {
+        msg!("Account {} is not owned by the program", zerosol_account_info.key);
+        return Err(ZerosolError::WrongAccountOwner.into());
+    }
+
+    let global_state = load_global_state(program_id, global_state_info)?;
+    if global_state.is_paused(PAUSE_REGISTER) {
+        msg!("Registration is paused");
+        return Err(ZerosolError::PoolPaused.into());
+    }
+
+    let mut zerosol_account = ZerosolAccount::try_from_slice(&zerosol_account_info.data.borrow())?;
+    if zerosol_account.is_registered {
+        msg!("Account {} is already claimed", zerosol_account_info.key);
+        return Err(ZerosolError::AccountAlreadyRegistered.into());
+    }
+
+    // Only the funded PDA of the key can be claimed
+    let (zerosol_address, _) = ZerosolAccount::find_address(program_id, &zerosol_account.public_key);
+    if *zerosol_account_info.key != zerosol_address {
+        msg!("Account {} is not the address derived from the public key", zerosol_account_info.key);
+        return Err(ZerosolError::InvalidPda.into());
+    }
+
+    // Same proof of key ownership as `Register`; balances are left untouched
+    let message = register_account_message(program_id, zerosol_account_info.key, payer_info.key);
+    if !verify_schnorr_signature(
+        &zerosol_account.get_public_key()?,
+        &message,
+        &scalar_from_bytes(&challenge),
+        &scalar_from_bytes(&response),
+    ) {
+        msg!("Claim signature does not verify for {}", zerosol_account_info.key);
+        return Err(ZerosolError::InvalidRegistrationSignature.into());
+    }
+
+    zerosol_account.is_registered = true;
+    zerosol_account.serialize(&mut &mut zerosol_account_info.data.borrow_mut()[..])?;
+
+    Registered {
+        account: *zerosol_account_info.key,
+        public_key: zerosol_account.public_key,
+    }
+    .emit();
+
+    msg!("Account claimed");
+    Ok(())
+}
+
+/// Move `amount` tokens from the funder into the pool and add `g^amount` to
+/// the account's pending balance
+#[allow(clippy::too_many_arguments)]
+fn deposit<'a>(
+    program_id: &Pubkey,
+    funder_info: &AccountInfo<'a>,
+    zerosol_account_info: &AccountInfo<'a>,
+    zerosol_account: &mut ZerosolAccount,
+    pending_account_info: &AccountInfo<'a>,
+    funder_token_info: &AccountInfo<'a>,
+    program_token_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    global_state_info: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    if amount > MAX_TRANSFER_AMOUNT {
+        msg!("Deposit of {} exceeds the maximum amount", amount);
+        return Err(ZerosolError::TransferAmountOutOfRange.into());
+    }
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    if global_state.is_paused(PAUSE_FUND) {
+        msg!("Deposits are paused");
+        return Err(ZerosolError::PoolPaused.into());
     }
 
+    check_pool_token_account(program_id, program_token_info, &global_state)?;
+
+    // Roll over if needed
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+    rollover_account(program_id, zerosol_account_info, zerosol_account, pending_account_info, current_epoch)?;
+
     // Transfer tokens
     invoke(
         &token_instruction::transfer(
@@ -270,7 +546,7 @@ fn process_fund(
     // Use optimized Pedersen commitment
     let amount_scalar = Scalar::from(amount);
     let amount_commitment = if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
-        G1Point { point: ops.pedersen_commit(&amount_scalar, &Scalar::zero()) }
+        G1Point { point: ops.pedersen_commit(&amount_scalar, &Scalar::zero())? }
     } else {
         let g = G1Point::generator();
         g.mul(&amount_scalar)
@@ -280,6 +556,14 @@ fn process_fund(
     pending_account.set_commitment_left(&new_left);
     pending_account.serialize(&mut &mut pending_account_info.data.borrow_mut()[..])?;
 
+    Funded {
+        account: *zerosol_account_info.key,
+        amount,
+        pending_left: pending_account.commitment_left,
+        pending_right: pending_account.commitment_right,
+    }
+    .emit();
+
     msg!("Account funded with {} tokens", amount);
     Ok(())
 }
@@ -287,12 +571,7 @@ fn process_fund(
 fn process_transfer(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    commitments_c: Vec<[u8; 32]>,
-    commitment_d: [u8; 32],
-    public_keys: Vec<[u8; 32]>,
-    nonce: [u8; 32],
-    beneficiary: [u8; 32],
-    proof: crate::state::ZerosolProof,
+    payload: TransferPayload,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let relayer_info = next_account_info(account_info_iter)?;
@@ -306,95 +585,189 @@ fn process_transfer(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    // Use optimized curve operations for proof verification
-    if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
-        // Convert commitments to points for batch validation
-        let commitment_points: Result<Vec<_>, _> = commitments_c.iter()
-            .map(|c| G1Point::from_bytes(c).map(|p| p.point))
-            .collect();
-        
-        if let Ok(points) = commitment_points {
-            // Perform batch range constraint validation
-            if !SpecializedOps::verify_range_constraints(&points, 32)? {
-                return Err(ZerosolError::TransferProofVerificationFailed.into());
-            }
-        }
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    check_transfer_allowed(&global_state, &payload)?;
+
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+
+    // Verify proof (simplified - in practice would need full bulletproof verification)
+    if let Err(check) = check_transfer_proof(&payload, current_epoch) {
+        msg!("Transfer proof failed {:?} in epoch {}", check, current_epoch);
+        return Err(ZerosolError::from(check).into());
     }
 
-    // Check nonce hasn't been used
-    if nonce_account_info.data_len() > 0 {
-        let nonce_state = NonceState::try_from_slice(&nonce_account_info.data.borrow())?;
-        if nonce_state.used {
-            return Err(ZerosolError::NonceAlreadySeen.into());
-        }
-    } else {
-        // Create nonce account
-        let rent = Rent::get()?;
-        let space = NonceState::LEN;
-        let lamports = rent.minimum_balance(space);
+    apply_transfer(
+        program_id,
+        relayer_info,
+        beneficiary_account_info,
+        beneficiary_pending_info,
+        nonce_account_info,
+        system_program_info,
+        account_info_iter,
+        payload,
+        current_epoch,
+    )
+}
 
-        invoke(
-            &system_instruction::create_account(
-                relayer_info.key,
-                nonce_account_info.key,
-                lamports,
-                space as u64,
-                program_id,
-            ),
-            &[
-                relayer_info.clone(),
-                nonce_account_info.clone(),
-                system_program_info.clone(),
-            ],
-        )?;
+/// Require the pool's token account to hold the pool mint in the name of the
+/// token authority, so deposits cannot be credited for another token
+fn check_pool_token_account(
+    program_id: &Pubkey,
+    program_token_info: &AccountInfo,
+    global_state: &GlobalState,
+) -> ProgramResult {
+    if *program_token_info.owner != spl_token::id() {
+        msg!("Pool token account {} is not owned by the token program", program_token_info.key);
+        return Err(ZerosolError::WrongAccountOwner.into());
     }
 
-    let global_state = GlobalState::try_from_slice(&global_state_info.data.borrow())?;
-    let clock = Clock::get()?;
-    let current_epoch = clock.unix_timestamp as u64 / global_state.epoch_length;
+    let token_account = TokenAccount::unpack(&program_token_info.data.borrow())?;
+    if token_account.mint != global_state.token_mint {
+        msg!("Pool token account {} holds mint {}, not {}", program_token_info.key, token_account.mint, global_state.token_mint);
+        return Err(ZerosolError::MintMismatch.into());
+    }
 
-    // Verify proof (simplified - in practice would need full bulletproof verification)
-    if !verify_transfer_proof(&proof, &commitments_c, &commitment_d, &public_keys, current_epoch) {
-        return Err(ZerosolError::TransferProofVerificationFailed.into());
+    let (token_authority, _) = find_token_authority_address(program_id);
+    if token_account.owner != token_authority {
+        msg!("Pool token account {} is not held by the token authority {}", program_token_info.key, token_authority);
+        return Err(ZerosolError::WrongAccountOwner.into());
     }
+    Ok(())
+}
+
+/// Reject transfers while paused or with a fee outside the accepted range
+fn check_transfer_allowed(global_state: &GlobalState, payload: &TransferPayload) -> ProgramResult {
+    if global_state.is_paused(PAUSE_TRANSFER) {
+        msg!("Transfers are paused");
+        return Err(ZerosolError::PoolPaused.into());
+    }
+
+    // The configured fee is the minimum a relayer accepts
+    if payload.fee < global_state.fee || payload.fee > MAX_TRANSFER_AMOUNT {
+        msg!("Fee {} is outside [{}, {}]", payload.fee, global_state.fee, MAX_TRANSFER_AMOUNT);
+        return Err(ZerosolError::TransferAmountOutOfRange.into());
+    }
+
+    Ok(())
+}
+
+/// Apply a verified transfer: consume the nonce, pay the relayer and add the
+/// ciphertexts to every participant's pending balance
+///
+/// `account_info_iter` yields the optional tip payer followed by the
+/// participant pairs.
+#[allow(clippy::too_many_arguments)]
+fn apply_transfer<'a, 'b>(
+    program_id: &Pubkey,
+    relayer_info: &'a AccountInfo<'b>,
+    beneficiary_account_info: &'a AccountInfo<'b>,
+    beneficiary_pending_info: &'a AccountInfo<'b>,
+    nonce_account_info: &'a AccountInfo<'b>,
+    system_program_info: &'a AccountInfo<'b>,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    payload: TransferPayload,
+    current_epoch: u64,
+) -> ProgramResult {
+    let TransferPayload {
+        commitments_c,
+        commitment_d,
+        public_keys,
+        nonce,
+        beneficiary,
+        fee,
+        tip_lamports,
+        ..
+    } = payload;
+
+    // Nonces are scoped to the epoch; creating the PDA fails if it was used
+    consume_nonce(
+        program_id,
+        relayer_info,
+        nonce_account_info,
+        system_program_info,
+        &nonce,
+        current_epoch,
+    )?;
 
-    // Update beneficiary account with fee
+    // Credit the fee to the relayer's account named in the proof
     let mut beneficiary_account = ZerosolAccount::try_from_slice(&beneficiary_account_info.data.borrow())?;
+    check_pending_account(program_id, beneficiary_account_info, &beneficiary_account, beneficiary_pending_info)?;
     if !beneficiary_account.is_registered {
+        msg!("Beneficiary {} is not registered", beneficiary_account_info.key);
         return Err(ZerosolError::AccountNotRegistered.into());
     }
-
-    if beneficiary_account.last_rollover < current_epoch {
-        rollover_account(&mut beneficiary_account, beneficiary_pending_info, current_epoch)?;
+    if beneficiary_account.public_key != beneficiary {
+        msg!("Beneficiary {} does not hold the proof's beneficiary key", beneficiary_account_info.key);
+        return Err(ZerosolError::InvalidAccountData.into());
     }
 
+    rollover_account(
+        program_id,
+        beneficiary_account_info,
+        &mut beneficiary_account,
+        beneficiary_pending_info,
+        current_epoch,
+    )?;
+
     let mut beneficiary_pending = PendingAccount::try_from_slice(&beneficiary_pending_info.data.borrow())?;
     let current_left = beneficiary_pending.get_commitment_left()?;
     let g = G1Point::generator();
-    let fee_scalar = Scalar::from(global_state.fee);
+    let fee_scalar = Scalar::from(fee);
     let new_left = current_left.add(&g.mul(&fee_scalar));
     beneficiary_pending.set_commitment_left(&new_left);
     beneficiary_pending.serialize(&mut &mut beneficiary_pending_info.data.borrow_mut()[..])?;
 
-    // Process participant accounts
-    let remaining_accounts = &accounts[6..];
-    for (i, chunk) in remaining_accounts.chunks(2).enumerate() {
-        if i >= public_keys.len() {
-            break;
+    // Optional lamport tip to the relayer
+    if tip_lamports > 0 {
+        let tip_payer_info = next_account_info(account_info_iter)?;
+        if !tip_payer_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
         }
 
+        invoke(
+            &system_instruction::transfer(tip_payer_info.key, relayer_info.key, tip_lamports),
+            &[
+                tip_payer_info.clone(),
+                relayer_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+
+    // Process participant accounts
+    let remaining_accounts = account_info_iter.as_slice();
+    if remaining_accounts.len() < 2 * public_keys.len() {
+        msg!(
+            "Expected {} participant accounts, got {}",
+            2 * public_keys.len(),
+            remaining_accounts.len()
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let mut participants = Vec::with_capacity(public_keys.len());
+    for (i, chunk) in remaining_accounts.chunks_exact(2).take(public_keys.len()).enumerate() {
         let account_info = &chunk[0];
         let pending_info = &chunk[1];
+        participants.push(*account_info.key);
 
         let mut zerosol_account = ZerosolAccount::try_from_slice(&account_info.data.borrow())?;
+        check_pending_account(program_id, account_info, &zerosol_account, pending_info)?;
         if !zerosol_account.is_registered {
+            msg!("Participant {} is not registered", account_info.key);
             return Err(ZerosolError::AccountNotRegistered.into());
         }
 
-        if zerosol_account.last_rollover < current_epoch {
-            rollover_account(&mut zerosol_account, pending_info, current_epoch)?;
+        // The proof encrypts each ciphertext under the ring key at its index
+        if zerosol_account.public_key != public_keys[i] {
+            msg!("Participant {} does not hold ring key {}", account_info.key, i);
+            return Err(ZerosolError::InvalidAccountData.into());
         }
 
+        check_lock(&zerosol_account, account_info.key, relayer_info)?;
+
+        rollover_account(program_id, account_info, &mut zerosol_account, pending_info, current_epoch)?;
+
         // Update pending commitments
         let mut pending_account = PendingAccount::try_from_slice(&pending_info.data.borrow())?;
         let current_left = pending_account.get_commitment_left()?;
@@ -405,8 +778,8 @@ fn process_transfer(
             let c_point = G1Point::from_bytes(&commitments_c[i])?;
             let d_point = G1Point::from_bytes(&commitment_d)?;
             
-            let new_left = ops.cached_point_add(&current_left.point, &c_point.point);
-            let new_right = ops.cached_point_add(&current_right.point, &d_point.point);
+            let new_left = ops.cached_point_add(&current_left.point, &c_point.point)?;
+            let new_right = ops.cached_point_add(&current_right.point, &d_point.point)?;
             
             pending_account.set_commitment_left(&G1Point { point: new_left });
             pending_account.set_commitment_right(&G1Point { point: new_right });
@@ -423,13 +796,19 @@ fn process_transfer(
         pending_account.serialize(&mut &mut pending_info.data.borrow_mut()[..])?;
     }
 
-    // Mark nonce as used
-    let nonce_state = NonceState::new(nonce, current_epoch);
-    let mut used_nonce = nonce_state;
-    used_nonce.used = true;
-    used_nonce.serialize(&mut &mut nonce_account_info.data.borrow_mut()[..])?;
+    Transferred {
+        accounts: participants,
+        public_keys,
+        commitments_c,
+        commitment_d,
+        nonce,
+        epoch: current_epoch,
+        beneficiary,
+        fee,
+    }
+    .emit();
 
-    msg!("Transfer completed successfully");
+    msg!("Transfer completed successfully, relayer fee {}", fee);
     Ok(())
 }
 
@@ -450,60 +829,56 @@ fn process_burn(
     let token_program_info = next_account_info(account_info_iter)?;
     let global_state_info = next_account_info(account_info_iter)?;
     let system_program_info = next_account_info(account_info_iter)?;
+    let token_authority_info = next_account_info(account_info_iter)?;
 
     if !withdrawer_info.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
     if amount > MAX_TRANSFER_AMOUNT {
+        msg!("Burn of {} exceeds the maximum amount", amount);
         return Err(ZerosolError::TransferAmountOutOfRange.into());
     }
 
-    // Check nonce
-    if nonce_account_info.data_len() > 0 {
-        let nonce_state = NonceState::try_from_slice(&nonce_account_info.data.borrow())?;
-        if nonce_state.used {
-            return Err(ZerosolError::NonceAlreadySeen.into());
-        }
-    } else {
-        // Create nonce account
-        let rent = Rent::get()?;
-        let space = NonceState::LEN;
-        let lamports = rent.minimum_balance(space);
-
-        invoke(
-            &system_instruction::create_account(
-                withdrawer_info.key,
-                nonce_account_info.key,
-                lamports,
-                space as u64,
-                program_id,
-            ),
-            &[
-                withdrawer_info.clone(),
-                nonce_account_info.clone(),
-                system_program_info.clone(),
-            ],
-        )?;
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    if global_state.is_paused(PAUSE_BURN) {
+        msg!("Burns are paused");
+        return Err(ZerosolError::PoolPaused.into());
     }
 
-    let global_state = GlobalState::try_from_slice(&global_state_info.data.borrow())?;
-    let clock = Clock::get()?;
-    let current_epoch = clock.unix_timestamp as u64 / global_state.epoch_length;
+    check_pool_token_account(program_id, program_token_info, &global_state)?;
+
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+
+    // Nonces are scoped to the epoch; creating the PDA fails if it was used
+    consume_nonce(
+        program_id,
+        withdrawer_info,
+        nonce_account_info,
+        system_program_info,
+        &nonce,
+        current_epoch,
+    )?;
+
+    // Enforce the per-epoch withdrawal cap
+    global_state.record_burn(current_epoch, amount)?;
+    global_state.serialize(&mut &mut global_state_info.data.borrow_mut()[..])?;
 
     // Load and rollover account
     let mut zerosol_account = ZerosolAccount::try_from_slice(&zerosol_account_info.data.borrow())?;
     if !zerosol_account.is_registered {
+        msg!("Account {} is not registered", zerosol_account_info.key);
         return Err(ZerosolError::AccountNotRegistered.into());
     }
 
-    if zerosol_account.last_rollover < current_epoch {
-        rollover_account(&mut zerosol_account, pending_account_info, current_epoch)?;
-    }
+    check_lock(&zerosol_account, zerosol_account_info.key, withdrawer_info)?;
+
+    rollover_account(program_id, zerosol_account_info, &mut zerosol_account, pending_account_info, current_epoch)?;
 
     // Verify burn proof (simplified)
-    if !verify_burn_proof(&proof, &zerosol_account, amount, current_epoch) {
-        return Err(ZerosolError::BurnProofVerificationFailed.into());
+    if let Err(check) = check_burn_proof(&proof, &zerosol_account, amount, &nonce, current_epoch) {
+        msg!("Burn proof failed {:?} in epoch {}", check, current_epoch);
+        return Err(ZerosolError::from(check).into());
     }
 
     // Update pending commitment (subtract amount)
@@ -513,7 +888,7 @@ fn process_burn(
     
     // Use optimized operations for commitment update
     let amount_commitment = if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
-        G1Point { point: ops.pedersen_commit(&(-amount_scalar), &Scalar::zero()) }
+        G1Point { point: ops.pedersen_commit(&(-amount_scalar), &Scalar::zero())? }
     } else {
         let g = G1Point::generator();
         g.mul(&(-amount_scalar))
@@ -523,10 +898,23 @@ fn process_burn(
     pending_account.set_commitment_left(&new_left);
     pending_account.serialize(&mut &mut pending_account_info.data.borrow_mut()[..])?;
 
+    Burned {
+        account: *zerosol_account_info.key,
+        amount,
+        nonce,
+        epoch: current_epoch,
+        pending_left: pending_account.commitment_left,
+        pending_right: pending_account.commitment_right,
+    }
+    .emit();
+
     // Transfer tokens back to user
-    let seeds = &[b"token_authority"];
-    let (token_authority, bump) = Pubkey::find_program_address(seeds, program_id);
-    let authority_seeds = &[&seeds[0][..], &[bump]];
+    let (token_authority, bump) = find_token_authority_address(program_id);
+    if token_authority != *token_authority_info.key {
+        msg!("Token authority {} is not the program address {}", token_authority_info.key, token_authority);
+        return Err(ZerosolError::InvalidPda.into());
+    }
+    let authority_seeds = &[TOKEN_AUTHORITY_SEED, &[bump]];
 
     invoke_signed(
         &token_instruction::transfer(
@@ -540,17 +928,12 @@ fn process_burn(
         &[
             program_token_info.clone(),
             withdrawer_token_info.clone(),
+            token_authority_info.clone(),
             token_program_info.clone(),
         ],
         &[authority_seeds],
     )?;
 
-    // Mark nonce as used
-    let nonce_state = NonceState::new(nonce, current_epoch);
-    let mut used_nonce = nonce_state;
-    used_nonce.used = true;
-    used_nonce.serialize(&mut &mut nonce_account_info.data.borrow_mut()[..])?;
-
     msg!("Burn completed successfully, {} tokens withdrawn", amount);
     Ok(())
 }
@@ -565,42 +948,897 @@ fn process_rollover(
     let pending_account_info = next_account_info(account_info_iter)?;
     let global_state_info = next_account_info(account_info_iter)?;
 
-    let global_state = GlobalState::try_from_slice(&global_state_info.data.borrow())?;
-    let clock = Clock::get()?;
-    let current_epoch = clock.unix_timestamp as u64 / global_state.epoch_length;
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
 
     let mut zerosol_account = ZerosolAccount::try_from_slice(&zerosol_account_info.data.borrow())?;
-    rollover_account(&mut zerosol_account, pending_account_info, current_epoch)?;
+    rollover_account(program_id, zerosol_account_info, &mut zerosol_account, pending_account_info, current_epoch)?;
 
     msg!("Account rolled over to epoch {}", current_epoch);
     Ok(())
 }
 
-fn rollover_account(
-    zerosol_account: &mut ZerosolAccount,
-    pending_account_info: &AccountInfo,
-    current_epoch: u64,
+fn process_set_guardian(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    guardian: Pubkey,
 ) -> ProgramResult {
-    if zerosol_account.last_rollover >= current_epoch {
-        return Ok(());
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    if !authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let pending_account = PendingAccount::try_from_slice(&pending_account_info.data.borrow())?;
-    
-    // Use optimized operations for commitment addition
-    if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
-        let current_left = zerosol_account.get_commitment_left()?;
-        let current_right = zerosol_account.get_commitment_right()?;
-        let pending_left = pending_account.get_commitment_left()?;
-        let pending_right = pending_account.get_commitment_right()?;
-        
-        let new_left = ops.cached_point_add(&current_left.point, &pending_left.point);
-        let new_right = ops.cached_point_add(&current_right.point, &pending_right.point);
-        
-        zerosol_account.set_commitment_left(&G1Point { point: new_left });
-        zerosol_account.set_commitment_right(&G1Point { point: new_right });
-    } else {
-        // Fallback to standard operations
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    if *authority_info.key != global_state.authority {
+        msg!("Signer {} is not the pool authority", authority_info.key);
+        return Err(ZerosolError::Unauthorized.into());
+    }
+
+    global_state.guardian = guardian;
+    global_state.serialize(&mut &mut global_state_info.data.borrow_mut()[..])?;
+
+    emit_config_changed(authority_info.key, &global_state);
+
+    msg!("Guardian set to {}", guardian);
+    Ok(())
+}
+
+fn process_set_pause(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    paused: bool,
+    pause_flags: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let signer_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    if !global_state.is_pause_authority(signer_info.key) {
+        msg!("Signer {} may not pause the pool", signer_info.key);
+        return Err(ZerosolError::Unauthorized.into());
+    }
+    if *signer_info.key != global_state.authority && !global_state.tightens_pause(paused, pause_flags) {
+        msg!("Only the authority may lift a pause");
+        return Err(ZerosolError::Unauthorized.into());
+    }
+
+    global_state.paused = paused;
+    global_state.pause_flags = pause_flags;
+    global_state.serialize(&mut &mut global_state_info.data.borrow_mut()[..])?;
+
+    emit_config_changed(signer_info.key, &global_state);
+
+    msg!("Pause state updated: paused={}, flags={:#06b}", paused, pause_flags);
+    Ok(())
+}
+
+fn process_set_withdrawal_cap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    burn_cap_per_epoch: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let signer_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    if !global_state.is_pause_authority(signer_info.key) {
+        msg!("Signer {} may not set the withdrawal cap", signer_info.key);
+        return Err(ZerosolError::Unauthorized.into());
+    }
+    if *signer_info.key != global_state.authority && !global_state.tightens_cap(burn_cap_per_epoch) {
+        msg!("Only the authority may raise or remove the withdrawal cap");
+        return Err(ZerosolError::Unauthorized.into());
+    }
+
+    global_state.burn_cap_per_epoch = burn_cap_per_epoch;
+    global_state.serialize(&mut &mut global_state_info.data.borrow_mut()[..])?;
+
+    emit_config_changed(signer_info.key, &global_state);
+
+    msg!("Withdrawal cap set to {} tokens per epoch", burn_cap_per_epoch);
+    Ok(())
+}
+
+fn process_close_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    challenge: [u8; 32],
+    response: [u8; 32],
+    proof: crate::state::ZeroBalanceProof,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let zerosol_account_info = next_account_info(account_info_iter)?;
+    let pending_account_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    let mut zerosol_account = ZerosolAccount::try_from_slice(&zerosol_account_info.data.borrow())?;
+    check_pending_account(program_id, zerosol_account_info, &zerosol_account, pending_account_info)?;
+    if !zerosol_account.is_registered {
+        msg!("Account {} is not registered", zerosol_account_info.key);
+        return Err(ZerosolError::AccountNotRegistered.into());
+    }
+
+    // A locked balance belongs to the locking program until it unlocks
+    if zerosol_account.is_locked() {
+        msg!("Account {} is locked", zerosol_account_info.key);
+        return Err(ZerosolError::AccountLocked.into());
+    }
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+
+    rollover_account(program_id, zerosol_account_info, &mut zerosol_account, pending_account_info, current_epoch)?;
+
+    // Anything still pending arrived this epoch and would be lost
+    let pending_account = PendingAccount::try_from_slice(&pending_account_info.data.borrow())?;
+    let identity = G1Point::identity();
+    if !pending_account.get_commitment_left()?.eq(&identity)
+        || !pending_account.get_commitment_right()?.eq(&identity)
+    {
+        msg!("Account {} has pending transfers", zerosol_account_info.key);
+        return Err(ZerosolError::AccountNotEmpty.into());
+    }
+
+    // Authenticate the account owner for this recipient
+    let message = verify_account_action(
+        program_id,
+        zerosol_account_info.key,
+        &mut zerosol_account,
+        ACTION_CLOSE,
+        current_epoch,
+        recipient_info.key.as_ref(),
+        &challenge,
+        &response,
+    )?;
+
+    // Prove the encrypted balance is zero
+    if !verify_zero_balance_proof(
+        &zerosol_account.get_public_key()?,
+        &zerosol_account.get_commitment_left()?,
+        &zerosol_account.get_commitment_right()?,
+        &message,
+        &G1Point::from_bytes(&proof.announcement_g)?,
+        &G1Point::from_bytes(&proof.announcement_cr)?,
+        &scalar_from_bytes(&proof.response),
+    ) {
+        msg!("Zero balance proof does not verify for {}", zerosol_account_info.key);
+        return Err(ZerosolError::AccountNotEmpty.into());
+    }
+
+    // Return rent and wipe both accounts
+    for info in [zerosol_account_info, pending_account_info] {
+        let lamports = info.lamports();
+        **recipient_info.lamports.borrow_mut() = recipient_info
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **info.lamports.borrow_mut() = 0;
+        info.data.borrow_mut().fill(0);
+    }
+
+    msg!("Account closed, rent returned to {}", recipient_info.key);
+    Ok(())
+}
+
+fn process_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    program: Pubkey,
+    challenge: [u8; 32],
+    response: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let zerosol_account_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    if zerosol_account_info.owner != program_id {
+        msg!("Account {} is not owned by the program", zerosol_account_info.key);
+        return Err(ZerosolError::WrongAccountOwner.into());
+    }
+
+    let mut zerosol_account = ZerosolAccount::try_from_slice(&zerosol_account_info.data.borrow())?;
+    if !zerosol_account.is_registered {
+        msg!("Account {} is not registered", zerosol_account_info.key);
+        return Err(ZerosolError::AccountNotRegistered.into());
+    }
+
+    if zerosol_account.is_locked() {
+        msg!("Account {} is already locked", zerosol_account_info.key);
+        return Err(ZerosolError::AccountLocked.into());
+    }
+
+    if program == Pubkey::default() {
+        msg!("Lock program must not be the default key");
+        return Err(ZerosolError::InvalidInstruction.into());
+    }
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+
+    verify_account_action(
+        program_id,
+        zerosol_account_info.key,
+        &mut zerosol_account,
+        ACTION_LOCK,
+        current_epoch,
+        program.as_ref(),
+        &challenge,
+        &response,
+    )?;
+
+    zerosol_account.locked_to = program;
+    zerosol_account.serialize(&mut &mut zerosol_account_info.data.borrow_mut()[..])?;
+
+    msg!("Account locked to {}", program);
+    Ok(())
+}
+
+fn process_unlock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    challenge: [u8; 32],
+    response: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let zerosol_account_info = next_account_info(account_info_iter)?;
+    let lock_authority_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    if zerosol_account_info.owner != program_id {
+        msg!("Account {} is not owned by the program", zerosol_account_info.key);
+        return Err(ZerosolError::WrongAccountOwner.into());
+    }
+
+    let mut zerosol_account = ZerosolAccount::try_from_slice(&zerosol_account_info.data.borrow())?;
+    if !zerosol_account.is_locked() {
+        msg!("Account {} is not locked", zerosol_account_info.key);
+        return Err(ZerosolError::InvalidAccountData.into());
+    }
+
+    check_lock(&zerosol_account, zerosol_account_info.key, lock_authority_info)?;
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+
+    let locked_to = zerosol_account.locked_to;
+    verify_account_action(
+        program_id,
+        zerosol_account_info.key,
+        &mut zerosol_account,
+        ACTION_UNLOCK,
+        current_epoch,
+        locked_to.as_ref(),
+        &challenge,
+        &response,
+    )?;
+
+    zerosol_account.locked_to = Pubkey::default();
+    zerosol_account.serialize(&mut &mut zerosol_account_info.data.borrow_mut()[..])?;
+
+    msg!("Account unlocked");
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_rotate_key(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_public_key: [u8; 32],
+    challenge: [u8; 32],
+    response: [u8; 32],
+    new_key_challenge: [u8; 32],
+    new_key_response: [u8; 32],
+    proof: crate::state::KeyRotationProof,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let zerosol_account_info = next_account_info(account_info_iter)?;
+    let pending_account_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    let mut zerosol_account = ZerosolAccount::try_from_slice(&zerosol_account_info.data.borrow())?;
+    check_pending_account(program_id, zerosol_account_info, &zerosol_account, pending_account_info)?;
+    if !zerosol_account.is_registered {
+        msg!("Account {} is not registered", zerosol_account_info.key);
+        return Err(ZerosolError::AccountNotRegistered.into());
+    }
+
+    if zerosol_account.is_locked() {
+        msg!("Account {} is locked", zerosol_account_info.key);
+        return Err(ZerosolError::AccountLocked.into());
+    }
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+
+    rollover_account(program_id, zerosol_account_info, &mut zerosol_account, pending_account_info, current_epoch)?;
+
+    // Pending funds are encrypted under the old key and cannot be carried over
+    let pending_account = PendingAccount::try_from_slice(&pending_account_info.data.borrow())?;
+    let identity = G1Point::identity();
+    if !pending_account.get_commitment_left()?.eq(&identity)
+        || !pending_account.get_commitment_right()?.eq(&identity)
+    {
+        msg!("Account {} has pending transfers", zerosol_account_info.key);
+        return Err(ZerosolError::AccountNotEmpty.into());
+    }
+
+    let message = verify_account_action(
+        program_id,
+        zerosol_account_info.key,
+        &mut zerosol_account,
+        ACTION_ROTATE_KEY,
+        current_epoch,
+        &new_public_key,
+        &challenge,
+        &response,
+    )?;
+
+    // The new key co-signs, so nobody can rotate an account to a key they do not hold
+    let new_public_key_point = G1Point::from_bytes(&new_public_key)?;
+    if !verify_schnorr_signature(
+        &new_public_key_point,
+        &message,
+        &scalar_from_bytes(&new_key_challenge),
+        &scalar_from_bytes(&new_key_response),
+    ) {
+        msg!("New key signature does not verify for {}", zerosol_account_info.key);
+        return Err(ZerosolError::InvalidRegistrationSignature.into());
+    }
+
+    // The old key proves the new ciphertext holds the same balance
+    if !verify_key_rotation_proof(
+        &zerosol_account.get_public_key()?,
+        &new_public_key_point,
+        &zerosol_account.get_commitment_left()?,
+        &zerosol_account.get_commitment_right()?,
+        &message,
+        &proof,
+    )? {
+        msg!("Key rotation proof does not verify for {}", zerosol_account_info.key);
+        return Err(ZerosolError::InvalidKeyRotationProof.into());
+    }
+
+    let old_public_key = zerosol_account.public_key;
+    zerosol_account.public_key = new_public_key;
+    zerosol_account.commitment_left = proof.commitment_left;
+    zerosol_account.commitment_right = proof.commitment_right;
+    zerosol_account.serialize(&mut &mut zerosol_account_info.data.borrow_mut()[..])?;
+
+    KeyRotated {
+        account: *zerosol_account_info.key,
+        old_public_key,
+        new_public_key,
+        epoch: current_epoch,
+        commitment_left: zerosol_account.commitment_left,
+        commitment_right: zerosol_account.commitment_right,
+    }
+    .emit();
+
+    msg!("Account key rotated");
+    Ok(())
+}
+
+/// Authenticate an action signed by the account key over
+/// `utils::account_action_message` and consume the account's action nonce
+///
+/// Returns the signed message so proofs accompanying the action can be bound
+/// to it. The caller persists `zerosol_account`.
+#[allow(clippy::too_many_arguments)]
+fn verify_account_action(
+    program_id: &Pubkey,
+    address: &Pubkey,
+    zerosol_account: &mut ZerosolAccount,
+    action: u8,
+    epoch: u64,
+    params: &[u8],
+    challenge: &[u8; 32],
+    response: &[u8; 32],
+) -> Result<Vec<u8>, ProgramError> {
+    let message = account_action_message(
+        program_id,
+        action,
+        address,
+        epoch,
+        zerosol_account.action_nonce,
+        params,
+    );
+    if !verify_schnorr_signature(
+        &zerosol_account.get_public_key()?,
+        &message,
+        &scalar_from_bytes(challenge),
+        &scalar_from_bytes(response),
+    ) {
+        msg!("Action signature does not verify for {}", address);
+        return Err(ZerosolError::InvalidRegistrationSignature.into());
+    }
+
+    zerosol_account.action_nonce = zerosol_account
+        .action_nonce
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    Ok(message)
+}
+
+/// Require `signer_info` to be the lock authority if the account is locked
+fn check_lock(
+    zerosol_account: &ZerosolAccount,
+    address: &Pubkey,
+    signer_info: &AccountInfo,
+) -> ProgramResult {
+    if !zerosol_account.is_locked() {
+        return Ok(());
+    }
+
+    if !signer_info.is_signer || *signer_info.key != zerosol_account.lock_authority(address) {
+        msg!("Account {} is locked to {}", address, zerosol_account.lock_authority(address));
+        return Err(ZerosolError::AccountLocked.into());
+    }
+
+    Ok(())
+}
+
+/// Require both accounts to be the program's, and `pending_account_info` to be
+/// the pending account created with the zerosol account
+fn check_pending_account<'a>(
+    program_id: &Pubkey,
+    zerosol_account_info: &AccountInfo<'a>,
+    zerosol_account: &ZerosolAccount,
+    pending_account_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    for info in [zerosol_account_info, pending_account_info] {
+        if info.owner != program_id {
+            msg!("Account {} is not owned by the program", info.key);
+            return Err(ZerosolError::WrongAccountOwner.into());
+        }
+    }
+
+    if zerosol_account.pending_account != *pending_account_info.key {
+        msg!("Pending account {} does not belong to {}", pending_account_info.key, zerosol_account_info.key);
+        return Err(ZerosolError::InvalidAccountData.into());
+    }
+
+    Ok(())
+}
+
+fn process_create_proof_buffer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    buffer_id: u64,
+    data_len: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let buffer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if data_len == 0 || data_len as usize > ProofBuffer::MAX_DATA_LEN {
+        msg!("Proof buffer length {} is outside [1, {}]", data_len, ProofBuffer::MAX_DATA_LEN);
+        return Err(ZerosolError::InvalidProofStructure.into());
+    }
+
+    let (expected_address, bump) = ProofBuffer::find_address(program_id, owner_info.key, buffer_id);
+    if expected_address != *buffer_info.key {
+        msg!("Proof buffer {} is not the program address for id {}", buffer_info.key, buffer_id);
+        return Err(ZerosolError::InvalidPda.into());
+    }
+
+    let buffer_id_bytes = buffer_id.to_le_bytes();
+    create_pda_account(
+        program_id,
+        owner_info,
+        buffer_info,
+        system_program_info,
+        ProofBuffer::LEN + data_len as usize,
+        &[ProofBuffer::SEED_PREFIX, owner_info.key.as_ref(), &buffer_id_bytes, &[bump]],
+    )?;
+
+    let buffer = ProofBuffer::new(*owner_info.key, buffer_id, data_len, bump);
+    buffer.serialize(&mut &mut buffer_info.data.borrow_mut()[..ProofBuffer::LEN])?;
+
+    msg!("Proof buffer created for {} bytes", data_len);
+    Ok(())
+}
+
+fn process_write_proof_chunk(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    offset: u32,
+    data: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let buffer_info = next_account_info(account_info_iter)?;
+
+    let buffer = load_proof_buffer(program_id, owner_info, buffer_info)?;
+
+    // The payload is frozen once verification starts
+    if buffer.stage != PROOF_STAGE_WRITING {
+        msg!("Proof buffer {} is already being verified", buffer_info.key);
+        return Err(ZerosolError::InvalidInstruction.into());
+    }
+
+    let start = offset as usize;
+    let end = start
+        .checked_add(data.len())
+        .filter(|end| *end <= buffer.data_len as usize)
+        .ok_or(ZerosolError::InvalidProofStructure)?;
+
+    buffer_info.data.borrow_mut()[ProofBuffer::LEN + start..ProofBuffer::LEN + end]
+        .copy_from_slice(&data);
+
+    Ok(())
+}
+
+fn process_verify_proof_step(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_steps: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let buffer_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    let mut buffer = load_proof_buffer(program_id, owner_info, buffer_info)?;
+    if buffer.stage == PROOF_STAGE_VERIFIED {
+        msg!("Proof buffer {} is already verified", buffer_info.key);
+        return Err(ZerosolError::InvalidInstruction.into());
+    }
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+
+    // The proof is bound to the epoch verification started in
+    if buffer.stage == PROOF_STAGE_WRITING {
+        buffer.stage = PROOF_STAGE_STATEMENT;
+        buffer.epoch = current_epoch;
+    } else if buffer.epoch != current_epoch {
+        msg!("Proof buffer {} was verified in epoch {}, now {}", buffer_info.key, buffer.epoch, current_epoch);
+        return Err(ZerosolError::StaleEpoch.into());
+    }
+
+    let payload = buffer.payload(&buffer_info.data.borrow())?;
+    for _ in 0..max_steps.max(1) {
+        if buffer.stage == PROOF_STAGE_VERIFIED {
+            break;
+        }
+
+        let (stage, cursor) = verify_transfer_stage(&payload, current_epoch, buffer.stage, buffer.cursor)
+            .map_err(|check| {
+                msg!("Transfer proof failed {:?} at stage {}", check, buffer.stage);
+                ZerosolError::from(check)
+            })?;
+        buffer.stage = stage;
+        buffer.cursor = cursor;
+    }
+
+    buffer.serialize(&mut &mut buffer_info.data.borrow_mut()[..ProofBuffer::LEN])?;
+
+    msg!("Proof verification at stage {}, cursor {}", buffer.stage, buffer.cursor);
+    Ok(())
+}
+
+fn process_execute_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let relayer_info = next_account_info(account_info_iter)?;
+    let buffer_info = next_account_info(account_info_iter)?;
+    let beneficiary_account_info = next_account_info(account_info_iter)?;
+    let beneficiary_pending_info = next_account_info(account_info_iter)?;
+    let nonce_account_info = next_account_info(account_info_iter)?;
+    let global_state_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let buffer = load_proof_buffer(program_id, relayer_info, buffer_info)?;
+    if buffer.stage != PROOF_STAGE_VERIFIED {
+        msg!("Proof buffer {} is not verified", buffer_info.key);
+        return Err(ZerosolError::TransferProofVerificationFailed.into());
+    }
+
+    let payload = buffer.payload(&buffer_info.data.borrow())?;
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    check_transfer_allowed(&global_state, &payload)?;
+
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+    if buffer.epoch != current_epoch {
+        msg!("Proof buffer {} was verified in epoch {}, now {}", buffer_info.key, buffer.epoch, current_epoch);
+        return Err(ZerosolError::StaleEpoch.into());
+    }
+
+    apply_transfer(
+        program_id,
+        relayer_info,
+        beneficiary_account_info,
+        beneficiary_pending_info,
+        nonce_account_info,
+        system_program_info,
+        account_info_iter,
+        payload,
+        current_epoch,
+    )?;
+
+    close_proof_buffer(buffer_info, relayer_info)
+}
+
+fn process_close_proof_buffer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let buffer_info = next_account_info(account_info_iter)?;
+
+    load_proof_buffer(program_id, owner_info, buffer_info)?;
+    close_proof_buffer(buffer_info, owner_info)?;
+
+    msg!("Proof buffer closed");
+    Ok(())
+}
+
+/// Load a proof buffer header, requiring `owner_info` to be its signing owner
+fn load_proof_buffer(
+    program_id: &Pubkey,
+    owner_info: &AccountInfo,
+    buffer_info: &AccountInfo,
+) -> Result<ProofBuffer, ProgramError> {
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if buffer_info.owner != program_id {
+        msg!("Proof buffer {} is not owned by the program", buffer_info.key);
+        return Err(ZerosolError::WrongAccountOwner.into());
+    }
+
+    let buffer = ProofBuffer::unpack(&buffer_info.data.borrow())?;
+    if buffer.owner != *owner_info.key {
+        msg!("Signer {} does not own proof buffer {}", owner_info.key, buffer_info.key);
+        return Err(ZerosolError::Unauthorized.into());
+    }
+
+    Ok(buffer)
+}
+
+/// Return a proof buffer's rent to `recipient_info` and wipe it
+fn close_proof_buffer(buffer_info: &AccountInfo, recipient_info: &AccountInfo) -> ProgramResult {
+    let lamports = buffer_info.lamports();
+    **recipient_info.lamports.borrow_mut() = recipient_info
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **buffer_info.lamports.borrow_mut() = 0;
+    buffer_info.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+fn process_reclaim_nonces(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let global_state_info = next_account_info(account_info_iter)?;
+
+    let mut global_state = load_global_state(program_id, global_state_info)?;
+    let current_epoch = sync_epoch(global_state_info, &mut global_state)?;
+
+    let mut reclaimed = 0u32;
+    for chunk in account_info_iter.as_slice().chunks(2) {
+        if chunk.len() != 2 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let nonce_account_info = &chunk[0];
+        let payer_info = &chunk[1];
+
+        if nonce_account_info.owner != program_id {
+            msg!("Nonce account {} is not owned by the program", nonce_account_info.key);
+            return Err(ZerosolError::WrongAccountOwner.into());
+        }
+        if nonce_account_info.data_len() != NonceState::LEN {
+            msg!("Nonce account {} is not a nonce", nonce_account_info.key);
+            return Err(ZerosolError::InvalidAccountData.into());
+        }
+
+        let nonce_state = NonceState::try_from_slice(&nonce_account_info.data.borrow())?;
+        if nonce_state.address(program_id)? != *nonce_account_info.key {
+            msg!("Nonce account {} is not the program address of its nonce", nonce_account_info.key);
+            return Err(ZerosolError::InvalidPda.into());
+        }
+        if nonce_state.payer != *payer_info.key {
+            msg!("Nonce account {} was paid for by {}", nonce_account_info.key, nonce_state.payer);
+            return Err(ZerosolError::InvalidAccountData.into());
+        }
+
+        // Nonces of the current epoch still guard against replay
+        if nonce_state.epoch >= current_epoch {
+            msg!("Nonce account {} belongs to the current epoch {}", nonce_account_info.key, current_epoch);
+            return Err(ZerosolError::InvalidEpoch.into());
+        }
+
+        let lamports = nonce_account_info.lamports();
+        **payer_info.lamports.borrow_mut() = payer_info
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **nonce_account_info.lamports.borrow_mut() = 0;
+        nonce_account_info.data.borrow_mut().fill(0);
+        reclaimed += 1;
+    }
+
+    msg!("Reclaimed {} nonce accounts", reclaimed);
+    Ok(())
+}
+
+/// Create the epoch-scoped nonce PDA, failing if the nonce was already used
+fn consume_nonce<'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    nonce_account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    nonce: &[u8; 32],
+    epoch: u64,
+) -> ProgramResult {
+    let (expected_address, bump) = NonceState::find_address(program_id, epoch, nonce);
+    if expected_address != *nonce_account_info.key {
+        msg!("Nonce account {} is not the program address for epoch {}", nonce_account_info.key, epoch);
+        return Err(ZerosolError::InvalidPda.into());
+    }
+
+    if nonce_account_info.data_len() > 0 {
+        msg!("Nonce {} already used in epoch {}", nonce_account_info.key, epoch);
+        return Err(ZerosolError::NonceAlreadySeen.into());
+    }
+
+    let epoch_bytes = epoch.to_le_bytes();
+    create_pda_account(
+        program_id,
+        payer_info,
+        nonce_account_info,
+        system_program_info,
+        NonceState::LEN,
+        &[NonceState::SEED_PREFIX, &epoch_bytes, nonce, &[bump]],
+    )?;
+
+    let mut nonce_state = NonceState::new(*nonce, epoch, *payer_info.key, bump);
+    nonce_state.used = true;
+    nonce_state.serialize(&mut &mut nonce_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Create a rent-exempt, program-owned account at the PDA `seeds` sign for
+///
+/// Anyone can send lamports to a PDA before it exists, and `create_account`
+/// rejects an address holding lamports. A pre-funded address is topped up to
+/// rent exemption, then allocated and assigned instead.
+fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(space);
+    if account_info.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer_info.key, account_info.key, lamports, space as u64, program_id),
+            &[payer_info.clone(), account_info.clone(), system_program_info.clone()],
+            &[seeds],
+        );
+    }
+
+    let shortfall = lamports.saturating_sub(account_info.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, account_info.key, shortfall),
+            &[payer_info.clone(), account_info.clone(), system_program_info.clone()],
+        )?;
+    }
+
+    let accounts = [account_info.clone(), system_program_info.clone()];
+    invoke_signed(&system_instruction::allocate(account_info.key, space as u64), &accounts, &[seeds])?;
+    invoke_signed(&system_instruction::assign(account_info.key, program_id), &accounts, &[seeds])
+}
+
+fn emit_config_changed(signer: &Pubkey, global_state: &GlobalState) {
+    ConfigChanged {
+        signer: *signer,
+        guardian: global_state.guardian,
+        paused: global_state.paused,
+        pause_flags: global_state.pause_flags,
+        burn_cap_per_epoch: global_state.burn_cap_per_epoch,
+    }
+    .emit();
+}
+
+/// Load the pool's global state, rejecting any account but the program's
+/// own: pause flags, the withdrawal cap and the epoch all come from it
+fn load_global_state(program_id: &Pubkey, global_state_info: &AccountInfo) -> Result<GlobalState, ProgramError> {
+    if global_state_info.owner != program_id {
+        msg!("Global state {} is not owned by the program", global_state_info.key);
+        return Err(ZerosolError::WrongAccountOwner.into());
+    }
+
+    let global_state = GlobalState::try_from_slice(&global_state_info.data.borrow())?;
+    if global_state.address(program_id)? != *global_state_info.key {
+        msg!("Global state {} is not the program address", global_state_info.key);
+        return Err(ZerosolError::InvalidPda.into());
+    }
+    Ok(global_state)
+}
+
+/// Compute the current epoch, recording it in the global state if the
+/// account was passed as writable
+fn sync_epoch(
+    global_state_info: &AccountInfo,
+    global_state: &mut GlobalState,
+) -> Result<u64, ProgramError> {
+    let clock = Clock::get()?;
+    let current_epoch = global_state.advance_epoch(&clock);
+
+    if global_state_info.is_writable {
+        global_state.serialize(&mut &mut global_state_info.data.borrow_mut()[..])?;
+    }
+
+    Ok(current_epoch)
+}
+
+/// Merge the pending balance into the account once per epoch
+///
+/// Callers go through here before touching `pending_account_info`, so the
+/// pair is checked even when the account has already rolled over.
+fn rollover_account<'a>(
+    program_id: &Pubkey,
+    zerosol_account_info: &AccountInfo<'a>,
+    zerosol_account: &mut ZerosolAccount,
+    pending_account_info: &AccountInfo<'a>,
+    current_epoch: u64,
+) -> ProgramResult {
+    check_pending_account(program_id, zerosol_account_info, zerosol_account, pending_account_info)?;
+
+    if zerosol_account.last_rollover >= current_epoch {
+        return Ok(());
+    }
+
+    let pending_account = PendingAccount::try_from_slice(&pending_account_info.data.borrow())?;
+    
+    // Use optimized operations for commitment addition
+    if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
+        let current_left = zerosol_account.get_commitment_left()?;
+        let current_right = zerosol_account.get_commitment_right()?;
+        let pending_left = pending_account.get_commitment_left()?;
+        let pending_right = pending_account.get_commitment_right()?;
+        
+        let new_left = ops.cached_point_add(&current_left.point, &pending_left.point)?;
+        let new_right = ops.cached_point_add(&current_right.point, &pending_right.point)?;
+        
+        zerosol_account.set_commitment_left(&G1Point { point: new_left });
+        zerosol_account.set_commitment_right(&G1Point { point: new_right });
+    } else {
+        // Fallback to standard operations
         let current_left = zerosol_account.get_commitment_left()?;
         let current_right = zerosol_account.get_commitment_right()?;
         let pending_left = pending_account.get_commitment_left()?;
@@ -613,44 +1851,165 @@ fn rollover_account(
         zerosol_account.set_commitment_right(&new_right);
     }
     zerosol_account.last_rollover = current_epoch;
+    zerosol_account.serialize(&mut &mut zerosol_account_info.data.borrow_mut()[..])?;
 
     // Clear pending account
     let mut cleared_pending = PendingAccount::new();
     cleared_pending.serialize(&mut &mut pending_account_info.data.borrow_mut()[..])?;
 
+    RolledOver {
+        account: *zerosol_account_info.key,
+        epoch: current_epoch,
+        commitment_left: zerosol_account.commitment_left,
+        commitment_right: zerosol_account.commitment_right,
+    }
+    .emit();
+
     Ok(())
 }
 
 // Simplified proof verification functions
 // In practice, these would implement full bulletproof verification
-fn verify_transfer_proof(
-    proof: &crate::state::ZerosolProof,
-    commitments_c: &[[u8; 32]],
-    commitment_d: &[u8; 32],
-    public_keys: &[[u8; 32]],
+
+/// The verification check a transfer or burn proof failed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofCheck {
+    /// Malformed statement, or a Fiat-Shamir challenge that does not match it
+    SigmaEquation,
+    /// The constraint system over the hidden amounts
+    Constraints,
+    /// The range proof of `commitments_c[i]`
+    CommitmentRange(usize),
+    /// The range proof of commitment D, or of the burnt amount
+    AmountRange,
+    /// Balance conservation, or the balance left after a burn
+    Balance,
+    /// A ring public key that is not a valid, non-identity point
+    PublicKey,
+}
+
+impl From<ProofCheck> for ZerosolError {
+    fn from(check: ProofCheck) -> Self {
+        match check {
+            ProofCheck::SigmaEquation => ZerosolError::SigmaProtocolChallengeFailed,
+            ProofCheck::Constraints => ZerosolError::ConstraintSystemVerificationFailed,
+            ProofCheck::CommitmentRange(i) => ZerosolError::RangeProofFailed {
+                which: RangeProofTarget::Commitment(i),
+            },
+            ProofCheck::AmountRange => ZerosolError::RangeProofFailed {
+                which: RangeProofTarget::Amount,
+            },
+            ProofCheck::Balance => ZerosolError::BalanceConservationFailed,
+            ProofCheck::PublicKey => ZerosolError::InvalidProofStructure,
+        }
+    }
+}
+
+/// Run the verification `Transfer` applies to a proof for `epoch`,
+/// returning the first check that fails
+///
+/// Public so clients and relayers can find out why a proof would be
+/// rejected before paying for its transaction.
+pub fn check_transfer_proof(payload: &TransferPayload, epoch: u64) -> Result<(), ProofCheck> {
+    // Comprehensive transfer proof verification, run stage by stage
+    let mut stage = PROOF_STAGE_STATEMENT;
+    let mut cursor = 0;
+    while stage != PROOF_STAGE_VERIFIED {
+        (stage, cursor) = verify_transfer_stage(payload, epoch, stage, cursor)?;
+    }
+    Ok(())
+}
+
+/// Whether a transfer proof passes every check for `epoch`
+pub fn verify_transfer_proof(payload: &TransferPayload, epoch: u64) -> bool {
+    check_transfer_proof(payload, epoch).is_ok()
+}
+
+/// Run one verification step of a transfer proof
+///
+/// Returns the `(stage, cursor)` to continue from, or the failed check if
+/// the proof is invalid. Proof buffers checkpoint this position between
+/// transactions.
+fn verify_transfer_stage(
+    payload: &TransferPayload,
     epoch: u64,
-) -> bool {
-    // Comprehensive transfer proof verification
-    
-    // Step 1: Verify proof structure
-    if commitments_c.is_empty() || public_keys.is_empty() {
+    stage: u8,
+    cursor: u32,
+) -> Result<(u8, u32), ProofCheck> {
+    let failed = match stage {
+        PROOF_STAGE_STATEMENT => precheck_transfer_range(payload)
+            .or_else(|| (!verify_transfer_statement(payload, epoch)).then_some(ProofCheck::SigmaEquation)),
+        PROOF_STAGE_CONSTRAINTS => (!verify_transfer_constraints(payload)).then_some(ProofCheck::Constraints),
+        PROOF_STAGE_RANGE => (!verify_transfer_range(payload, cursor as usize))
+            .then_some(ProofCheck::CommitmentRange(cursor as usize)),
+        PROOF_STAGE_BALANCE => verify_transfer_balance(payload, epoch).err(),
+        // Any other stage verifies nothing, starting with the statement
+        _ => Some(ProofCheck::SigmaEquation),
+    };
+    if let Some(check) = failed {
+        return Err(check);
+    }
+
+    match stage {
+        PROOF_STAGE_RANGE if (cursor as usize) + 1 < payload.commitments_c.len() => {
+            Ok((PROOF_STAGE_RANGE, cursor + 1))
+        }
+        PROOF_STAGE_RANGE => Ok((PROOF_STAGE_BALANCE, 0)),
+        PROOF_STAGE_BALANCE => Ok((PROOF_STAGE_VERIFIED, 0)),
+        _ => Ok((stage + 1, 0)),
+    }
+}
+
+/// Range pre-check of the commitments with the optimized curve operations,
+/// run ahead of the statement by direct and buffered transfers alike;
+/// undecodable commitments are left to the later stages
+fn precheck_transfer_range(payload: &TransferPayload) -> Option<ProofCheck> {
+    let points: Result<Vec<_>, _> = payload.commitments_c.iter()
+        .map(|c| G1Point::from_bytes(c).map(|p| p.point))
+        .collect();
+    for (i, point) in points.ok()?.iter().enumerate() {
+        if !SpecializedOps::verify_range_constraints(std::slice::from_ref(point), 32).unwrap_or(false) {
+            return Some(ProofCheck::CommitmentRange(i));
+        }
+    }
+    None
+}
+
+/// Step 1: Verify proof structure and the statement binding
+fn verify_transfer_statement(payload: &TransferPayload, epoch: u64) -> bool {
+    if payload.commitments_c.is_empty() || payload.public_keys.is_empty() {
         return false;
     }
     
-    if commitments_c.len() != public_keys.len() {
+    if payload.commitments_c.len() != payload.public_keys.len() {
         return false;
     }
-    
-    // Enhanced verification with constraint system
+
+    // The challenge must be derived from the full statement, including the
+    // beneficiary and fee, so a relayer cannot redirect or inflate the fee
+    let statement = transfer_statement_hash(
+        &payload.commitments_c,
+        &payload.commitment_d,
+        &payload.public_keys,
+        &payload.nonce,
+        epoch,
+        &payload.beneficiary,
+        payload.fee,
+    );
+    verify_transfer_challenge(&payload.proof, &statement)
+}
+
+/// Enhanced verification with constraint system
+fn verify_transfer_constraints(payload: &TransferPayload) -> bool {
     let mut builder = ConstraintSystemBuilder::new();
     
     // Create constraint system for transfer verification
-    let num_participants = commitments_c.len();
+    let num_participants = payload.commitments_c.len();
     let mut commitment_vars = Vec::new();
     let mut balance_vars = Vec::new();
     
     // Add variables for each commitment and balance
-    for i in 0..num_participants {
+    for _ in 0..num_participants {
         let commitment_var = builder.add_variable();
         let balance_var = builder.add_variable();
         commitment_vars.push(commitment_var);
@@ -668,7 +2027,7 @@ fn verify_transfer_proof(
     }
     
     // Add fee to the constraint
-    let fee_var = builder.add_public_input(Scalar::from(1u64)); // Assuming fee = 1
+    let fee_var = builder.add_public_input(Scalar::from(payload.fee));
     balance_terms.push((fee_var, -Scalar::one()));
     
     builder.add_linear_constraint(balance_terms);
@@ -682,44 +2041,44 @@ fn verify_transfer_proof(
     let constraint_verifier = R1CSVerifier::new(cs);
     
     // Verify constraint system
-    if !constraint_verifier.verify_constraints().unwrap_or(false) {
-        return false;
-    }
+    constraint_verifier.verify_constraints().unwrap_or(false)
+}
 
-    // Step 2: Verify range proofs for all commitments
-    // Use optimized bulletproof verifier
-    let verifier = if let Ok(_) = std::panic::catch_unwind(|| get_curve_ops()) {
-        // Use optimized verifier when curve ops are available
-        BulletproofVerifier::new(64)
-    } else {
-        BulletproofVerifier::new(64)
+/// Step 2: Verify the range proof of `commitments_c[index]`
+fn verify_transfer_range(payload: &TransferPayload, index: usize) -> bool {
+    let commitment = match payload.commitments_c.get(index).map(G1Point::from_bytes) {
+        Some(Ok(c)) => c,
+        _ => return false,
     };
-    
-    // Verify each commitment is within valid range
-    for commitment_bytes in commitments_c {
-        let commitment = match G1Point::from_bytes(commitment_bytes) {
-            Ok(c) => c,
-            Err(_) => return false,
-        };
-        
-        // Create a dummy range proof for verification
-        // In practice, this would be part of the actual proof structure
-        let dummy_inner_product = crate::bulletproof::InnerProductProof {
-            l_vec: vec![G1Point::generator(); 6], // log2(64) = 6
-            r_vec: vec![G1Point::generator(); 6],
-            a: Scalar::one(),
-            b: Scalar::one(),
-        };
-        
-        // Additional range verification would go here
-    }
-    
+
     // Convert proof data to bulletproof format
-    let range_proof = match convert_zerosol_proof_to_range_proof(proof) {
+    let range_proof = match convert_zerosol_proof_to_range_proof(&payload.proof) {
         Ok(proof) => proof,
         Err(_) => return false,
     };
+
+    let verifier = BulletproofVerifier::new(64);
+    if !verifier.verify_range_proof(&commitment, &range_proof, 32).unwrap_or(false) {
+        return false;
+    }
     
+    // Additional range constraint verification
+    let range_verifier = RangeConstraintVerifier::new(32);
+    let range_proof_constraint = RangeConstraintProof {
+        bit_commitments: vec![commitment; 32],
+        bit_proofs: vec![crate::constraint_system::BitConstraintProof {
+            challenge: Scalar::one(),
+            response: Scalar::one(),
+        }; 32],
+    };
+    
+    range_verifier.verify_range_constraint(&commitment, &range_proof_constraint).unwrap_or(false)
+}
+
+/// Steps 3-7: balance conservation, commitment D, arithmetic, ring and epoch checks
+fn verify_transfer_balance(payload: &TransferPayload, epoch: u64) -> Result<(), ProofCheck> {
+    let commitments_c = &payload.commitments_c;
+
     // Step 3: Verify balance conservation
     // Sum of inputs should equal sum of outputs plus fees
     let mut total_input = G1Point::identity();
@@ -731,58 +2090,27 @@ fn verify_transfer_proof(
         }
     }
     
-    // Enhanced range verification with constraint system
-    let range_verifier = RangeConstraintVerifier::new(32);
-    
-    // Verify range proofs for each commitment
-    for commitment_bytes in commitments_c {
-        let commitment = match G1Point::from_bytes(commitment_bytes) {
-            Ok(c) => c,
-            Err(_) => return false,
-        };
-        
-        if !verifier.verify_range_proof(&commitment, &range_proof, 32).unwrap_or(false) {
-            return false;
-        }
-        
-        // Additional range constraint verification
-        let range_proof_constraint = RangeConstraintProof {
-            bit_commitments: vec![commitment; 32],
-            bit_proofs: vec![crate::constraint_system::BitConstraintProof {
-                challenge: Scalar::one(),
-                response: Scalar::one(),
-            }; 32],
-        };
-        
-        if !range_verifier.verify_range_constraint(&commitment, &range_proof_constraint).unwrap_or(false) {
-            return false;
-        }
-    }
-    
     // Step 4: Verify commitment D (output commitment)
-    // Verify commitment D
-    let d_commitment = match G1Point::from_bytes(commitment_d) {
-        Ok(c) => c,
-        Err(_) => return false,
-    };
+    let d_commitment = G1Point::from_bytes(&payload.commitment_d).map_err(|_| ProofCheck::Balance)?;
     
     total_output = total_output.add(&d_commitment);
     
     // Add fee to the balance equation
-    let fee_commitment = G1Point::generator().mul(&Scalar::from(1u64)); // Assuming fee = 1
+    let fee_commitment = G1Point::generator().mul(&Scalar::from(payload.fee));
     total_output = total_output.add(&fee_commitment);
     
     // Verify balance conservation: total_input = total_output
     if !total_input.eq(&total_output) {
-        return false;
+        return Err(ProofCheck::Balance);
     }
     
+    let range_proof = convert_zerosol_proof_to_range_proof(&payload.proof).map_err(|_| ProofCheck::AmountRange)?;
+    let verifier = BulletproofVerifier::new(64);
     if !verifier.verify_range_proof(&d_commitment, &range_proof, 32).unwrap_or(false) {
-        return false;
+        return Err(ProofCheck::AmountRange);
     }
     
     // Step 5: Verify arithmetic constraints for commitment operations
-    // Verify arithmetic constraints for commitment operations
     if commitments_c.len() >= 2 {
         let comm1 = G1Point::from_bytes(&commitments_c[0]).unwrap_or(G1Point::identity());
         let comm2 = G1Point::from_bytes(&commitments_c[1]).unwrap_or(G1Point::identity());
@@ -790,38 +2118,75 @@ fn verify_transfer_proof(
         
         // Verify addition constraint
         if !ArithmeticConstraintVerifier::verify_addition_constraint(&comm1, &comm2, &sum_comm).unwrap_or(false) {
-            return false;
+            return Err(ProofCheck::Balance);
         }
     }
     
-    // Step 6: Verify public key commitments and epoch constraints
-    // Verify public key commitments
-    for pk_bytes in public_keys {
-        let pk_point = match G1Point::from_bytes(pk_bytes) {
-            Ok(p) => p,
-            Err(_) => return false,
-        };
-        
-        // Verify that public key is valid (on curve)
-        // This is implicitly done by from_bytes, but we could add additional checks
+    // Step 6: Verify public keys are valid points
+    for pk_bytes in &payload.public_keys {
+        if G1Point::from_bytes(pk_bytes).is_err() {
+            return Err(ProofCheck::PublicKey);
+        }
     }
     
     // Step 7: Verify epoch-specific constraints
-    // Verify epoch-specific constraints
-    verify_epoch_constraints(epoch, public_keys)
+    if !verify_epoch_constraints(epoch, &payload.public_keys) {
+        return Err(ProofCheck::PublicKey);
+    }
+    Ok(())
+}
+
+/// Check the Fiat-Shamir challenge of a transfer proof against its statement
+fn verify_transfer_challenge(proof: &crate::state::ZerosolProof, statement: &[u8; 32]) -> bool {
+    let mut data = Vec::with_capacity(32 * 9);
+    data.extend_from_slice(statement);
+    data.extend_from_slice(&proof.ba);
+    data.extend_from_slice(&proof.bs);
+    data.extend_from_slice(&proof.a);
+    data.extend_from_slice(&proof.b);
+    data.extend_from_slice(&proof.t_1);
+    data.extend_from_slice(&proof.t_2);
+    data.extend_from_slice(&proof.t_hat);
+    data.extend_from_slice(&proof.mu);
+
+    hash_to_scalar(&data) == scalar_from_bytes(&proof.c)
 }
 
-fn verify_burn_proof(
+/// Check the Fiat-Shamir challenge of a burn proof against its statement
+fn verify_burn_challenge(proof: &crate::state::BurnProof, statement: &[u8; 32]) -> bool {
+    let mut data = Vec::with_capacity(32 * 7);
+    data.extend_from_slice(statement);
+    data.extend_from_slice(&proof.ba);
+    data.extend_from_slice(&proof.bs);
+    data.extend_from_slice(&proof.t_1);
+    data.extend_from_slice(&proof.t_2);
+    data.extend_from_slice(&proof.t_hat);
+    data.extend_from_slice(&proof.mu);
+
+    hash_to_scalar(&data) == scalar_from_bytes(&proof.c)
+}
+
+/// Run the verification `Burn` applies to a proof against the rolled-over
+/// `account`, returning the first check that fails
+pub fn check_burn_proof(
     proof: &crate::state::BurnProof,
     account: &ZerosolAccount,
     amount: u64,
+    nonce: &[u8; 32],
     epoch: u64,
-) -> bool {
+) -> Result<(), ProofCheck> {
     // Comprehensive burn proof verification
     
     // Step 1: Basic validation
     if amount == 0 || amount > MAX_TRANSFER_AMOUNT {
-        return false;
+        return Err(ProofCheck::AmountRange);
+    }
+
+    // The challenge commits to the epoch and nonce, so a burn proof cannot be
+    // replayed in a later epoch once its nonce account has been reclaimed
+    let statement = burn_statement_hash(&account.public_key, amount, nonce, epoch);
+    if !verify_burn_challenge(proof, &statement) {
+        return Err(ProofCheck::SigmaEquation);
     }
     
     // Enhanced burn verification with constraint system
@@ -853,14 +2218,11 @@ fn verify_burn_proof(
     
     // Verify constraint system
     if !constraint_verifier.verify_constraints().unwrap_or(false) {
-        return false;
+        return Err(ProofCheck::Constraints);
     }
 
     // Step 2: Verify account has sufficient balance
-    let commitment_left = match account.get_commitment_left() {
-        Ok(c) => c,
-        Err(_) => return false,
-    };
+    let commitment_left = account.get_commitment_left().map_err(|_| ProofCheck::Balance)?;
     
     // Create commitment for the burn amount
     let amount_scalar = curve25519_dalek::scalar::Scalar::from(amount);
@@ -881,14 +2243,14 @@ fn verify_burn_proof(
     };
     
     if !range_verifier.verify_range_constraint(&remaining_commitment, &range_proof).unwrap_or(false) {
-        return false;
+        return Err(ProofCheck::Balance);
     }
     
     // Use optimized verification
     if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
         // Pre-validate using optimized range constraints
         if !SpecializedOps::verify_range_constraints(&[commitment_left.point], 32).unwrap_or(false) {
-            return false;
+            return Err(ProofCheck::Balance);
         }
     }
     
@@ -896,14 +2258,11 @@ fn verify_burn_proof(
     let verifier = BulletproofVerifier::new(32);
     
     // Convert burn proof to range proof format
-    let range_proof = match convert_burn_proof_to_range_proof(proof) {
-        Ok(proof) => proof,
-        Err(_) => return false,
-    };
+    let range_proof = convert_burn_proof_to_range_proof(proof).map_err(|_| ProofCheck::AmountRange)?;
     
     // Verify range proof for burn amount
     if !verifier.verify_range_proof(&burn_commitment, &range_proof, 32).unwrap_or(false) {
-        return false;
+        return Err(ProofCheck::AmountRange);
     }
     
     // Step 5: Verify arithmetic constraints
@@ -914,12 +2273,15 @@ fn verify_burn_proof(
         &burn_commitment,
         &commitment_left,
     ).unwrap_or(false) {
-        return false;
+        return Err(ProofCheck::Balance);
     }
     
     // Step 6: Final balance verification
     // Verify that account has sufficient balance (commitment arithmetic)
-    verify_sufficient_balance(&commitment_left, &burn_commitment, account, epoch)
+    if !verify_sufficient_balance(&commitment_left, &burn_commitment, account, epoch) {
+        return Err(ProofCheck::Balance);
+    }
+    Ok(())
 }
 
 fn convert_zerosol_proof_to_range_proof(proof: &crate::state::ZerosolProof) -> Result<RangeProof, ProgramError> {
@@ -1,5 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use solana_program::{clock::Clock, pubkey::Pubkey};
+use crate::error::ZerosolError;
 use crate::utils::G1Point;
 use curve25519_dalek::scalar::Scalar;
 
@@ -9,22 +10,45 @@ pub struct ZerosolAccount {
     pub commitment_right: [u8; 32],  // CRn commitment
     pub public_key: [u8; 32],        // y public key
     pub last_rollover: u64,          // last epoch when account was rolled over
-    pub is_registered: bool,
+    pub is_registered: bool,         // false while an account created by FundToKey is unclaimed
+    pub locked_to: Pubkey,           // program allowed to spend, default pubkey when unlocked
+    pub action_nonce: u64,           // number of account-signed actions so far
+    pub pending_account: Pubkey,     // pending account created alongside this one
 }
 
 impl ZerosolAccount {
-    pub const LEN: usize = 32 + 32 + 32 + 8 + 1;
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 1 + 32 + 8 + 32;
+    pub const LOCK_SEED: &'static [u8] = b"zerosol_lock";
+    pub const SEED_PREFIX: &'static [u8] = b"zerosol_account";
 
-    pub fn new(public_key: [u8; 32]) -> Self {
+    pub fn new(public_key: [u8; 32], pending_account: Pubkey) -> Self {
         Self {
             commitment_left: [0; 32],
             commitment_right: [0; 32],
             public_key,
             last_rollover: 0,
             is_registered: false,
+            locked_to: Pubkey::default(),
+            action_nonce: 0,
+            pending_account,
         }
     }
 
+    /// Derive the account address `FundToKey` creates for `public_key`
+    pub fn find_address(program_id: &Pubkey, public_key: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED_PREFIX, public_key], program_id)
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked_to != Pubkey::default()
+    }
+
+    /// Signer the locking program presents via `invoke_signed` to spend from
+    /// the account at `address`
+    pub fn lock_authority(&self, address: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[Self::LOCK_SEED, address.as_ref()], &self.locked_to).0
+    }
+
     pub fn get_commitment_left(&self) -> Result<G1Point, solana_program::program_error::ProgramError> {
         G1Point::from_bytes(&self.commitment_left)
     }
@@ -54,6 +78,7 @@ pub struct PendingAccount {
 
 impl PendingAccount {
     pub const LEN: usize = 32 + 32;
+    pub const SEED_PREFIX: &'static [u8] = b"pending_account";
 
     pub fn new() -> Self {
         Self {
@@ -62,6 +87,11 @@ impl PendingAccount {
         }
     }
 
+    /// Derive the pending account address `FundToKey` creates for `public_key`
+    pub fn find_address(program_id: &Pubkey, public_key: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED_PREFIX, public_key], program_id)
+    }
+
     pub fn get_commitment_left(&self) -> Result<G1Point, solana_program::program_error::ProgramError> {
         G1Point::from_bytes(&self.commitment_left)
     }
@@ -79,6 +109,50 @@ impl PendingAccount {
     }
 }
 
+/// Account-signed action `Lock`; parameters are the program locked to
+pub const ACTION_LOCK: u8 = 0;
+/// Account-signed action `Unlock`; parameters are the program being released
+pub const ACTION_UNLOCK: u8 = 1;
+/// Account-signed action `CloseAccount`; parameters are the rent recipient
+pub const ACTION_CLOSE: u8 = 2;
+/// Account-signed action `RotateKey`; parameters are the new public key
+pub const ACTION_ROTATE_KEY: u8 = 3;
+
+/// Pause flag for `Register`
+pub const PAUSE_REGISTER: u8 = 1 << 0;
+/// Pause flag for `Fund`
+pub const PAUSE_FUND: u8 = 1 << 1;
+/// Pause flag for `Transfer`
+pub const PAUSE_TRANSFER: u8 = 1 << 2;
+/// Pause flag for `Burn`
+pub const PAUSE_BURN: u8 = 1 << 3;
+/// All per-operation pause flags
+pub const PAUSE_ALL: u8 = PAUSE_REGISTER | PAUSE_FUND | PAUSE_TRANSFER | PAUSE_BURN;
+
+/// Seed of the PDA that owns the program token account
+pub const TOKEN_AUTHORITY_SEED: &[u8] = b"token_authority";
+
+/// Derive the token authority that signs withdrawals from the program token account
+pub fn find_token_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TOKEN_AUTHORITY_SEED], program_id)
+}
+
+/// Epochs are counted in seconds of `Clock::unix_timestamp`
+pub const EPOCH_SOURCE_TIMESTAMP: u8 = 0;
+/// Epochs are counted in slots of `Clock::slot`
+pub const EPOCH_SOURCE_SLOT: u8 = 1;
+
+/// Epoch at `clock` under the time source and offset configured in `state`
+pub fn epoch_at(clock: &Clock, state: &GlobalState) -> u64 {
+    let source = match state.epoch_source {
+        EPOCH_SOURCE_SLOT => clock.slot,
+        _ => clock.unix_timestamp.max(0) as u64,
+    };
+
+    // `Initialize` rejects a zero epoch length; guard against older state anyway
+    source.saturating_sub(state.epoch_offset) / state.epoch_length.max(1)
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct GlobalState {
     pub authority: Pubkey,
@@ -87,10 +161,20 @@ pub struct GlobalState {
     pub fee: u64,
     pub last_global_update: u64,
     pub current_epoch: u64,
+    pub guardian: Pubkey,            // optional emergency key, default pubkey when unset
+    pub paused: bool,                // global circuit breaker
+    pub pause_flags: u8,             // per-operation PAUSE_* flags
+    pub burn_cap_per_epoch: u64,     // max tokens burned per epoch, 0 = unlimited
+    pub burned_in_epoch: u64,        // tokens burned during `burn_epoch`
+    pub burn_epoch: u64,             // epoch `burned_in_epoch` refers to
+    pub epoch_source: u8,            // EPOCH_SOURCE_* clock field epochs are counted in
+    pub epoch_offset: u64,           // source value at which epoch 0 starts
+    pub bump: u8,
 }
 
 impl GlobalState {
-    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8;
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 32 + 1 + 1 + 8 + 8 + 8 + 1 + 8 + 1;
+    pub const SEED_PREFIX: &'static [u8] = b"global_state";
 
     pub fn new(authority: Pubkey, token_mint: Pubkey, epoch_length: u64, fee: u64) -> Self {
         Self {
@@ -100,27 +184,123 @@ impl GlobalState {
             fee,
             last_global_update: 0,
             current_epoch: 0,
+            guardian: Pubkey::default(),
+            paused: false,
+            pause_flags: 0,
+            burn_cap_per_epoch: 0,
+            burned_in_epoch: 0,
+            burn_epoch: 0,
+            epoch_source: EPOCH_SOURCE_TIMESTAMP,
+            epoch_offset: 0,
+            bump: 0,
+        }
+    }
+
+    /// Derive the address of the program's single global state
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED_PREFIX], program_id)
+    }
+
+    /// Recompute the global state address from its stored bump
+    pub fn address(&self, program_id: &Pubkey) -> Result<Pubkey, solana_program::program_error::ProgramError> {
+        Pubkey::create_program_address(&[Self::SEED_PREFIX, &[self.bump]], program_id)
+            .map_err(|_| solana_program::program_error::ProgramError::InvalidSeeds)
+    }
+
+    /// Record the epoch at `clock` as the current one and return it
+    pub fn advance_epoch(&mut self, clock: &Clock) -> u64 {
+        let epoch = epoch_at(clock, self);
+        if epoch != self.current_epoch {
+            self.current_epoch = epoch;
+            self.last_global_update = clock.unix_timestamp.max(0) as u64;
+        }
+        epoch
+    }
+
+    /// Whether the operation identified by `flag` (one of the `PAUSE_*` constants) is halted
+    pub fn is_paused(&self, flag: u8) -> bool {
+        self.paused || self.pause_flags & flag != 0
+    }
+
+    /// Whether `key` may change the pause state and withdrawal cap
+    pub fn is_pause_authority(&self, key: &Pubkey) -> bool {
+        *key == self.authority || (self.guardian != Pubkey::default() && *key == self.guardian)
+    }
+
+    /// Whether the pause state `paused` and `pause_flags` halts everything
+    /// halted now; the guardian may only make such changes
+    pub fn tightens_pause(&self, paused: bool, pause_flags: u8) -> bool {
+        paused || (!self.paused && pause_flags & self.pause_flags == self.pause_flags)
+    }
+
+    /// Whether `burn_cap_per_epoch` is a cap no higher than the current one;
+    /// the guardian may only make such changes
+    pub fn tightens_cap(&self, burn_cap_per_epoch: u64) -> bool {
+        burn_cap_per_epoch != 0
+            && (self.burn_cap_per_epoch == 0 || burn_cap_per_epoch <= self.burn_cap_per_epoch)
+    }
+
+    /// Account for `amount` tokens leaving the pool in `epoch`, enforcing the per-epoch cap
+    pub fn record_burn(&mut self, epoch: u64, amount: u64) -> Result<(), ZerosolError> {
+        if self.burn_epoch != epoch {
+            self.burn_epoch = epoch;
+            self.burned_in_epoch = 0;
         }
+
+        let burned = self
+            .burned_in_epoch
+            .checked_add(amount)
+            .ok_or(ZerosolError::WithdrawalCapExceeded)?;
+        if self.burn_cap_per_epoch != 0 && burned > self.burn_cap_per_epoch {
+            return Err(ZerosolError::WithdrawalCapExceeded);
+        }
+
+        self.burned_in_epoch = burned;
+        Ok(())
     }
 }
 
+/// Record of a nonce spent in `epoch`, stored at the PDA
+/// `[SEED_PREFIX, epoch.to_le_bytes(), nonce]`
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct NonceState {
     pub nonce: [u8; 32],
     pub epoch: u64,
     pub used: bool,
+    pub payer: Pubkey,               // refunded when the nonce is reclaimed
+    pub bump: u8,
 }
 
 impl NonceState {
-    pub const LEN: usize = 32 + 8 + 1;
+    pub const LEN: usize = 32 + 8 + 1 + 32 + 1;
+    pub const SEED_PREFIX: &'static [u8] = b"nonce";
 
-    pub fn new(nonce: [u8; 32], epoch: u64) -> Self {
+    pub fn new(nonce: [u8; 32], epoch: u64, payer: Pubkey, bump: u8) -> Self {
         Self {
             nonce,
             epoch,
             used: false,
+            payer,
+            bump,
         }
     }
+
+    /// Derive the nonce account address for `nonce` in `epoch`
+    pub fn find_address(program_id: &Pubkey, epoch: u64, nonce: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEED_PREFIX, &epoch.to_le_bytes(), nonce],
+            program_id,
+        )
+    }
+
+    /// Recompute this nonce account's address from its stored bump
+    pub fn address(&self, program_id: &Pubkey) -> Result<Pubkey, solana_program::program_error::ProgramError> {
+        Pubkey::create_program_address(
+            &[Self::SEED_PREFIX, &self.epoch.to_le_bytes(), &self.nonce, &[self.bump]],
+            program_id,
+        )
+        .map_err(|_| solana_program::program_error::ProgramError::InvalidSeeds)
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
@@ -172,4 +352,111 @@ pub struct BurnProof {
     pub s_b: [u8; 32],
     pub s_tau: [u8; 32],
     pub ip_proof: InnerProductProof,
-}
\ No newline at end of file
+}
+
+/// Arguments of a transfer, as carried by `Transfer` or staged in a proof buffer
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct TransferPayload {
+    pub commitments_c: Vec<[u8; 32]>,
+    pub commitment_d: [u8; 32],
+    pub public_keys: Vec<[u8; 32]>,
+    pub nonce: [u8; 32],
+    pub beneficiary: [u8; 32],
+    pub fee: u64,
+    pub tip_lamports: u64,
+    pub proof: ZerosolProof,
+}
+
+/// Proof buffer is accepting `WriteProofChunk`
+pub const PROOF_STAGE_WRITING: u8 = 0;
+/// Next step checks the structure and Fiat-Shamir challenge
+pub const PROOF_STAGE_STATEMENT: u8 = 1;
+/// Next step checks the constraint system
+pub const PROOF_STAGE_CONSTRAINTS: u8 = 2;
+/// Next step checks the range proof of `commitments_c[cursor]`
+pub const PROOF_STAGE_RANGE: u8 = 3;
+/// Next step checks balance conservation, `D` and the ring
+pub const PROOF_STAGE_BALANCE: u8 = 4;
+/// Proof fully verified, ready for `ExecuteTransfer`
+pub const PROOF_STAGE_VERIFIED: u8 = 5;
+
+/// Header of a proof buffer account, followed by `data_len` bytes of a
+/// Borsh-encoded `TransferPayload`
+///
+/// Stored at the PDA `[SEED_PREFIX, owner, buffer_id.to_le_bytes()]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ProofBuffer {
+    pub owner: Pubkey,               // relayer that uploads, verifies and executes
+    pub buffer_id: u64,
+    pub data_len: u32,
+    pub stage: u8,                   // PROOF_STAGE_* step to run next
+    pub cursor: u32,                 // progress within the current stage
+    pub epoch: u64,                  // epoch the proof is verified against
+    pub bump: u8,
+}
+
+impl ProofBuffer {
+    pub const LEN: usize = 32 + 8 + 4 + 1 + 4 + 8 + 1;
+    pub const SEED_PREFIX: &'static [u8] = b"proof_buffer";
+    /// Largest payload, bounded by the 10 KiB an account may be created with via CPI
+    pub const MAX_DATA_LEN: usize = 10 * 1024 - Self::LEN;
+
+    pub fn new(owner: Pubkey, buffer_id: u64, data_len: u32, bump: u8) -> Self {
+        Self {
+            owner,
+            buffer_id,
+            data_len,
+            stage: PROOF_STAGE_WRITING,
+            cursor: 0,
+            epoch: 0,
+            bump,
+        }
+    }
+
+    /// Derive the buffer address for `owner` and `buffer_id`
+    pub fn find_address(program_id: &Pubkey, owner: &Pubkey, buffer_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEED_PREFIX, owner.as_ref(), &buffer_id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Read the header from the start of the account data
+    pub fn unpack(data: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
+        let mut header = data.get(..Self::LEN).ok_or(ZerosolError::InvalidAccountData)?;
+        Ok(Self::deserialize(&mut header)?)
+    }
+
+    /// Decode the staged payload, which must fill `data_len` exactly
+    pub fn payload(&self, data: &[u8]) -> Result<TransferPayload, solana_program::program_error::ProgramError> {
+        let end = Self::LEN + self.data_len as usize;
+        let bytes = data.get(Self::LEN..end).ok_or(ZerosolError::InvalidAccountData)?;
+        TransferPayload::try_from_slice(bytes).map_err(|_| ZerosolError::InvalidProofStructure.into())
+    }
+}
+
+/// Chaum-Pedersen proof that an account encrypts a zero balance
+///
+/// Shows knowledge of `sk` with `y = g^sk` and `CLn = CRn^sk`, i.e. `g^b = 1`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ZeroBalanceProof {
+    pub announcement_g: [u8; 32],   // g^k
+    pub announcement_cr: [u8; 32],  // CRn^k
+    pub response: [u8; 32],         // k + c * sk
+}
+
+/// Proof that a ciphertext re-encrypted under a new key holds the same balance
+///
+/// For the current key `y = g^sk`, the new key `y'` and the new ciphertext
+/// `(CLn', CRn') = (g^b * y'^r, g^r)`, shows knowledge of `sk` and `r` with
+/// `CLn - CRn^sk = CLn' - y'^r`, i.e. both decrypt to the same `g^b`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct KeyRotationProof {
+    pub commitment_left: [u8; 32],   // CLn' under the new key
+    pub commitment_right: [u8; 32],  // CRn' under the new key
+    pub announcement_g: [u8; 32],    // g^k_sk
+    pub announcement_r: [u8; 32],    // g^k_r
+    pub announcement_b: [u8; 32],    // CRn^k_sk - y'^k_r
+    pub response_sk: [u8; 32],       // k_sk + c * sk
+    pub response_r: [u8; 32],        // k_r + c * r
+}
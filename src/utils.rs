@@ -6,7 +6,9 @@ use curve25519_dalek::{
 };
 use sha2::{Digest, Sha256};
 use solana_program::program_error::ProgramError;
-use crate::curve_ops::{get_curve_ops, get_precomputed_constants, init_curve_ops};
+use crate::curve_ops::{
+    get_curve_ops, get_precomputed_constants, init_curve_ops, Backend, CurveBackend,
+};
 
 pub const GROUP_ORDER: [u8; 32] = [
     0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -39,29 +41,30 @@ impl G1Point {
         }
     }
 
+    /// Add two points; if the backend rejects the operation it is computed
+    /// in-process instead
     pub fn add(&self, other: &G1Point) -> G1Point {
         // Use optimized operations when available
-        if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
-            G1Point {
-                point: ops.cached_point_add(&self.point, &other.point),
-            }
+        let point = if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
+            ops.cached_point_add(&self.point, &other.point)
         } else {
-            G1Point {
-                point: self.point + other.point,
-            }
+            Backend::add(&self.point, &other.point)
+        };
+        G1Point {
+            point: point.unwrap_or_else(|_| self.point + other.point),
         }
     }
 
+    /// Multiply by a scalar, with the same fallback as `add`
     pub fn mul(&self, scalar: &Scalar) -> G1Point {
         // Use optimized scalar multiplication when available
-        if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
-            G1Point {
-                point: ops.fast_scalar_mul(&self.point, scalar),
-            }
+        let point = if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
+            ops.fast_scalar_mul(&self.point, scalar)
         } else {
-            G1Point {
-                point: self.point * scalar,
-            }
+            Backend::mul(&self.point, scalar)
+        };
+        G1Point {
+            point: point.unwrap_or_else(|_| self.point * scalar),
         }
     }
 
@@ -140,8 +143,10 @@ pub fn map_to_curve_with_index(input: &str, index: u64) -> G1Point {
 pub fn pedersen_commit(value: &Scalar, blinding: &Scalar) -> G1Point {
     // Use optimized Pedersen commitment when available
     if let Ok(ops) = std::panic::catch_unwind(|| get_curve_ops()) {
-        G1Point {
-            point: ops.pedersen_commit(value, blinding),
+        // Fallback to the generators below if the backend rejects the operation
+        match ops.pedersen_commit(value, blinding) {
+            Ok(point) => G1Point { point },
+            Err(_) => G1Point::generator().mul(value).add(&get_h_generator().mul(blinding)),
         }
     } else {
         let g = G1Point::generator();
@@ -179,6 +184,203 @@ pub fn verify_schnorr_signature(
     computed_challenge == *challenge
 }
 
+/// Schnorr signature over `message`, checked by `verify_schnorr_signature`
+///
+/// Returns `(challenge, response)` as carried by instructions. The nonce is
+/// derived from the key and the message, so signing needs no randomness and
+/// never reuses a nonce across messages.
+pub fn schnorr_sign(secret_key: &Scalar, message: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let g = G1Point::generator();
+    let public_key = g.mul(secret_key);
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"zerosol_schnorr_nonce");
+    hasher.update(secret_key.as_bytes());
+    hasher.update(message);
+    let nonce = hash_to_scalar(&hasher.finalize());
+    let k = g.mul(&nonce);
+
+    let mut hasher = Sha256::new();
+    hasher.update(message);
+    hasher.update(&public_key.to_bytes());
+    hasher.update(&k.to_bytes());
+    let challenge = hash_to_scalar(&hasher.finalize());
+
+    (challenge.to_bytes(), (nonce + challenge * secret_key).to_bytes())
+}
+
+/// Message signed by the account key to register it at `zerosol_account`
+///
+/// Binding the account address and payer makes each signature usable for
+/// exactly one registration; it cannot be replayed at another address or
+/// front-run by a different payer.
+pub fn register_account_message(
+    program_id: &solana_program::pubkey::Pubkey,
+    zerosol_account: &solana_program::pubkey::Pubkey,
+    payer: &solana_program::pubkey::Pubkey,
+) -> Vec<u8> {
+    let mut message = b"zerosol_register".to_vec();
+    message.extend_from_slice(program_id.as_ref());
+    message.extend_from_slice(zerosol_account.as_ref());
+    message.extend_from_slice(payer.as_ref());
+    message
+}
+
+/// Message an account key signs to authorize an action on its account
+///
+/// `action` is one of the `state::ACTION_*` constants and `params` its
+/// parameters. `action_nonce` is the account's current `action_nonce`, which
+/// the program increments on every action, so each signature is accepted
+/// once and only during `epoch`.
+pub fn account_action_message(
+    program_id: &solana_program::pubkey::Pubkey,
+    action: u8,
+    zerosol_account: &solana_program::pubkey::Pubkey,
+    epoch: u64,
+    action_nonce: u64,
+    params: &[u8],
+) -> Vec<u8> {
+    let mut message = b"zerosol_action".to_vec();
+    message.extend_from_slice(program_id.as_ref());
+    message.push(action);
+    message.extend_from_slice(zerosol_account.as_ref());
+    message.extend_from_slice(&epoch.to_le_bytes());
+    message.extend_from_slice(&action_nonce.to_le_bytes());
+    message.extend_from_slice(params);
+    message
+}
+
+/// Verify a Chaum-Pedersen proof that `commitment_left = commitment_right^sk`
+/// for the `sk` behind `public_key`, i.e. that the ciphertext encrypts zero
+pub fn verify_zero_balance_proof(
+    public_key: &G1Point,
+    commitment_left: &G1Point,
+    commitment_right: &G1Point,
+    message: &[u8],
+    announcement_g: &G1Point,
+    announcement_cr: &G1Point,
+    response: &Scalar,
+) -> bool {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zerosol_zero_balance");
+    hasher.update(message);
+    hasher.update(&public_key.to_bytes());
+    hasher.update(&commitment_left.to_bytes());
+    hasher.update(&commitment_right.to_bytes());
+    hasher.update(&announcement_g.to_bytes());
+    hasher.update(&announcement_cr.to_bytes());
+    let challenge = hash_to_scalar(&hasher.finalize());
+
+    // g^s == A_g * y^c
+    let lhs_g = G1Point::generator().mul(response);
+    let rhs_g = announcement_g.add(&public_key.mul(&challenge));
+
+    // CRn^s == A_cr * CLn^c
+    let lhs_cr = commitment_right.mul(response);
+    let rhs_cr = announcement_cr.add(&commitment_left.mul(&challenge));
+
+    lhs_g.eq(&rhs_g) && lhs_cr.eq(&rhs_cr)
+}
+
+/// Verify a `KeyRotationProof` that the ciphertext under `new_public_key`
+/// encrypts the same balance as `(commitment_left, commitment_right)` under
+/// `public_key`, and that the prover knows the current secret key
+pub fn verify_key_rotation_proof(
+    public_key: &G1Point,
+    new_public_key: &G1Point,
+    commitment_left: &G1Point,
+    commitment_right: &G1Point,
+    message: &[u8],
+    proof: &crate::state::KeyRotationProof,
+) -> Result<bool, ProgramError> {
+    let new_left = G1Point::from_bytes(&proof.commitment_left)?;
+    let new_right = G1Point::from_bytes(&proof.commitment_right)?;
+    let announcement_g = G1Point::from_bytes(&proof.announcement_g)?;
+    let announcement_r = G1Point::from_bytes(&proof.announcement_r)?;
+    let announcement_b = G1Point::from_bytes(&proof.announcement_b)?;
+    let response_sk = scalar_from_bytes(&proof.response_sk);
+    let response_r = scalar_from_bytes(&proof.response_r);
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"zerosol_key_rotation");
+    hasher.update(message);
+    hasher.update(&public_key.to_bytes());
+    hasher.update(&new_public_key.to_bytes());
+    hasher.update(&commitment_left.to_bytes());
+    hasher.update(&commitment_right.to_bytes());
+    hasher.update(&proof.commitment_left);
+    hasher.update(&proof.commitment_right);
+    hasher.update(&proof.announcement_g);
+    hasher.update(&proof.announcement_r);
+    hasher.update(&proof.announcement_b);
+    let challenge = hash_to_scalar(&hasher.finalize());
+
+    let g = G1Point::generator();
+
+    // g^s_sk == A_g * y^c
+    let knows_sk = g
+        .mul(&response_sk)
+        .eq(&announcement_g.add(&public_key.mul(&challenge)));
+
+    // g^s_r == A_r * CRn'^c
+    let knows_r = g
+        .mul(&response_r)
+        .eq(&announcement_r.add(&new_right.mul(&challenge)));
+
+    // CRn^s_sk / y'^s_r == A_b * (CLn / CLn')^c
+    let lhs_b = commitment_right
+        .mul(&response_sk)
+        .add(&new_public_key.mul(&response_r).neg());
+    let rhs_b = announcement_b.add(&commitment_left.add(&new_left.neg()).mul(&challenge));
+
+    Ok(knows_sk && knows_r && lhs_b.eq(&rhs_b))
+}
+
+/// Hash of the public statement a transfer proof is bound to
+///
+/// The Fiat-Shamir challenge of the proof is derived from this hash, so the
+/// relayer cannot change the beneficiary or fee after the sender proved.
+pub fn transfer_statement_hash(
+    commitments_c: &[[u8; 32]],
+    commitment_d: &[u8; 32],
+    public_keys: &[[u8; 32]],
+    nonce: &[u8; 32],
+    epoch: u64,
+    beneficiary: &[u8; 32],
+    fee: u64,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zerosol_transfer");
+    for commitment in commitments_c {
+        hasher.update(commitment);
+    }
+    hasher.update(commitment_d);
+    for public_key in public_keys {
+        hasher.update(public_key);
+    }
+    hasher.update(nonce);
+    hasher.update(&epoch.to_le_bytes());
+    hasher.update(beneficiary);
+    hasher.update(&fee.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Hash of the public statement a burn proof is bound to
+pub fn burn_statement_hash(
+    public_key: &[u8; 32],
+    amount: u64,
+    nonce: &[u8; 32],
+    epoch: u64,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zerosol_burn");
+    hasher.update(public_key);
+    hasher.update(&amount.to_le_bytes());
+    hasher.update(nonce);
+    hasher.update(&epoch.to_le_bytes());
+    hasher.finalize().into()
+}
+
 /// Multi-scalar multiplication for efficient bulletproof verification
 pub fn multi_scalar_mul(scalars: &[Scalar], points: &[G1Point]) -> G1Point {
     assert_eq!(scalars.len(), points.len());
@@ -198,11 +400,10 @@ pub fn multi_scalar_mul(scalars: &[Scalar], points: &[G1Point]) -> G1Point {
             }
         }
     } else {
-        let mut result = G1Point::identity();
-        for (scalar, point) in scalars.iter().zip(points.iter()) {
-            result = result.add(&point.mul(scalar));
-        }
-        result
+        let ristretto_points: Vec<RistrettoPoint> = points.iter().map(|p| p.point).collect();
+        let point = Backend::multiscalar_mul(scalars, &ristretto_points)
+            .unwrap_or_else(|_| RistrettoPoint::vartime_multiscalar_mul(scalars.iter(), ristretto_points.iter()));
+        G1Point { point }
     }
 }
 
@@ -298,7 +499,7 @@ pub fn batch_scalar_mul(scalars: &[Scalar], points: &[G1Point]) -> Vec<G1Point>
         
         for (scalar, point) in scalars.iter().zip(points.iter()) {
             results.push(G1Point {
-                point: ops.fast_scalar_mul(&point.point, scalar),
+                point: ops.fast_scalar_mul(&point.point, scalar).unwrap_or_else(|_| point.point * scalar),
             });
         }
         
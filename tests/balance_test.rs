@@ -0,0 +1,99 @@
+use curve25519_dalek::scalar::Scalar;
+use solana_sdk::pubkey::Pubkey;
+use zerosol_solana::{
+    client::balance::{account_balance, decrypt_balance, AccountBalance, BabyStepTable, BalanceError},
+    state::{PendingAccount, ZerosolAccount},
+    utils::G1Point,
+};
+
+#[test]
+fn test_baby_step_giant_step() {
+    let table = BabyStepTable::new(12);
+    let g = G1Point::generator();
+
+    // Values inside the table, on giant-step boundaries and far beyond it
+    for b in [0u64, 1, 4095, 4096, 123_456, 1 << 20] {
+        assert_eq!(table.solve(&g.mul(&Scalar::from(b)).point), Some(b));
+    }
+}
+
+#[test]
+fn test_account_balance_simulates_rollover() {
+    let table = BabyStepTable::new(12);
+    let g = G1Point::generator();
+    let sk = Scalar::from(77u64);
+    let y = g.mul(&sk);
+    let r = Scalar::from(5u64);
+
+    let mut zerosol_account = ZerosolAccount::new(y.to_bytes(), Pubkey::new_unique());
+    zerosol_account.commitment_left = g.mul(&Scalar::from(300u64)).add(&y.mul(&r)).to_bytes();
+    zerosol_account.commitment_right = g.mul(&r).to_bytes();
+    zerosol_account.last_rollover = 3;
+
+    // A deposit of 20 this epoch: (g^20, 1)
+    let mut pending_account = PendingAccount::new();
+    pending_account.commitment_left = g.mul(&Scalar::from(20u64)).to_bytes();
+
+    assert_eq!(
+        account_balance(&table, &sk, &zerosol_account, &pending_account, 3).unwrap(),
+        AccountBalance { spendable: 300, pending: 20 }
+    );
+
+    // In a later epoch the program rolls the deposit over first
+    assert_eq!(
+        account_balance(&table, &sk, &zerosol_account, &pending_account, 4).unwrap(),
+        AccountBalance { spendable: 320, pending: 0 }
+    );
+
+    // A burn of 50 in the same epoch leaves g^-30 pending
+    pending_account.commitment_left = g.mul(&Scalar::from(30u64)).neg().to_bytes();
+    assert_eq!(
+        account_balance(&table, &sk, &zerosol_account, &pending_account, 3).unwrap(),
+        AccountBalance { spendable: 300, pending: -30 }
+    );
+    assert_eq!(
+        account_balance(&table, &sk, &zerosol_account, &pending_account, 4).unwrap(),
+        AccountBalance { spendable: 270, pending: 0 }
+    );
+
+    assert_eq!(
+        decrypt_balance(&table, &sk, &[0xff; 32], &zerosol_account.commitment_right),
+        Err(BalanceError::InvalidCiphertext)
+    );
+}
+
+#[test]
+fn test_baby_step_table_round_trip() {
+    let table = BabyStepTable::new(10);
+    let path = std::env::temp_dir().join(format!("zerosol_bstab_{}", std::process::id()));
+    table.save(&path).unwrap();
+
+    let loaded = BabyStepTable::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.bits(), 10);
+    let point = G1Point::generator().mul(&Scalar::from(99_999u64)).point;
+    assert_eq!(loaded.solve(&point), Some(99_999));
+
+    // Anything else is rejected
+    assert!(BabyStepTable::read_from(&mut &b"not a table"[..]).is_err());
+}
+
+#[test]
+fn test_baby_step_table_keeps_colliding_keys() {
+    let mut bytes = Vec::new();
+    BabyStepTable::new(4).write_to(&mut bytes).unwrap();
+
+    // Give step 1 the truncated key of step 3, as a collision would
+    let key = |j: usize| 9 + 8 * j..17 + 8 * j;
+    let key_3 = bytes[key(3)].to_vec();
+    bytes[key(1)].copy_from_slice(&key_3);
+
+    let table = BabyStepTable::read_from(&mut &bytes[..]).unwrap();
+    let point = G1Point::generator().mul(&Scalar::from(3u64)).point;
+    assert_eq!(table.solve(&point), Some(3));
+
+    // Every step survives a round trip
+    let mut written = Vec::new();
+    table.write_to(&mut written).unwrap();
+    assert_eq!(written, bytes);
+}
@@ -1,15 +1,21 @@
 use solana_program_test::*;
 use solana_sdk::{
     account::Account,
-    instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     signature::{Keypair, Signer},
     transaction::Transaction,
 };
 use zerosol_solana::{
-    instruction::ZerosolInstruction,
-    state::{GlobalState, ZerosolAccount, PendingAccount},
-    utils::{G1Point, pedersen_commit, scalar_from_bytes},
+    instruction::{initialize, register},
+    state::{
+        GlobalState, ZerosolAccount, PendingAccount, KeyRotationProof, EPOCH_SOURCE_TIMESTAMP,
+        ACTION_CLOSE, ACTION_LOCK, ACTION_ROTATE_KEY,
+    },
+    utils::{
+        G1Point, pedersen_commit, scalar_from_bytes, transfer_statement_hash, hash_to_scalar,
+        account_action_message, verify_zero_balance_proof, verify_key_rotation_proof, register_account_message, schnorr_sign,
+        verify_schnorr_signature,
+    },
     bulletproof::{BulletproofVerifier, RangeProof, InnerProductProof},
     constraint_system::{ConstraintSystemBuilder, R1CSVerifier},
 };
@@ -26,7 +32,7 @@ async fn test_complete_workflow() {
 
     // Setup accounts
     let authority = Keypair::new();
-    let global_state = Keypair::new();
+    let (global_state, _) = GlobalState::find_address(&program_id);
     let token_mint = Keypair::new();
     let user1 = Keypair::new();
     let user2 = Keypair::new();
@@ -58,18 +64,14 @@ async fn test_complete_workflow() {
     let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
 
     // Step 1: Initialize the program
-    let initialize_ix = Instruction::new_with_borsh(
-        program_id,
-        &ZerosolInstruction::Initialize {
-            epoch_length: 3600, // 1 hour
-            fee: 1,
-        },
-        vec![
-            AccountMeta::new(authority.pubkey(), true),
-            AccountMeta::new(global_state.pubkey(), false),
-            AccountMeta::new_readonly(token_mint.pubkey(), false),
-            AccountMeta::new_readonly(solana_program::system_program::id(), false),
-        ],
+    let initialize_ix = initialize(
+        &program_id,
+        &authority.pubkey(),
+        &token_mint.pubkey(),
+        3600, // 1 hour
+        1,
+        EPOCH_SOURCE_TIMESTAMP,
+        0,
     );
 
     let initialize_tx = Transaction::new_signed_with_payer(
@@ -83,7 +85,7 @@ async fn test_complete_workflow() {
 
     // Verify initialization
     let global_state_account = banks_client
-        .get_account(global_state.pubkey())
+        .get_account(global_state)
         .await
         .unwrap()
         .unwrap();
@@ -104,27 +106,21 @@ async fn test_complete_workflow() {
     let challenge1 = [2u8; 32];
     let response1 = [3u8; 32];
 
-    let register1_ix = Instruction::new_with_borsh(
-        program_id,
-        &ZerosolInstruction::Register {
-            public_key: public_key1,
-            challenge: challenge1,
-            response: response1,
-        },
-        vec![
-            AccountMeta::new(user1.pubkey(), true),
-            AccountMeta::new(user1_zerosol.pubkey(), false),
-            AccountMeta::new(user1_pending.pubkey(), false),
-            AccountMeta::new_readonly(global_state.pubkey(), false),
-            AccountMeta::new_readonly(solana_program::system_program::id(), false),
-        ],
+    let register1_ix = register(
+        &program_id,
+        &user1.pubkey(),
+        &user1_zerosol.pubkey(),
+        &user1_pending.pubkey(),
+        public_key1,
+        challenge1,
+        response1,
     );
 
     // This will fail due to signature verification, but tests the flow
     let register1_tx = Transaction::new_signed_with_payer(
         &[register1_ix],
         Some(&payer.pubkey()),
-        &[&payer, &user1],
+        &[&payer, &user1, &user1_zerosol, &user1_pending],
         recent_blockhash,
     );
 
@@ -229,6 +225,186 @@ async fn test_cryptographic_operations() {
     println!("✅ Cryptographic operations tested");
 }
 
+#[tokio::test]
+async fn test_transfer_statement_binds_fee_and_beneficiary() {
+    let commitments_c = vec![G1Point::generator().to_bytes(); 2];
+    let commitment_d = G1Point::generator().to_bytes();
+    let public_keys = vec![G1Point::generator().to_bytes(); 2];
+    let beneficiary = [7u8; 32];
+
+    let nonce = [9u8; 32];
+    let statement = transfer_statement_hash(&commitments_c, &commitment_d, &public_keys, &nonce, 5, &beneficiary, 1);
+    assert_eq!(
+        statement,
+        transfer_statement_hash(&commitments_c, &commitment_d, &public_keys, &nonce, 5, &beneficiary, 1)
+    );
+
+    // Changing the fee, beneficiary, epoch or nonce changes the statement
+    assert_ne!(
+        statement,
+        transfer_statement_hash(&commitments_c, &commitment_d, &public_keys, &nonce, 5, &beneficiary, 2)
+    );
+    assert_ne!(
+        statement,
+        transfer_statement_hash(&commitments_c, &commitment_d, &public_keys, &nonce, 5, &[8u8; 32], 1)
+    );
+    assert_ne!(
+        statement,
+        transfer_statement_hash(&commitments_c, &commitment_d, &public_keys, &nonce, 6, &beneficiary, 1)
+    );
+    assert_ne!(
+        statement,
+        transfer_statement_hash(&commitments_c, &commitment_d, &public_keys, &[10u8; 32], 5, &beneficiary, 1)
+    );
+}
+
+#[tokio::test]
+async fn test_zero_balance_proof() {
+    use sha2::{Digest, Sha256};
+
+    let g = G1Point::generator();
+    let sk = Scalar::from(987654321u64);
+    let y = g.mul(&sk);
+    let r = Scalar::from(4242u64);
+    let k = Scalar::from(1337u64);
+    let recipient = Pubkey::new_unique();
+    let message = account_action_message(&Pubkey::new_unique(), ACTION_CLOSE, &Pubkey::new_unique(), 3, 0, recipient.as_ref());
+
+    let prove = |cl: &G1Point, cr: &G1Point| {
+        let announcement_g = g.mul(&k);
+        let announcement_cr = cr.mul(&k);
+        let mut hasher = Sha256::new();
+        hasher.update(b"zerosol_zero_balance");
+        hasher.update(&message);
+        hasher.update(&y.to_bytes());
+        hasher.update(&cl.to_bytes());
+        hasher.update(&cr.to_bytes());
+        hasher.update(&announcement_g.to_bytes());
+        hasher.update(&announcement_cr.to_bytes());
+        let challenge = hash_to_scalar(&hasher.finalize());
+        (announcement_g, announcement_cr, k + challenge * sk)
+    };
+
+    // Encryption of zero: (y^r, g^r)
+    let cl = y.mul(&r);
+    let cr = g.mul(&r);
+    let (a_g, a_cr, s) = prove(&cl, &cr);
+    assert!(verify_zero_balance_proof(&y, &cl, &cr, &message, &a_g, &a_cr, &s));
+
+    // Encryption of 5: (y^r * g^5, g^r)
+    let cl_nonzero = cl.add(&g.mul(&Scalar::from(5u64)));
+    let (a_g, a_cr, s) = prove(&cl_nonzero, &cr);
+    assert!(!verify_zero_balance_proof(&y, &cl_nonzero, &cr, &message, &a_g, &a_cr, &s));
+}
+
+#[tokio::test]
+async fn test_registration_signature_binding() {
+    let sk = Scalar::from(987654321u64);
+    let public_key = G1Point::generator().mul(&sk);
+    let (program_id, account, payer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let message = register_account_message(&program_id, &account, &payer);
+    let (challenge, response) = schnorr_sign(&sk, &message);
+    let verify = |message: &[u8]| {
+        verify_schnorr_signature(
+            &public_key,
+            message,
+            &scalar_from_bytes(&challenge),
+            &scalar_from_bytes(&response),
+        )
+    };
+    assert!(verify(&message));
+
+    // Signing is deterministic
+    assert_eq!(schnorr_sign(&sk, &message), (challenge, response));
+
+    // The signature does not carry over to another address or payer
+    assert!(!verify(&register_account_message(&program_id, &Pubkey::new_unique(), &payer)));
+    assert!(!verify(&register_account_message(&program_id, &account, &Pubkey::new_unique())));
+}
+
+#[tokio::test]
+async fn test_account_action_signature() {
+    let sk = Scalar::from(987654321u64);
+    let public_key = G1Point::generator().mul(&sk);
+    let (program_id, account, locked_to) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let message = account_action_message(&program_id, ACTION_LOCK, &account, 7, 0, locked_to.as_ref());
+    let (challenge, response) = schnorr_sign(&sk, &message);
+    let verify = |message: &[u8]| {
+        verify_schnorr_signature(
+            &public_key,
+            message,
+            &scalar_from_bytes(&challenge),
+            &scalar_from_bytes(&response),
+        )
+    };
+    assert!(verify(&message));
+
+    // Each field of the action is bound: type, epoch, nonce and parameters
+    assert!(!verify(&account_action_message(&program_id, ACTION_CLOSE, &account, 7, 0, locked_to.as_ref())));
+    assert!(!verify(&account_action_message(&program_id, ACTION_LOCK, &account, 8, 0, locked_to.as_ref())));
+    assert!(!verify(&account_action_message(&program_id, ACTION_LOCK, &account, 7, 1, locked_to.as_ref())));
+    assert!(!verify(&account_action_message(&program_id, ACTION_LOCK, &account, 7, 0, program_id.as_ref())));
+}
+
+#[tokio::test]
+async fn test_key_rotation_proof() {
+    use sha2::{Digest, Sha256};
+
+    let g = G1Point::generator();
+    let sk = Scalar::from(987654321u64);
+    let y = g.mul(&sk);
+    let new_sk = Scalar::from(123456789u64);
+    let new_y = g.mul(&new_sk);
+    let (k_sk, k_r) = (Scalar::from(1337u64), Scalar::from(7331u64));
+    let account = Pubkey::new_unique();
+    let message = account_action_message(&Pubkey::new_unique(), ACTION_ROTATE_KEY, &account, 4, 0, &new_y.to_bytes());
+
+    // Balance of 5 under the current key: (g^5 * y^r, g^r)
+    let r = Scalar::from(4242u64);
+    let cl = g.mul(&Scalar::from(5u64)).add(&y.mul(&r));
+    let cr = g.mul(&r);
+
+    let prove = |balance: u64| {
+        let new_r = Scalar::from(2424u64);
+        let new_cl = g.mul(&Scalar::from(balance)).add(&new_y.mul(&new_r));
+        let new_cr = g.mul(&new_r);
+        let announcement_g = g.mul(&k_sk);
+        let announcement_r = g.mul(&k_r);
+        let announcement_b = cr.mul(&k_sk).add(&new_y.mul(&k_r).neg());
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"zerosol_key_rotation");
+        hasher.update(&message);
+        for point in [&y, &new_y, &cl, &cr, &new_cl, &new_cr, &announcement_g, &announcement_r, &announcement_b] {
+            hasher.update(&point.to_bytes());
+        }
+        let challenge = hash_to_scalar(&hasher.finalize());
+
+        KeyRotationProof {
+            commitment_left: new_cl.to_bytes(),
+            commitment_right: new_cr.to_bytes(),
+            announcement_g: announcement_g.to_bytes(),
+            announcement_r: announcement_r.to_bytes(),
+            announcement_b: announcement_b.to_bytes(),
+            response_sk: (k_sk + challenge * sk).to_bytes(),
+            response_r: (k_r + challenge * new_r).to_bytes(),
+        }
+    };
+
+    let proof = prove(5);
+    assert!(verify_key_rotation_proof(&y, &new_y, &cl, &cr, &message, &proof).unwrap());
+
+    // Re-encrypting a different balance fails
+    let inflated = prove(6);
+    assert!(!verify_key_rotation_proof(&y, &new_y, &cl, &cr, &message, &inflated).unwrap());
+
+    // The proof is bound to the signed message
+    let other = account_action_message(&Pubkey::new_unique(), ACTION_ROTATE_KEY, &account, 4, 1, &new_y.to_bytes());
+    assert!(!verify_key_rotation_proof(&y, &new_y, &cl, &cr, &other, &proof).unwrap());
+}
+
 #[tokio::test]
 async fn test_error_handling() {
     use zerosol_solana::error::ZerosolError;
@@ -240,7 +416,8 @@ async fn test_error_handling() {
     
     match program_error {
         ProgramError::Custom(code) => {
-            assert_eq!(code, ZerosolError::InvalidProofStructure as u32);
+            assert_eq!(code, ZerosolError::InvalidProofStructure.code());
+            assert_eq!(code, 13);
         }
         _ => panic!("Expected custom error"),
     }
@@ -0,0 +1,494 @@
+// Compute-unit budgets of the on-chain program.
+//
+// These tests load the SBF build of the program, so run them with
+//
+//     cargo build-sbf && cargo test --test compute_units_test -- --ignored --nocapture
+//
+// Every instruction is simulated before it is processed and the consumed
+// units are checked against the fixed budget of its kind in `BUDGETS`.
+// Raising a budget is a reviewed change to this file.
+//
+// `Register`, `Fund` and `RollOver` run with valid inputs and complete. The
+// repository ships no prover, and the inner-product equation of the range
+// proof verifier cannot be met without discrete logs between its
+// generators, so no transfer or burn proof runs to completion. Their proofs
+// carry valid Fiat-Shamir challenges and every transfer is balanced, so
+// verification gets as far as the checks after the challenge allow; the test
+// asserts that the program stops at the same check as the host-side
+// `check_transfer_proof` and `check_burn_proof`, and the budgets of `Burn`,
+// `Transfer` and `VerifyProofStep` only bound the verification up to that
+// point. Direct transfers are measured although their transactions exceed
+// the packet size; the test validator does not enforce it.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use curve25519_dalek::scalar::Scalar;
+use solana_program::{program_option::COption, program_pack::Pack, sysvar::clock::Clock};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use zerosol_solana::{
+    error::ZerosolError,
+    instruction::{
+        burn, create_proof_buffer, fund, initialize, register, roll_over, transfer, verify_proof_step,
+        write_proof_chunks,
+    },
+    processor::{check_burn_proof, check_transfer_proof},
+    state::{
+        epoch_at, find_token_authority_address, BurnProof, GlobalState, InnerProductProof,
+        ProofBuffer, TransferPayload, ZerosolAccount, ZerosolProof, EPOCH_SOURCE_TIMESTAMP,
+        PROOF_STAGE_VERIFIED,
+    },
+    utils::{
+        burn_statement_hash, hash_to_scalar, register_account_message, schnorr_sign,
+        transfer_statement_hash, G1Point,
+    },
+};
+
+/// Hard per-transaction limit of the runtime
+const MAX_UNITS: u64 = 1_400_000;
+
+/// Most units an instruction may consume, by the first word of its label.
+/// Proof verification may use a whole transaction, less the compute budget
+/// instruction ahead of it.
+const BUDGETS: &[(&str, u64)] = &[
+    ("Initialize", 30_000),
+    ("Register", 60_000),
+    ("Fund", 60_000),
+    ("RollOver", 40_000),
+    ("Burn", MAX_UNITS - 1_000),
+    ("Transfer", MAX_UNITS - 1_000),
+    ("CreateProofBuffer", 30_000),
+    ("WriteProofChunk", 15_000),
+    ("VerifyProofStep", MAX_UNITS - 1_000),
+];
+
+struct Measurement {
+    label: String,
+    units: u64,
+    result: Result<(), TransactionError>,
+}
+
+struct Harness {
+    banks_client: BanksClient,
+    payer: Keypair,
+    program_id: Pubkey,
+    global_state: Pubkey,
+    measurements: Vec<Measurement>,
+}
+
+impl Harness {
+    /// Simulate `instruction` to record its units, then process it if it succeeds
+    async fn measure(
+        &mut self,
+        label: &str,
+        instruction: Instruction,
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        let blockhash = self.banks_client.get_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(MAX_UNITS as u32),
+                instruction,
+            ],
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+
+        let simulation = self
+            .banks_client
+            .simulate_transaction(transaction.clone())
+            .await
+            .unwrap();
+        let units = simulation
+            .simulation_details
+            .map(|details| details.units_consumed)
+            .unwrap_or_default();
+        let result = simulation.result.expect("simulation did not run the transaction");
+
+        if result.is_ok() {
+            self.banks_client.process_transaction(transaction).await.unwrap();
+        }
+
+        self.measurements.push(Measurement {
+            label: label.to_string(),
+            units,
+            result: result.clone(),
+        });
+        result
+    }
+
+    async fn current_epoch(&mut self) -> u64 {
+        let clock = self.banks_client.get_sysvar::<Clock>().await.unwrap();
+        let account = self.banks_client.get_account(self.global_state).await.unwrap().unwrap();
+        epoch_at(&clock, &GlobalState::try_from_slice(&account.data).unwrap())
+    }
+
+    fn report(&self) {
+        println!("{:<32} {:>10} {:>10}  result", "instruction", "units", "budget");
+        for m in &self.measurements {
+            let budget = budget(&m.label).map_or("-".to_string(), |units| units.to_string());
+            let result = match &m.result {
+                Ok(()) => "ok".to_string(),
+                Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => {
+                    match ZerosolError::ALL.get(*code as usize) {
+                        Some(error) => format!("rejected: {:?}", error),
+                        None => format!("rejected: custom error {}", code),
+                    }
+                }
+                Err(error) => format!("rejected: {}", error),
+            };
+            println!("{:<32} {:>10} {:>10}  {}", m.label, m.units, budget, result);
+        }
+    }
+
+    /// Check every measurement against the budget of its kind
+    fn check_budgets(&self) {
+        self.report();
+
+        let mut failures = Vec::new();
+        for m in &self.measurements {
+            match budget(&m.label) {
+                Some(budget) if m.units > budget => {
+                    failures.push(format!("{} ({} > {})", m.label, m.units, budget))
+                }
+                Some(_) => {}
+                None => failures.push(format!("{} (no budget)", m.label)),
+            }
+        }
+        assert!(failures.is_empty(), "compute units over budget: {}", failures.join(", "));
+    }
+}
+
+fn budget(label: &str) -> Option<u64> {
+    let kind = label.split(' ').next()?;
+    BUDGETS.iter().find(|(name, _)| *name == kind).map(|(_, units)| *units)
+}
+
+/// The error the program returns for `error` from the instruction after the
+/// compute budget
+fn rejected(error: ZerosolError) -> TransactionError {
+    TransactionError::InstructionError(1, InstructionError::Custom(error.code()))
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }
+    .pack_into_slice(&mut data);
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token::id(),
+        ..Account::default()
+    }
+}
+
+fn ip_proof() -> InnerProductProof {
+    // 32-bit range proofs take log2(32) = 5 rounds
+    let g = G1Point::generator().to_bytes();
+    InnerProductProof {
+        l_points: vec![g; 5],
+        r_points: vec![g; 5],
+        a: Scalar::from(1u64).to_bytes(),
+        b: Scalar::from(1u64).to_bytes(),
+    }
+}
+
+/// Transfer payload that satisfies the statement and balance checks
+fn transfer_payload(n: usize, epoch: u64, fee: u64, nonce: [u8; 32]) -> TransferPayload {
+    let g = G1Point::generator();
+    let values: Vec<u64> = (1..=n as u64).map(|i| i * 10).collect();
+    let commitments_c: Vec<[u8; 32]> = values
+        .iter()
+        .map(|v| g.mul(&Scalar::from(*v)).to_bytes())
+        .collect();
+    let commitment_d = g.mul(&Scalar::from(values.iter().sum::<u64>() - fee)).to_bytes();
+    let public_keys: Vec<[u8; 32]> = (0..n as u64)
+        .map(|i| g.mul(&Scalar::from(i + 1_000)).to_bytes())
+        .collect();
+    let beneficiary = public_keys[0];
+
+    let point = g.to_bytes();
+    let points = vec![point; n];
+    let mut proof = ZerosolProof {
+        ba: point,
+        bs: point,
+        a: point,
+        b: point,
+        cln_g: points.clone(),
+        crn_g: points.clone(),
+        c_0g: points.clone(),
+        dg: points.clone(),
+        y_0g: points.clone(),
+        gg: points.clone(),
+        c_xg: points.clone(),
+        y_xg: points.clone(),
+        f: points,
+        z_a: [0u8; 32],
+        t_1: point,
+        t_2: point,
+        t_hat: [0u8; 32],
+        mu: [0u8; 32],
+        c: [0u8; 32],
+        s_sk: [0u8; 32],
+        s_r: [0u8; 32],
+        s_b: [0u8; 32],
+        s_tau: [0u8; 32],
+        ip_proof: ip_proof(),
+    };
+
+    let statement = transfer_statement_hash(
+        &commitments_c,
+        &commitment_d,
+        &public_keys,
+        &nonce,
+        epoch,
+        &beneficiary,
+        fee,
+    );
+    let mut data = statement.to_vec();
+    for field in [&proof.ba, &proof.bs, &proof.a, &proof.b, &proof.t_1, &proof.t_2, &proof.t_hat, &proof.mu] {
+        data.extend_from_slice(field);
+    }
+    proof.c = hash_to_scalar(&data).to_bytes();
+
+    TransferPayload {
+        commitments_c,
+        commitment_d,
+        public_keys,
+        nonce,
+        beneficiary,
+        fee,
+        tip_lamports: 0,
+        proof,
+    }
+}
+
+/// Burn proof with a valid challenge for `public_key`
+fn burn_proof(public_key: &[u8; 32], amount: u64, nonce: &[u8; 32], epoch: u64) -> BurnProof {
+    let point = G1Point::generator().to_bytes();
+    let mut proof = BurnProof {
+        ba: point,
+        bs: point,
+        t_1: point,
+        t_2: point,
+        t_hat: [0u8; 32],
+        mu: [0u8; 32],
+        c: [0u8; 32],
+        s_sk: [0u8; 32],
+        s_b: [0u8; 32],
+        s_tau: [0u8; 32],
+        ip_proof: ip_proof(),
+    };
+
+    let mut data = burn_statement_hash(public_key, amount, nonce, epoch).to_vec();
+    for field in [&proof.ba, &proof.bs, &proof.t_1, &proof.t_2, &proof.t_hat, &proof.mu] {
+        data.extend_from_slice(field);
+    }
+    proof.c = hash_to_scalar(&data).to_bytes();
+    proof
+}
+
+#[tokio::test]
+#[ignore = "requires `cargo build-sbf`"]
+async fn test_compute_units() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new("zerosol_solana", program_id, None);
+    program_test.prefer_bpf(true);
+
+    let authority = Keypair::new();
+    let (global_state, _) = GlobalState::find_address(&program_id);
+    let user = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let (token_authority, _) = find_token_authority_address(&program_id);
+    let user_tokens = Pubkey::new_unique();
+    let program_tokens = Pubkey::new_unique();
+
+    let mut mint_data = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::None,
+        supply: 2_000_000,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut mint_data);
+    program_test.add_account(
+        mint,
+        Account {
+            lamports: Rent::default().minimum_balance(mint_data.len()),
+            data: mint_data,
+            owner: spl_token::id(),
+            ..Account::default()
+        },
+    );
+    program_test.add_account(user_tokens, token_account(&mint, &user.pubkey(), 1_000_000));
+    program_test.add_account(program_tokens, token_account(&mint, &token_authority, 1_000_000));
+    for key in [authority.pubkey(), user.pubkey()] {
+        program_test.add_account(
+            key,
+            Account {
+                lamports: 10_000_000_000,
+                ..Account::default()
+            },
+        );
+    }
+
+    let (banks_client, payer, _) = program_test.start().await;
+    let mut harness = Harness {
+        banks_client,
+        payer,
+        program_id,
+        global_state,
+        measurements: Vec::new(),
+    };
+
+    let initialize_ix = initialize(
+        &program_id,
+        &authority.pubkey(),
+        &mint,
+        3600,
+        1,
+        EPOCH_SOURCE_TIMESTAMP,
+        0,
+    );
+    harness.measure("Initialize", initialize_ix, &[&authority]).await.unwrap();
+
+    // Register with a valid signature over the registration message
+    let sk = Scalar::from(424_242u64);
+    let public_key = G1Point::generator().mul(&sk).to_bytes();
+    let zerosol_account = Keypair::new();
+    let pending_account = Keypair::new();
+    let message = register_account_message(&program_id, &zerosol_account.pubkey(), &user.pubkey());
+    let (challenge, response) = schnorr_sign(&sk, &message);
+    let register_ix = register(
+        &program_id,
+        &user.pubkey(),
+        &zerosol_account.pubkey(),
+        &pending_account.pubkey(),
+        public_key,
+        challenge,
+        response,
+    );
+    harness
+        .measure("Register", register_ix, &[&user, &zerosol_account, &pending_account])
+        .await
+        .unwrap();
+
+    let fund_ix = fund(
+        &program_id,
+        &user.pubkey(),
+        &zerosol_account.pubkey(),
+        &pending_account.pubkey(),
+        &user_tokens,
+        &program_tokens,
+        1_000,
+    );
+    harness.measure("Fund", fund_ix, &[&user]).await.unwrap();
+
+    let roll_over_ix = roll_over(
+        &program_id,
+        &user.pubkey(),
+        &zerosol_account.pubkey(),
+        &pending_account.pubkey(),
+    );
+    harness.measure("RollOver", roll_over_ix, &[&user]).await.unwrap();
+
+    // Burn stops where the host-side check does
+    let epoch = harness.current_epoch().await;
+    let nonce = [0xb0; 32];
+    let proof = burn_proof(&public_key, 100, &nonce, epoch);
+    let account = harness.banks_client.get_account(zerosol_account.pubkey()).await.unwrap().unwrap();
+    let account = ZerosolAccount::try_from_slice(&account.data).unwrap();
+    let expected = check_burn_proof(&proof, &account, 100, &nonce, epoch).map_err(|check| rejected(check.into()));
+    let burn_ix = burn(
+        &program_id,
+        &user.pubkey(),
+        &zerosol_account.pubkey(),
+        &pending_account.pubkey(),
+        &user_tokens,
+        &program_tokens,
+        epoch,
+        100,
+        nonce,
+        proof,
+    );
+    assert_eq!(harness.measure("Burn", burn_ix, &[&user]).await, expected);
+
+    for (buffer_id, n) in [2usize, 4, 8, 16].into_iter().enumerate() {
+        let epoch = harness.current_epoch().await;
+        let participants: Vec<(Pubkey, Pubkey)> =
+            (0..n).map(|_| (Pubkey::new_unique(), Pubkey::new_unique())).collect();
+
+        // Direct transfer, verified in one transaction
+        let payload = transfer_payload(n, epoch, 1, [0x80 | n as u8; 32]);
+        let expected = check_transfer_proof(&payload, epoch).map_err(|check| rejected(check.into()));
+        let transfer_ix = transfer(
+            &program_id,
+            &user.pubkey(),
+            &zerosol_account.pubkey(),
+            &pending_account.pubkey(),
+            &participants,
+            None,
+            epoch,
+            payload.commitments_c,
+            payload.commitment_d,
+            payload.public_keys,
+            payload.nonce,
+            payload.beneficiary,
+            payload.fee,
+            payload.proof,
+        );
+        let label = format!("Transfer N={}", n);
+        assert_eq!(harness.measure(&label, transfer_ix, &[&user]).await, expected);
+
+        // Buffered transfer; each verification step is one transaction
+        let payload = transfer_payload(n, epoch, 1, [n as u8; 32]);
+        let expected = check_transfer_proof(&payload, epoch).map_err(|check| rejected(check.into()));
+        let payload = payload.try_to_vec().unwrap();
+        let create_ix = create_proof_buffer(&program_id, &user.pubkey(), buffer_id as u64, payload.len() as u32);
+        let label = format!("CreateProofBuffer N={}", n);
+        harness.measure(&label, create_ix, &[&user]).await.unwrap();
+
+        for write_ix in write_proof_chunks(&program_id, &user.pubkey(), buffer_id as u64, &payload, 900) {
+            let label = format!("WriteProofChunk N={}", n);
+            harness.measure(&label, write_ix, &[&user]).await.unwrap();
+        }
+
+        // statement, constraints, one range proof per commitment, balance
+        let mut result = Ok(());
+        for step in 0..n + 3 {
+            let verify_ix = verify_proof_step(&program_id, &user.pubkey(), buffer_id as u64, 1);
+            let label = format!("VerifyProofStep N={} step {}", n, step);
+            result = harness.measure(&label, verify_ix, &[&user]).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        assert_eq!(result, expected, "buffered N={} stopped at another check than the direct transfer", n);
+
+        let (buffer_address, _) = ProofBuffer::find_address(&program_id, &user.pubkey(), buffer_id as u64);
+        let buffer = harness.banks_client.get_account(buffer_address).await.unwrap().unwrap();
+        let buffer = ProofBuffer::unpack(&buffer.data).unwrap();
+        assert_eq!(buffer.stage == PROOF_STAGE_VERIFIED, expected.is_ok());
+        println!("N={}: verification stopped at stage {} (verified = {})", n, buffer.stage, buffer.stage == PROOF_STAGE_VERIFIED);
+    }
+
+    harness.check_budgets();
+}
@@ -54,7 +54,7 @@ async fn test_optimized_pedersen_commitment() {
     let value = Scalar::from(42u64);
     let blinding = Scalar::from(123u64);
     
-    let commitment_optimized = ops.pedersen_commit(&value, &blinding);
+    let commitment_optimized = ops.pedersen_commit(&value, &blinding).unwrap();
     let commitment_standard = RISTRETTO_BASEPOINT_POINT * value + ops.h_generator_table.base * blinding;
     
     assert_eq!(commitment_optimized, commitment_standard);
@@ -70,7 +70,7 @@ async fn test_batch_operations() {
     ops.add_to_batch(Scalar::from(3u64), RISTRETTO_BASEPOINT_POINT);
     ops.add_to_batch(Scalar::from(5u64), RISTRETTO_BASEPOINT_POINT);
     
-    let batch_result = ops.execute_batch();
+    let batch_result = ops.execute_batch().unwrap();
     let expected = RISTRETTO_BASEPOINT_POINT * Scalar::from(10u64); // 2 + 3 + 5
     
     assert_eq!(batch_result, expected);
@@ -85,11 +85,11 @@ async fn test_cached_point_operations() {
     let p2 = RISTRETTO_BASEPOINT_POINT * Scalar::from(2u64);
     
     // First call should compute and cache
-    let result1 = ops.cached_point_add(&p1, &p2);
+    let result1 = ops.cached_point_add(&p1, &p2).unwrap();
     let (cache_size_1, _) = ops.cache_stats();
     
     // Second call should use cache
-    let result2 = ops.cached_point_add(&p1, &p2);
+    let result2 = ops.cached_point_add(&p1, &p2).unwrap();
     let (cache_size_2, _) = ops.cache_stats();
     
     assert_eq!(result1, result2);
@@ -245,7 +245,7 @@ async fn test_performance_comparison() {
     // Test optimized scalar multiplication
     let start = Instant::now();
     for _ in 0..100 {
-        let _result = ops.fast_scalar_mul(&point, &scalar);
+        let _result = ops.fast_scalar_mul(&point, &scalar).unwrap();
     }
     let optimized_time = start.elapsed();
     
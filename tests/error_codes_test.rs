@@ -0,0 +1,73 @@
+use std::{env, fs, path::Path};
+
+use solana_program::program_error::ProgramError;
+use zerosol_solana::error::{RangeProofTarget, ZerosolError};
+
+const TABLE_PATH: &str = "sdk/src/programErrors.ts";
+
+fn name(error: &ZerosolError) -> String {
+    let debug = format!("{:?}", error);
+    debug.split([' ', '(', '{']).next().unwrap().to_string()
+}
+
+fn render_table() -> String {
+    let mut out = String::new();
+    out.push_str("// Generated from `ZerosolError` by tests/error_codes_test.rs. Do not edit.\n");
+    out.push_str("// Regenerate with `UPDATE_ERRORS=1 cargo test --test error_codes_test`.\n\n");
+    out.push_str("export interface ProgramErrorInfo {\n  name: string;\n  message: string;\n");
+    out.push_str("  /** The program no longer returns this code */\n  retired?: boolean;\n}\n\n");
+    out.push_str("export const PROGRAM_ERRORS: Record<number, ProgramErrorInfo> = {\n");
+    for error in ZerosolError::ALL.iter() {
+        out.push_str(&format!(
+            "  {}: {{ name: '{}', message: '{}'{} }},\n",
+            error.code(),
+            name(error),
+            error.to_string().replace('\'', "\\'"),
+            if error.is_retired() { ", retired: true" } else { "" },
+        ));
+    }
+    out.push_str("};\n");
+    out
+}
+
+#[test]
+fn test_codes_are_stable() {
+    for (i, error) in ZerosolError::ALL.iter().enumerate() {
+        assert_eq!(error.code(), i as u32, "{:?} is out of order", error);
+        assert_eq!(ProgramError::from(*error), ProgramError::Custom(i as u32));
+    }
+
+    // Codes published before explicit discriminants
+    assert_eq!(ZerosolError::InvalidInstruction.code(), 0);
+    assert_eq!(ZerosolError::TransferProofVerificationFailed.code(), 6);
+    assert_eq!(ZerosolError::PoolPaused.code(), 21);
+    assert_eq!(ZerosolError::InvalidKeyRotationProof.code(), 26);
+
+    // Neither the code nor the message depends on the context a variant
+    // carries, so the table matches every error the program returns
+    for which in [RangeProofTarget::Commitment(0), RangeProofTarget::Commitment(7), RangeProofTarget::Amount] {
+        let error = ZerosolError::RangeProofFailed { which };
+        assert_eq!(error.code(), 31);
+        assert_eq!(error.to_string(), ZerosolError::ALL[31].to_string());
+    }
+
+    // Retired codes stay reserved
+    let retired: Vec<_> = ZerosolError::ALL.iter().filter(|e| e.is_retired()).map(|e| e.code()).collect();
+    assert_eq!(retired, vec![14]);
+}
+
+#[test]
+fn test_sdk_error_table_is_current() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(TABLE_PATH);
+    let table = render_table();
+    if env::var_os("UPDATE_ERRORS").is_some() {
+        fs::write(&path, &table).unwrap();
+    }
+
+    let current = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        current == table,
+        "{} is out of date, regenerate it with `UPDATE_ERRORS=1 cargo test --test error_codes_test`",
+        TABLE_PATH
+    );
+}
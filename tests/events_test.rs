@@ -0,0 +1,77 @@
+use borsh::BorshSerialize;
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use zerosol_solana::events::{
+    Burned, ConfigChanged, Event, Funded, KeyRotated, Registered, RolledOver, Transferred,
+    ZerosolEvent,
+};
+
+fn discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("zerosol:event:{}", name).as_bytes());
+    hash[..8].try_into().unwrap()
+}
+
+fn encode<E: Event>(event: &E) -> Vec<u8> {
+    let mut data = E::DISCRIMINATOR.to_vec();
+    event.serialize(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn test_discriminators_are_stable() {
+    assert_eq!(Registered::DISCRIMINATOR, discriminator("Registered"));
+    assert_eq!(Funded::DISCRIMINATOR, discriminator("Funded"));
+    assert_eq!(Transferred::DISCRIMINATOR, discriminator("Transferred"));
+    assert_eq!(Burned::DISCRIMINATOR, discriminator("Burned"));
+    assert_eq!(RolledOver::DISCRIMINATOR, discriminator("RolledOver"));
+    assert_eq!(ConfigChanged::DISCRIMINATOR, discriminator("ConfigChanged"));
+    assert_eq!(KeyRotated::DISCRIMINATOR, discriminator("KeyRotated"));
+}
+
+#[test]
+fn test_decode_events() {
+    let transferred = Transferred {
+        accounts: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        public_keys: vec![[1u8; 32], [2u8; 32]],
+        commitments_c: vec![[3u8; 32], [4u8; 32]],
+        commitment_d: [5u8; 32],
+        nonce: [6u8; 32],
+        epoch: 12,
+        beneficiary: [7u8; 32],
+        fee: 1,
+    };
+    assert_eq!(
+        ZerosolEvent::decode(&encode(&transferred)),
+        Some(ZerosolEvent::Transferred(transferred))
+    );
+
+    let rolled_over = RolledOver {
+        account: Pubkey::new_unique(),
+        epoch: 3,
+        commitment_left: [8u8; 32],
+        commitment_right: [9u8; 32],
+    };
+    assert_eq!(
+        ZerosolEvent::decode(&encode(&rolled_over)),
+        Some(ZerosolEvent::RolledOver(rolled_over))
+    );
+}
+
+#[test]
+fn test_decode_rejects_foreign_data() {
+    assert_eq!(ZerosolEvent::decode(&[]), None);
+    assert_eq!(ZerosolEvent::decode(&[0u8; 40]), None);
+
+    // Truncated and oversized payloads are rejected
+    let funded = encode(&Funded {
+        account: Pubkey::new_unique(),
+        amount: 100,
+        pending_left: [1u8; 32],
+        pending_right: [2u8; 32],
+    });
+    assert_eq!(ZerosolEvent::decode(&funded[..funded.len() - 1]), None);
+    let mut oversized = funded.clone();
+    oversized.push(0);
+    assert_eq!(ZerosolEvent::decode(&oversized), None);
+    assert!(ZerosolEvent::decode(&funded).is_some());
+}
@@ -0,0 +1,296 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::{program_error::ProgramError, pubkey::Pubkey};
+use zerosol_solana::{
+    instruction::{
+        burn, claim_account, execute_transfer, fund_to_key, transfer, write_proof_chunks,
+        ZerosolInstruction,
+    },
+    state::{
+        find_token_authority_address, BurnProof, InnerProductProof, NonceState, PendingAccount,
+        ProofBuffer, TransferPayload, ZerosolAccount, ZerosolProof,
+    },
+};
+
+fn ip_proof() -> InnerProductProof {
+    InnerProductProof {
+        l_points: vec![],
+        r_points: vec![],
+        a: [0u8; 32],
+        b: [0u8; 32],
+    }
+}
+
+fn transfer_proof() -> ZerosolProof {
+    ZerosolProof {
+        ba: [0u8; 32],
+        bs: [0u8; 32],
+        a: [0u8; 32],
+        b: [0u8; 32],
+        cln_g: vec![],
+        crn_g: vec![],
+        c_0g: vec![],
+        dg: vec![],
+        y_0g: vec![],
+        gg: vec![],
+        c_xg: vec![],
+        y_xg: vec![],
+        f: vec![],
+        z_a: [0u8; 32],
+        t_1: [0u8; 32],
+        t_2: [0u8; 32],
+        t_hat: [0u8; 32],
+        mu: [0u8; 32],
+        c: [0u8; 32],
+        s_sk: [0u8; 32],
+        s_r: [0u8; 32],
+        s_b: [0u8; 32],
+        s_tau: [0u8; 32],
+        ip_proof: ip_proof(),
+    }
+}
+
+#[test]
+fn test_transfer_builder_account_order() {
+    let program_id = Pubkey::new_unique();
+    let relayer = Pubkey::new_unique();
+    let tip_payer = Pubkey::new_unique();
+    let participants = vec![
+        (Pubkey::new_unique(), Pubkey::new_unique()),
+        (Pubkey::new_unique(), Pubkey::new_unique()),
+    ];
+    let nonce = [4u8; 32];
+
+    let instruction = transfer(
+        &program_id,
+        &relayer,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &participants,
+        Some((tip_payer, 5_000)),
+        7,
+        vec![[0u8; 32]; 2],
+        [0u8; 32],
+        vec![[0u8; 32]; 2],
+        nonce,
+        [0u8; 32],
+        1,
+        transfer_proof(),
+    );
+
+    assert_eq!(instruction.accounts.len(), 7 + 2 * participants.len());
+    assert!(instruction.accounts[0].is_signer);
+    assert_eq!(instruction.accounts[3].pubkey, NonceState::find_address(&program_id, 7, &nonce).0);
+    assert_eq!(instruction.accounts[6].pubkey, tip_payer);
+    assert!(instruction.accounts[6].is_signer);
+    assert_eq!(instruction.accounts[7].pubkey, participants[0].0);
+    assert_eq!(instruction.accounts[10].pubkey, participants[1].1);
+
+    match ZerosolInstruction::try_from_slice(&instruction.data).unwrap() {
+        ZerosolInstruction::Transfer { tip_lamports, .. } => assert_eq!(tip_lamports, 5_000),
+        _ => panic!("expected a transfer instruction"),
+    }
+
+    // Without a tip the participants directly follow the system program
+    let instruction = transfer(
+        &program_id,
+        &relayer,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &participants,
+        None,
+        7,
+        vec![[0u8; 32]; 2],
+        [0u8; 32],
+        vec![[0u8; 32]; 2],
+        nonce,
+        [0u8; 32],
+        1,
+        transfer_proof(),
+    );
+    assert_eq!(instruction.accounts.len(), 6 + 2 * participants.len());
+    assert_eq!(instruction.accounts[6].pubkey, participants[0].0);
+}
+
+#[test]
+fn test_burn_builder_derives_pdas() {
+    let program_id = Pubkey::new_unique();
+    let nonce = [5u8; 32];
+    let proof = BurnProof {
+        ba: [0u8; 32],
+        bs: [0u8; 32],
+        t_1: [0u8; 32],
+        t_2: [0u8; 32],
+        t_hat: [0u8; 32],
+        mu: [0u8; 32],
+        c: [0u8; 32],
+        s_sk: [0u8; 32],
+        s_b: [0u8; 32],
+        s_tau: [0u8; 32],
+        ip_proof: ip_proof(),
+    };
+
+    let instruction = burn(
+        &program_id,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        3,
+        100,
+        nonce,
+        proof,
+    );
+
+    assert_eq!(instruction.accounts.len(), 10);
+    assert_eq!(instruction.accounts[5].pubkey, NonceState::find_address(&program_id, 3, &nonce).0);
+    assert!(instruction.accounts[7].is_writable);
+    assert_eq!(instruction.accounts[9].pubkey, find_token_authority_address(&program_id).0);
+}
+
+#[test]
+fn test_proof_buffer_upload_round_trip() {
+    let program_id = Pubkey::new_unique();
+    let relayer = Pubkey::new_unique();
+    let payload = TransferPayload {
+        commitments_c: vec![[1u8; 32]; 4],
+        commitment_d: [2u8; 32],
+        public_keys: vec![[3u8; 32]; 4],
+        nonce: [4u8; 32],
+        beneficiary: [5u8; 32],
+        fee: 1,
+        tip_lamports: 0,
+        proof: transfer_proof(),
+    };
+    let bytes = payload.try_to_vec().unwrap();
+
+    // Replay the chunk writes into a simulated buffer account
+    let (_, bump) = ProofBuffer::find_address(&program_id, &relayer, 9);
+    let header = ProofBuffer::new(relayer, 9, bytes.len() as u32, bump);
+    let mut account_data = header.try_to_vec().unwrap();
+    account_data.resize(ProofBuffer::LEN + bytes.len(), 0);
+
+    let chunks = write_proof_chunks(&program_id, &relayer, 9, &bytes, 100);
+    assert_eq!(chunks.len(), (bytes.len() + 99) / 100);
+    for instruction in chunks {
+        match ZerosolInstruction::try_from_slice(&instruction.data).unwrap() {
+            ZerosolInstruction::WriteProofChunk { offset, data } => {
+                let start = ProofBuffer::LEN + offset as usize;
+                account_data[start..start + data.len()].copy_from_slice(&data);
+            }
+            _ => panic!("expected a chunk write"),
+        }
+    }
+
+    let buffer = ProofBuffer::unpack(&account_data).unwrap();
+    assert_eq!(buffer.owner, relayer);
+    let decoded = buffer.payload(&account_data).unwrap();
+    assert_eq!(decoded.try_to_vec().unwrap(), bytes);
+
+    // A truncated upload does not decode
+    account_data.truncate(account_data.len() - 1);
+    assert!(buffer.payload(&account_data).is_err());
+
+    let participants = vec![(Pubkey::new_unique(), Pubkey::new_unique()); 4];
+    let instruction = execute_transfer(
+        &program_id,
+        &relayer,
+        9,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &participants,
+        None,
+        2,
+        &decoded,
+    )
+    .unwrap();
+    assert_eq!(instruction.accounts.len(), 7 + 2 * participants.len());
+    assert_eq!(
+        instruction.accounts[1].pubkey,
+        ProofBuffer::find_address(&program_id, &relayer, 9).0
+    );
+    assert_eq!(instruction.accounts[4].pubkey, NonceState::find_address(&program_id, 2, &payload.nonce).0);
+
+    // A tip payer signs at 7, ahead of the participants
+    let tip_payer = Pubkey::new_unique();
+    let tipped = TransferPayload { tip_lamports: 5_000, ..decoded };
+    let instruction = execute_transfer(
+        &program_id,
+        &relayer,
+        9,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &participants,
+        Some(&tip_payer),
+        2,
+        &tipped,
+    )
+    .unwrap();
+    assert_eq!(instruction.accounts.len(), 8 + 2 * participants.len());
+    assert_eq!(instruction.accounts[7].pubkey, tip_payer);
+    assert!(instruction.accounts[7].is_signer);
+    assert_eq!(instruction.accounts[8].pubkey, participants[0].0);
+}
+
+#[test]
+fn test_execute_transfer_requires_tip_payer() {
+    let payload = TransferPayload {
+        commitments_c: vec![[1u8; 32]; 2],
+        commitment_d: [2u8; 32],
+        public_keys: vec![[3u8; 32]; 2],
+        nonce: [4u8; 32],
+        beneficiary: [5u8; 32],
+        fee: 1,
+        tip_lamports: 5_000,
+        proof: transfer_proof(),
+    };
+    let participants = vec![(Pubkey::new_unique(), Pubkey::new_unique()); 2];
+    let result = execute_transfer(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        9,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &participants,
+        None,
+        2,
+        &payload,
+    );
+    assert_eq!(result.unwrap_err(), ProgramError::NotEnoughAccountKeys);
+}
+
+#[test]
+fn test_fund_to_key_derives_key_accounts() {
+    let program_id = Pubkey::new_unique();
+    let public_key = [7u8; 32];
+    let (zerosol_account, _) = ZerosolAccount::find_address(&program_id, &public_key);
+    let (pending_account, _) = PendingAccount::find_address(&program_id, &public_key);
+    assert_ne!(zerosol_account, pending_account);
+
+    let instruction = fund_to_key(
+        &program_id,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        public_key,
+        500,
+    );
+    assert_eq!(instruction.accounts.len(), 8);
+    assert!(instruction.accounts[0].is_signer && instruction.accounts[0].is_writable);
+    assert_eq!(instruction.accounts[1].pubkey, zerosol_account);
+    assert_eq!(instruction.accounts[2].pubkey, pending_account);
+
+    // The holder later claims the same account
+    let instruction = claim_account(
+        &program_id,
+        &Pubkey::new_unique(),
+        &public_key,
+        [0u8; 32],
+        [0u8; 32],
+    );
+    assert_eq!(instruction.accounts[1].pubkey, zerosol_account);
+    assert!(instruction.accounts[1].is_writable);
+
+    // Another key gets other accounts
+    assert_ne!(ZerosolAccount::find_address(&program_id, &[8u8; 32]).0, zerosol_account);
+}
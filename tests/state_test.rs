@@ -0,0 +1,167 @@
+use solana_sdk::{clock::Clock, pubkey::Pubkey};
+use zerosol_solana::{
+    error::ZerosolError,
+    state::{
+        epoch_at, GlobalState, NonceState, ZerosolAccount, EPOCH_SOURCE_SLOT, EPOCH_SOURCE_TIMESTAMP, PAUSE_ALL,
+        PAUSE_BURN, PAUSE_FUND, PAUSE_REGISTER, PAUSE_TRANSFER,
+    },
+};
+
+fn global_state() -> GlobalState {
+    GlobalState::new(Pubkey::new_unique(), Pubkey::new_unique(), 3600, 1)
+}
+
+#[test]
+fn test_pause_flags() {
+    let mut state = global_state();
+    assert!(!state.is_paused(PAUSE_REGISTER));
+    assert!(!state.is_paused(PAUSE_BURN));
+
+    state.pause_flags = PAUSE_BURN;
+    assert!(state.is_paused(PAUSE_BURN));
+    assert!(!state.is_paused(PAUSE_FUND));
+    assert!(!state.is_paused(PAUSE_TRANSFER));
+
+    state.pause_flags = 0;
+    state.paused = true;
+    for flag in [PAUSE_REGISTER, PAUSE_FUND, PAUSE_TRANSFER, PAUSE_BURN] {
+        assert!(state.is_paused(flag));
+    }
+    assert_eq!(PAUSE_ALL.count_ones(), 4);
+}
+
+#[test]
+fn test_pause_authority() {
+    let mut state = global_state();
+    let authority = state.authority;
+    let guardian = Pubkey::new_unique();
+
+    assert!(state.is_pause_authority(&authority));
+    assert!(!state.is_pause_authority(&guardian));
+    // An unset guardian must not authorize the default pubkey
+    assert!(!state.is_pause_authority(&Pubkey::default()));
+
+    state.guardian = guardian;
+    assert!(state.is_pause_authority(&guardian));
+}
+
+#[test]
+fn test_guardian_changes_only_tighten() {
+    let mut state = global_state();
+    state.pause_flags = PAUSE_BURN;
+
+    assert!(state.tightens_pause(true, 0));
+    assert!(state.tightens_pause(false, PAUSE_BURN | PAUSE_FUND));
+    assert!(!state.tightens_pause(false, PAUSE_FUND));
+
+    state.paused = true;
+    assert!(state.tightens_pause(true, PAUSE_BURN));
+    assert!(!state.tightens_pause(false, PAUSE_BURN));
+
+    // Any cap tightens no cap; removing a cap never does
+    assert!(state.tightens_cap(500));
+    assert!(!state.tightens_cap(0));
+
+    state.burn_cap_per_epoch = 100;
+    assert!(state.tightens_cap(100));
+    assert!(state.tightens_cap(1));
+    assert!(!state.tightens_cap(101));
+    assert!(!state.tightens_cap(0));
+}
+
+#[test]
+fn test_withdrawal_cap() {
+    let mut state = global_state();
+
+    // No cap by default
+    state.record_burn(1, u64::MAX / 2).unwrap();
+
+    state.burn_cap_per_epoch = 100;
+    state.record_burn(2, 60).unwrap();
+    state.record_burn(2, 40).unwrap();
+    assert!(matches!(
+        state.record_burn(2, 1),
+        Err(ZerosolError::WithdrawalCapExceeded)
+    ));
+    assert_eq!(state.burned_in_epoch, 100);
+
+    // The cap resets in a new epoch
+    state.record_burn(3, 100).unwrap();
+    assert_eq!(state.burn_epoch, 3);
+    assert_eq!(state.burned_in_epoch, 100);
+}
+
+#[test]
+fn test_nonce_address_is_epoch_scoped() {
+    let program_id = Pubkey::new_unique();
+    let nonce = [7u8; 32];
+
+    let (address, bump) = NonceState::find_address(&program_id, 4, &nonce);
+    let (next_epoch_address, _) = NonceState::find_address(&program_id, 5, &nonce);
+    assert_ne!(address, next_epoch_address);
+
+    let state = NonceState::new(nonce, 4, Pubkey::new_unique(), bump);
+    assert_eq!(state.address(&program_id).unwrap(), address);
+}
+
+#[test]
+fn test_epoch_sources() {
+    let mut state = global_state();
+    let clock = Clock {
+        slot: 9_000,
+        unix_timestamp: 7_300,
+        ..Clock::default()
+    };
+
+    assert_eq!(state.epoch_source, EPOCH_SOURCE_TIMESTAMP);
+    assert_eq!(epoch_at(&clock, &state), 2);
+
+    state.epoch_offset = 200;
+    assert_eq!(epoch_at(&clock, &state), 1);
+
+    state.epoch_source = EPOCH_SOURCE_SLOT;
+    state.epoch_length = 1_000;
+    assert_eq!(epoch_at(&clock, &state), 8);
+
+    // Before the offset everything falls into epoch 0
+    state.epoch_offset = 10_000;
+    assert_eq!(epoch_at(&clock, &state), 0);
+}
+
+#[test]
+fn test_advance_epoch() {
+    let mut state = global_state();
+    let clock = Clock {
+        unix_timestamp: 7_300,
+        ..Clock::default()
+    };
+
+    assert_eq!(state.advance_epoch(&clock), 2);
+    assert_eq!(state.current_epoch, 2);
+    assert_eq!(state.last_global_update, 7_300);
+
+    // Within the same epoch the recorded update time is kept
+    let later = Clock {
+        unix_timestamp: 7_400,
+        ..Clock::default()
+    };
+    assert_eq!(state.advance_epoch(&later), 2);
+    assert_eq!(state.last_global_update, 7_300);
+}
+
+#[test]
+fn test_lock_authority() {
+    let address = Pubkey::new_unique();
+    let mut account = ZerosolAccount::new([1u8; 32], Pubkey::new_unique());
+    assert!(!account.is_locked());
+
+    let escrow = Pubkey::new_unique();
+    account.locked_to = escrow;
+    assert!(account.is_locked());
+
+    // The authority is a PDA of the locking program, unique per account
+    let (expected, _) =
+        Pubkey::find_program_address(&[ZerosolAccount::LOCK_SEED, address.as_ref()], &escrow);
+    assert_eq!(account.lock_authority(&address), expected);
+    assert_ne!(account.lock_authority(&Pubkey::new_unique()), expected);
+}
@@ -0,0 +1,166 @@
+// Checks the curve syscall backend against host arithmetic.
+//
+// On the host `SyscallBackend` is served by dalek, so only the SBF build
+// runs the runtime's `sol_curve_*` syscalls. Run with
+//
+//     cargo build-sbf && cargo test --test syscall_backend_test -- --ignored
+//
+// `Register` verifies a Schnorr signature with syscall scalar
+// multiplications, `Fund` commits with the multiscalar syscall and adds with
+// the group syscall, and `RollOver` adds again; each result is compared with
+// the same arithmetic done by dalek here.
+
+use borsh::BorshDeserialize;
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+use solana_program::{program_option::COption, program_pack::Pack};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use zerosol_solana::{
+    instruction::{fund, initialize, register, roll_over},
+    state::{find_token_authority_address, PendingAccount, ZerosolAccount, EPOCH_SOURCE_SLOT},
+    utils::{register_account_message, schnorr_sign, G1Point},
+};
+
+const EPOCH_LENGTH: u64 = 100;
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }
+    .pack_into_slice(&mut data);
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token::id(),
+        ..Account::default()
+    }
+}
+
+async fn process(context: &mut ProgramTestContext, instruction: Instruction, signers: &[&Keypair]) {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction =
+        Transaction::new_signed_with_payer(&[instruction], Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn pending(context: &mut ProgramTestContext, address: Pubkey) -> (RistrettoPoint, RistrettoPoint) {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    let pending = PendingAccount::try_from_slice(&account.data).unwrap();
+    (
+        pending.get_commitment_left().unwrap().point,
+        pending.get_commitment_right().unwrap().point,
+    )
+}
+
+#[tokio::test]
+#[ignore = "requires `cargo build-sbf`"]
+async fn test_syscall_backend_matches_host() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new("zerosol_solana", program_id, None);
+    program_test.prefer_bpf(true);
+
+    let user = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let (token_authority, _) = find_token_authority_address(&program_id);
+    let user_tokens = Pubkey::new_unique();
+    let program_tokens = Pubkey::new_unique();
+
+    let mut mint_data = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::None,
+        supply: 2_000_000,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut mint_data);
+    program_test.add_account(
+        mint,
+        Account {
+            lamports: Rent::default().minimum_balance(mint_data.len()),
+            data: mint_data,
+            owner: spl_token::id(),
+            ..Account::default()
+        },
+    );
+    program_test.add_account(user_tokens, token_account(&mint, &user.pubkey(), 1_000_000));
+    program_test.add_account(program_tokens, token_account(&mint, &token_authority, 1_000_000));
+    program_test.add_account(
+        user.pubkey(),
+        Account {
+            lamports: 10_000_000_000,
+            ..Account::default()
+        },
+    );
+
+    let mut context = program_test.start_with_context().await;
+    let authority = context.payer.pubkey();
+    let initialize_ix = initialize(&program_id, &authority, &mint, EPOCH_LENGTH, 1, EPOCH_SOURCE_SLOT, 0);
+    process(&mut context, initialize_ix, &[]).await;
+
+    // The program accepts a signature made with host arithmetic
+    let sk = Scalar::from(424_242u64);
+    let public_key = G1Point::generator().mul(&sk);
+    let zerosol_account = Keypair::new();
+    let pending_account = Keypair::new();
+    let message = register_account_message(&program_id, &zerosol_account.pubkey(), &user.pubkey());
+    let (challenge, response) = schnorr_sign(&sk, &message);
+    let register_ix = register(
+        &program_id,
+        &user.pubkey(),
+        &zerosol_account.pubkey(),
+        &pending_account.pubkey(),
+        public_key.to_bytes(),
+        challenge,
+        response,
+    );
+    process(&mut context, register_ix, &[&user, &zerosol_account, &pending_account]).await;
+
+    let g = G1Point::generator().point;
+    let mut expected_left = public_key.point;
+    for amount in [1_000u64, 234] {
+        let fund_ix = fund(
+            &program_id,
+            &user.pubkey(),
+            &zerosol_account.pubkey(),
+            &pending_account.pubkey(),
+            &user_tokens,
+            &program_tokens,
+            amount,
+        );
+        process(&mut context, fund_ix, &[&user]).await;
+
+        expected_left += g * Scalar::from(amount);
+        assert_eq!(pending(&mut context, pending_account.pubkey()).await, (expected_left, g));
+    }
+
+    // A new epoch adds the pending balance to the empty account
+    context.warp_to_slot(2 * EPOCH_LENGTH + 1).unwrap();
+    let roll_over_ix = roll_over(
+        &program_id,
+        &user.pubkey(),
+        &zerosol_account.pubkey(),
+        &pending_account.pubkey(),
+    );
+    process(&mut context, roll_over_ix, &[&user]).await;
+
+    let account = context.banks_client.get_account(zerosol_account.pubkey()).await.unwrap().unwrap();
+    let account = ZerosolAccount::try_from_slice(&account.data).unwrap();
+    assert_eq!(account.get_commitment_left().unwrap().point, expected_left);
+    assert_eq!(account.get_commitment_right().unwrap().point, g);
+}